@@ -1,4 +1,27 @@
 -- down.sql
+DROP TABLE IF EXISTS audit_events CASCADE;
+DROP FUNCTION IF EXISTS audit_events_append_only();
+
+DROP TABLE IF EXISTS oidc_login_states CASCADE;
+
+DROP TABLE IF EXISTS linked_identities CASCADE;
+
+DROP TABLE IF EXISTS revoked_tokens CASCADE;
+
+DROP TABLE IF EXISTS sessions CASCADE;
+
+DROP TABLE IF EXISTS token_cleanup_runs CASCADE;
+
+DROP TABLE IF EXISTS task_reminders CASCADE;
+
+DROP TABLE IF EXISTS notification_preferences CASCADE;
+
+DROP TABLE IF EXISTS notifications CASCADE;
+
+DROP TABLE IF EXISTS email_outbox CASCADE;
+
+DROP TABLE IF EXISTS account_deletion_requests CASCADE;
+
 DROP TABLE IF EXISTS workspace_invitations CASCADE;
 
 DROP TABLE IF EXISTS attendance CASCADE;
@@ -19,12 +42,16 @@ DROP TABLE IF EXISTS worker_workspace_data CASCADE;
 
 DROP TABLE IF EXISTS problems CASCADE;
 
+DROP TABLE IF EXISTS task_attachments CASCADE;
+
 DROP TABLE IF EXISTS tasks CASCADE;
 
 DROP TABLE IF EXISTS tasks_category CASCADE;
 
 DROP TABLE IF EXISTS positions CASCADE;
 
+DROP TABLE IF EXISTS attachments CASCADE;
+
 DROP TABLE IF EXISTS workspaces CASCADE;
 
 DROP TABLE IF EXISTS user_roles CASCADE;
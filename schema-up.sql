@@ -10,7 +10,9 @@ CREATE TABLE auth_users (
     email         varchar NOT NULL UNIQUE,
     password      varchar NOT NULL,
     created_at    timestamp NOT NULL,
-    account_valid boolean NOT NULL
+    account_valid boolean NOT NULL,
+    locale        varchar(5) DEFAULT 'en' NOT NULL,
+    disabled_at   timestamp NULL
 );
 
 CREATE TABLE confirmation_tokens (
@@ -83,6 +85,25 @@ CREATE TABLE workspaces (
     name               varchar(60) NOT NULL
 );
 
+-- uploaded files, the content is in the configured storage under storage_key, see `storage`
+CREATE TABLE attachments (
+    id            serial PRIMARY KEY,
+    owner_id      integer NOT NULL REFERENCES auth_users ON UPDATE CASCADE ON DELETE CASCADE,
+    -- members of the workspace may download it
+    workspace_id  integer NULL REFERENCES workspaces ON UPDATE CASCADE ON DELETE CASCADE,
+    storage_key   varchar(100) NOT NULL UNIQUE,
+    -- a smaller JPEG rendition, images only
+    thumbnail_key varchar(100) NULL UNIQUE,
+    file_name     varchar(255) NOT NULL,
+    mime_type     varchar(100) NOT NULL,
+    size_bytes    bigint NOT NULL,
+    -- hex SHA-256 of the content
+    checksum      varchar(64) NOT NULL,
+    created_at    timestamp DEFAULT now() NOT NULL
+);
+
+CREATE INDEX attachments_owner_id_idx ON attachments (owner_id);
+
 CREATE TABLE tasks_category (
     id           serial PRIMARY KEY,
     workspace_id serial REFERENCES workspaces ON UPDATE CASCADE ON DELETE CASCADE,
@@ -90,26 +111,37 @@ CREATE TABLE tasks_category (
 );
 
 CREATE TABLE tasks (
-    id                     serial PRIMARY KEY,
-    workspace_id           serial REFERENCES workspaces ON UPDATE CASCADE ON DELETE CASCADE,
-    assigner_id            serial REFERENCES auth_users ON UPDATE CASCADE ON DELETE CASCADE,
-    worker_id              serial REFERENCES auth_users ON UPDATE CASCADE ON DELETE CASCADE,
-    description            text,
-    description_multimedia bytea,
-    assignment_date        timestamp DEFAULT now() NOT NULL,
-    due_date               timestamp,
-    status_id              serial REFERENCES status ON UPDATE CASCADE ON DELETE CASCADE,
-    title                  varchar(50) NOT NULL,
-    category_id            serial REFERENCES tasks_category ON UPDATE CASCADE ON DELETE CASCADE,
-    importance_id          serial REFERENCES importance ON UPDATE CASCADE ON DELETE CASCADE
+    id              serial PRIMARY KEY,
+    workspace_id    serial REFERENCES workspaces ON UPDATE CASCADE ON DELETE CASCADE,
+    assigner_id     serial REFERENCES auth_users ON UPDATE CASCADE ON DELETE CASCADE,
+    worker_id       serial REFERENCES auth_users ON UPDATE CASCADE ON DELETE CASCADE,
+    description     text,
+    assignment_date timestamp DEFAULT now() NOT NULL,
+    due_date        timestamp,
+    status_id       serial REFERENCES status ON UPDATE CASCADE ON DELETE CASCADE,
+    title           varchar(50) NOT NULL,
+    category_id     serial REFERENCES tasks_category ON UPDATE CASCADE ON DELETE CASCADE,
+    importance_id   serial REFERENCES importance ON UPDATE CASCADE ON DELETE CASCADE
+);
+
+-- an attachment belongs to one task at most, it goes away with the task
+CREATE TABLE task_attachments (
+    id            serial PRIMARY KEY,
+    task_id       integer NOT NULL REFERENCES tasks ON UPDATE CASCADE ON DELETE CASCADE,
+    attachment_id integer NOT NULL UNIQUE REFERENCES attachments ON UPDATE CASCADE ON DELETE CASCADE,
+    caption       varchar(500),
+    position      integer NOT NULL,
+    created_at    timestamp DEFAULT now() NOT NULL
 );
 
+CREATE INDEX task_attachments_task_id_idx ON task_attachments (task_id, position);
+
 CREATE TABLE problems (
-    id                 serial PRIMARY KEY,
-    worker_id          serial REFERENCES auth_users ON UPDATE CASCADE ON DELETE CASCADE,
-    description        text,
-    mentor_id          serial REFERENCES auth_users ON UPDATE CASCADE ON DELETE CASCADE,
-    problem_multimedia bytea
+    id            serial PRIMARY KEY,
+    worker_id     serial REFERENCES auth_users ON UPDATE CASCADE ON DELETE CASCADE,
+    description   text,
+    mentor_id     serial REFERENCES auth_users ON UPDATE CASCADE ON DELETE CASCADE,
+    attachment_id integer NULL REFERENCES attachments ON UPDATE CASCADE ON DELETE SET NULL
 );
 
 CREATE TABLE worker_workspace_data (
@@ -133,7 +165,7 @@ CREATE TABLE full_users (
     education            varchar(100),
     birth_date           date NOT NULL,
     account_bank_number  varchar(70),
-    photo                bytea
+    photo_attachment_id  integer NULL REFERENCES attachments ON UPDATE CASCADE ON DELETE SET NULL
 );
 
 CREATE TABLE workspace_users (
@@ -175,14 +207,14 @@ CREATE TABLE users_citizenships (
 );
 
 CREATE TABLE attendance (
-    id             serial PRIMARY KEY,
-    user_id        serial REFERENCES auth_users ON UPDATE CASCADE ON DELETE CASCADE,
-    date           date NOT NULL,
-    checkin        time NOT NULL,
-    checkin_photo  bytea,
-    checkout       time,
-    checkout_photo bytea,
-    workspace_id   serial REFERENCES workspaces ON UPDATE CASCADE ON DELETE CASCADE
+    id                           serial PRIMARY KEY,
+    user_id                      serial REFERENCES auth_users ON UPDATE CASCADE ON DELETE CASCADE,
+    date                         date NOT NULL,
+    checkin                      time NOT NULL,
+    checkin_photo_attachment_id  integer NULL REFERENCES attachments ON UPDATE CASCADE ON DELETE SET NULL,
+    checkout                     time,
+    checkout_photo_attachment_id integer NULL REFERENCES attachments ON UPDATE CASCADE ON DELETE SET NULL,
+    workspace_id                 serial REFERENCES workspaces ON UPDATE CASCADE ON DELETE CASCADE
 );
 
 CREATE TABLE workspace_invitations (
@@ -192,9 +224,152 @@ CREATE TABLE workspace_invitations (
     created_at   timestamp DEFAULT now() NOT NULL,
     expires_at   timestamp NOT NULL,
     confirmed_at timestamp,
-    workspace_id serial NOT NULL REFERENCES workspaces ON UPDATE CASCADE ON DELETE CASCADE
+    workspace_id serial NOT NULL REFERENCES workspaces ON UPDATE CASCADE ON DELETE CASCADE,
+    expired_at   timestamp
+);
+
+CREATE TABLE account_deletion_requests (
+    id            serial PRIMARY KEY,
+    user_email    varchar NOT NULL REFERENCES auth_users(email) ON UPDATE CASCADE ON DELETE CASCADE,
+    token         varchar NOT NULL,
+    created_at    timestamp DEFAULT now() NOT NULL,
+    expires_at    timestamp NOT NULL,
+    confirmed_at  timestamp,
+    scheduled_for timestamp,
+    cancelled_at  timestamp,
+    completed_at  timestamp
+);
+
+CREATE TABLE email_outbox (
+    id              serial PRIMARY KEY,
+    email_type      varchar(50) NOT NULL,
+    to_name         varchar NOT NULL,
+    to_email        varchar NOT NULL,
+    subject         varchar NOT NULL,
+    html_body       text NOT NULL,
+    text_body       text NOT NULL,
+    status          varchar(10) DEFAULT 'pending' NOT NULL,
+    attempts        integer DEFAULT 0 NOT NULL,
+    last_error      text,
+    next_attempt_at timestamp DEFAULT now() NOT NULL,
+    created_at      timestamp DEFAULT now() NOT NULL,
+    sent_at         timestamp
+);
+CREATE INDEX email_outbox_due_idx ON email_outbox (next_attempt_at) WHERE status = 'pending';
+
+CREATE TABLE notifications (
+    id         serial PRIMARY KEY,
+    user_id    integer NOT NULL REFERENCES auth_users ON UPDATE CASCADE ON DELETE CASCADE,
+    event_type varchar(50) NOT NULL,
+    data       jsonb DEFAULT '{}' NOT NULL,
+    read_at    timestamp,
+    created_at timestamp DEFAULT now() NOT NULL
+);
+CREATE INDEX notifications_unread_idx ON notifications (user_id) WHERE read_at IS NULL;
+
+CREATE TABLE notification_preferences (
+    user_id    integer REFERENCES auth_users ON UPDATE CASCADE ON DELETE CASCADE,
+    event_type varchar(50),
+    channel    varchar(10) NOT NULL,
+    PRIMARY KEY (user_id, event_type)
 );
 
+-- one row per reminder sent, keyed by the due date so a rescheduled task is reminded again
+CREATE TABLE task_reminders (
+    task_id  integer REFERENCES tasks ON UPDATE CASCADE ON DELETE CASCADE,
+    kind     varchar(10),
+    due_date timestamp,
+    sent_at  timestamp DEFAULT now() NOT NULL,
+    PRIMARY KEY (task_id, kind, due_date)
+);
+CREATE TABLE token_cleanup_runs (
+    id                    serial PRIMARY KEY,
+    ran_at                timestamp DEFAULT now() NOT NULL,
+    confirmation_tokens   integer NOT NULL,
+    password_reset_tokens integer NOT NULL,
+    workspace_invitations integer NOT NULL,
+    expired_invitations   integer NOT NULL,
+    revoked_tokens        integer DEFAULT 0 NOT NULL,
+    sessions              integer DEFAULT 0 NOT NULL,
+    oidc_login_states     integer DEFAULT 0 NOT NULL
+);
+
+-- devices a user is logged in on, every JWT belongs to one
+CREATE TABLE sessions (
+    id           serial PRIMARY KEY,
+    user_id      integer NOT NULL REFERENCES auth_users ON UPDATE CASCADE ON DELETE CASCADE,
+    device       varchar(100) NULL,
+    user_agent   text NULL,
+    ip           varchar(45) NULL,
+    created_at   timestamp DEFAULT now() NOT NULL,
+    last_seen_at timestamp DEFAULT now() NOT NULL,
+    expires_at   timestamp NOT NULL,
+    revoked_at   timestamp NULL
+);
+
+CREATE INDEX sessions_user_id_idx ON sessions (user_id);
+
+-- denylisted JWTs, kept until they expire
+CREATE TABLE revoked_tokens (
+    jti        varchar(36) PRIMARY KEY,
+    user_id    integer NULL REFERENCES auth_users ON UPDATE CASCADE ON DELETE CASCADE,
+    expires_at timestamp NOT NULL,
+    revoked_at timestamp DEFAULT now() NOT NULL
+);
+
+-- accounts at OpenID Connect providers users log in with
+CREATE TABLE linked_identities (
+    id            serial PRIMARY KEY,
+    user_id       integer NOT NULL REFERENCES auth_users ON UPDATE CASCADE ON DELETE CASCADE,
+    provider      varchar(50) NOT NULL,
+    subject       varchar(255) NOT NULL,
+    email         varchar(255) NULL,
+    created_at    timestamp DEFAULT now() NOT NULL,
+    last_login_at timestamp DEFAULT now() NOT NULL,
+    UNIQUE (provider, subject)
+);
+
+CREATE INDEX linked_identities_user_id_idx ON linked_identities (user_id);
+
+-- OpenID Connect logins started and not finished yet
+CREATE TABLE oidc_login_states (
+    state         varchar(64) PRIMARY KEY,
+    provider      varchar(50) NOT NULL,
+    code_verifier varchar(128) NOT NULL,
+    nonce         varchar(64) NOT NULL,
+    created_at    timestamp DEFAULT now() NOT NULL,
+    expires_at    timestamp NOT NULL
+);
+
+-- who did what, rows are never updated or deleted. Ids are not foreign keys so events
+-- outlive the users and workspaces they mention
+CREATE TABLE audit_events (
+    id           bigserial PRIMARY KEY,
+    actor_id     integer NULL,
+    action       varchar(50) NOT NULL,
+    target_type  varchar(30) NULL,
+    target_id    integer NULL,
+    workspace_id integer NULL,
+    ip           varchar(45) NULL,
+    data         jsonb DEFAULT '{}' NOT NULL,
+    created_at   timestamp DEFAULT now() NOT NULL
+);
+
+CREATE INDEX audit_events_actor_id_idx ON audit_events (actor_id);
+CREATE INDEX audit_events_target_idx ON audit_events (target_type, target_id);
+CREATE INDEX audit_events_workspace_id_idx ON audit_events (workspace_id);
+
+-- enforces the above for every database user, the application included
+CREATE FUNCTION audit_events_append_only() RETURNS trigger AS $$
+BEGIN
+    RAISE EXCEPTION 'audit_events is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER audit_events_append_only
+    BEFORE UPDATE OR DELETE OR TRUNCATE ON audit_events
+    FOR EACH STATEMENT EXECUTE FUNCTION audit_events_append_only();
+
 -- Set ownership for all tables
 ALTER TABLE roles OWNER TO postgres;
 ALTER TABLE auth_users OWNER TO postgres;
@@ -208,8 +383,10 @@ ALTER TABLE phone_dial_codes OWNER TO postgres;
 ALTER TABLE workspace_roles OWNER TO postgres;
 ALTER TABLE user_roles OWNER TO postgres;
 ALTER TABLE workspaces OWNER TO postgres;
+ALTER TABLE attachments OWNER TO postgres;
 ALTER TABLE tasks_category OWNER TO postgres;
 ALTER TABLE tasks OWNER TO postgres;
+ALTER TABLE task_attachments OWNER TO postgres;
 ALTER TABLE problems OWNER TO postgres;
 ALTER TABLE worker_workspace_data OWNER TO postgres;
 ALTER TABLE positions OWNER TO postgres;
@@ -221,6 +398,17 @@ ALTER TABLE conversation_participants OWNER TO postgres;
 ALTER TABLE users_citizenships OWNER TO postgres;
 ALTER TABLE attendance OWNER TO postgres;
 ALTER TABLE workspace_invitations OWNER TO postgres;
+ALTER TABLE account_deletion_requests OWNER TO postgres;
+ALTER TABLE email_outbox OWNER TO postgres;
+ALTER TABLE notifications OWNER TO postgres;
+ALTER TABLE notification_preferences OWNER TO postgres;
+ALTER TABLE task_reminders OWNER TO postgres;
+ALTER TABLE token_cleanup_runs OWNER TO postgres;
+ALTER TABLE revoked_tokens OWNER TO postgres;
+ALTER TABLE sessions OWNER TO postgres;
+ALTER TABLE linked_identities OWNER TO postgres;
+ALTER TABLE oidc_login_states OWNER TO postgres;
+ALTER TABLE audit_events OWNER TO postgres;
 INSERT into ev_subscriptions ( subscription) VALUES ('FREE'), ('PLUS'), ('PRO'), ('ENTERPRISE');
 INSERT into status (name) VALUES ('HELP_NEEDED'), ('TODO'), ('IN_PROGRESS'), ('COMPLETED'), ('CANCELED');
 
@@ -0,0 +1,43 @@
+-- Brings a database created before attachments to the attachments of schema-up.sql. The bytea
+-- columns stay, the server copies their files into storage on its next start and drops them,
+-- see `attachments::legacy`.
+
+CREATE TABLE IF NOT EXISTS attachments (
+    id            serial PRIMARY KEY,
+    owner_id      integer NOT NULL REFERENCES auth_users ON UPDATE CASCADE ON DELETE CASCADE,
+    -- members of the workspace may download it
+    workspace_id  integer NULL REFERENCES workspaces ON UPDATE CASCADE ON DELETE CASCADE,
+    storage_key   varchar(100) NOT NULL UNIQUE,
+    -- a smaller JPEG rendition, images only
+    thumbnail_key varchar(100) NULL UNIQUE,
+    file_name     varchar(255) NOT NULL,
+    mime_type     varchar(100) NOT NULL,
+    size_bytes    bigint NOT NULL,
+    -- hex SHA-256 of the content
+    checksum      varchar(64) NOT NULL,
+    created_at    timestamp DEFAULT now() NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS attachments_owner_id_idx ON attachments (owner_id);
+
+CREATE TABLE IF NOT EXISTS task_attachments (
+    id            serial PRIMARY KEY,
+    task_id       integer NOT NULL REFERENCES tasks ON UPDATE CASCADE ON DELETE CASCADE,
+    attachment_id integer NOT NULL UNIQUE REFERENCES attachments ON UPDATE CASCADE ON DELETE CASCADE,
+    caption       varchar(500),
+    position      integer NOT NULL,
+    created_at    timestamp DEFAULT now() NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS task_attachments_task_id_idx ON task_attachments (task_id, position);
+
+ALTER TABLE problems
+    ADD COLUMN IF NOT EXISTS attachment_id integer NULL REFERENCES attachments ON UPDATE CASCADE ON DELETE SET NULL;
+ALTER TABLE full_users
+    ADD COLUMN IF NOT EXISTS photo_attachment_id integer NULL REFERENCES attachments ON UPDATE CASCADE ON DELETE SET NULL;
+ALTER TABLE attendance
+    ADD COLUMN IF NOT EXISTS checkin_photo_attachment_id integer NULL REFERENCES attachments ON UPDATE CASCADE ON DELETE SET NULL,
+    ADD COLUMN IF NOT EXISTS checkout_photo_attachment_id integer NULL REFERENCES attachments ON UPDATE CASCADE ON DELETE SET NULL;
+
+ALTER TABLE attachments OWNER TO postgres;
+ALTER TABLE task_attachments OWNER TO postgres;
@@ -0,0 +1,264 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
+use diesel::result::{DatabaseErrorKind, Error as DieselError};
+use serde::Serialize;
+use validator::ValidationErrors;
+
+use crate::auth::auth_error::{
+    AccountDeletion, AccountVerification, AuthError, InvitationError, VerificationTokenError,
+    VerificationTokenServerError,
+};
+use crate::auth::oidc::OidcError;
+use crate::i18n::{self, keys, Locale};
+use crate::response::Response as Res;
+use crate::storage::StorageError;
+use crate::validation::{field_errors, FieldError};
+
+/// Error returned by every handler, rendered as `{ "response": { "code", "message" } }`.
+///
+/// The variants carrying a `&'static str` take a key from `i18n::keys`, it is both the
+/// stable machine-readable code and the catalog key of the localized message.
+#[derive(Debug)]
+pub enum ApiError {
+    BadRequest(&'static str),
+    Unauthorized(&'static str),
+    Forbidden(&'static str),
+    NotFound(&'static str),
+    Conflict(&'static str),
+    PayloadTooLarge(&'static str),
+    Validation(ValidationErrors),
+    Database(DieselError),
+    Token(VerificationTokenError),
+    Auth(AuthError),
+    Internal(String),
+}
+
+#[derive(Serialize)]
+pub struct ErrorBody {
+    pub code: &'static str,
+    pub message: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fields: Option<BTreeMap<String, Vec<FieldError>>>,
+}
+
+impl ApiError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            ApiError::BadRequest(code)
+            | ApiError::Unauthorized(code)
+            | ApiError::Forbidden(code)
+            | ApiError::NotFound(code)
+            | ApiError::Conflict(code)
+            | ApiError::PayloadTooLarge(code) => code,
+            ApiError::Validation(_) => keys::VALIDATION_FAILED,
+            ApiError::Database(err) => match err {
+                DieselError::NotFound => keys::NOT_FOUND,
+                DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, _) => {
+                    keys::ALREADY_EXISTS
+                }
+                DieselError::DatabaseError(DatabaseErrorKind::ForeignKeyViolation, _) => {
+                    keys::INVALID_REFERENCE
+                }
+                _ => keys::DATABASE_ERROR,
+            },
+            ApiError::Token(err) => match err {
+                VerificationTokenError::NotFound => keys::TOKEN_NOT_FOUND,
+                VerificationTokenError::Expired => keys::TOKEN_EXPIRED,
+                VerificationTokenError::TokenAlreadyExists => keys::TOKEN_ALREADY_EXISTS,
+                VerificationTokenError::Account(AccountVerification::AccountAlreadyVerified) => {
+                    keys::ACCOUNT_ALREADY_VERIFIED
+                }
+                VerificationTokenError::Password(reason) => match *reason {},
+                VerificationTokenError::Deletion(AccountDeletion::AlreadyScheduled) => {
+                    keys::ACCOUNT_DELETION_ALREADY_SCHEDULED
+                }
+                VerificationTokenError::Invitation(InvitationError::UserAlreadyInWorkspace) => {
+                    keys::USER_ALREADY_IN_WORKSPACE
+                }
+                VerificationTokenError::Invitation(InvitationError::NotInvited) => {
+                    keys::NOT_INVITED
+                }
+                VerificationTokenError::Invitation(InvitationError::Expired) => {
+                    keys::INVITATION_EXPIRED
+                }
+                VerificationTokenError::Invitation(InvitationError::NotFound) => {
+                    keys::INVITATION_NOT_FOUND
+                }
+                VerificationTokenError::ServerError(_) => keys::INTERNAL_ERROR,
+            },
+            ApiError::Auth(err) => match err {
+                AuthError::UsernameNotFound => keys::USERNAME_NOT_FOUND,
+                AuthError::EmailNotFound => keys::EMAIL_NOT_FOUND,
+                AuthError::PasswordIncorrect => keys::PASSWORD_INCORRECT,
+                AuthError::ServerError(_) => keys::INTERNAL_ERROR,
+            },
+            ApiError::Internal(_) => keys::INTERNAL_ERROR,
+        }
+    }
+
+    pub fn message(&self, locale: Locale) -> &'static str {
+        i18n::t(locale, self.code())
+    }
+
+    /// Builds the error response with messages in `locale`.
+    pub fn render(&self, locale: Locale) -> HttpResponse {
+        let fields = match self {
+            ApiError::Validation(errors) => Some(field_errors(errors, locale)),
+            _ => None,
+        };
+
+        HttpResponse::build(self.status_code()).json(Res::new(ErrorBody {
+            code: self.code(),
+            message: self.message(locale),
+            fields,
+        }))
+    }
+
+    /// Context for the logs, never sent to the client.
+    fn detail(&self) -> String {
+        match self {
+            ApiError::Internal(detail)
+            | ApiError::Auth(AuthError::ServerError(detail))
+            | ApiError::Token(VerificationTokenError::ServerError(
+                VerificationTokenServerError::Other(detail),
+            )) => detail.clone(),
+            err => format!("{:?}", err),
+        }
+    }
+
+    /// Maps Diesel `NotFound` to a domain specific code, e.g. `keys::USER_NOT_FOUND`.
+    pub fn or_not_found(code: &'static str) -> impl FnOnce(DieselError) -> ApiError {
+        move |err| match err {
+            DieselError::NotFound => ApiError::NotFound(code),
+            err => ApiError::Database(err),
+        }
+    }
+
+    /// Maps a unique constraint violation to a domain specific code, e.g. `keys::WORKSPACE_NAME_TAKEN`.
+    pub fn or_conflict(code: &'static str) -> impl FnOnce(DieselError) -> ApiError {
+        move |err| match err {
+            DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, _) => {
+                ApiError::Conflict(code)
+            }
+            err => ApiError::Database(err),
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ApiError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            ApiError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            ApiError::Forbidden(_) => StatusCode::FORBIDDEN,
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::Conflict(_) => StatusCode::CONFLICT,
+            ApiError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            ApiError::Validation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiError::Database(err) => match err {
+                DieselError::NotFound => StatusCode::NOT_FOUND,
+                DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, _) => {
+                    StatusCode::CONFLICT
+                }
+                DieselError::DatabaseError(DatabaseErrorKind::ForeignKeyViolation, _) => {
+                    StatusCode::BAD_REQUEST
+                }
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            },
+            ApiError::Token(err) => match err {
+                VerificationTokenError::NotFound | VerificationTokenError::Expired => {
+                    StatusCode::BAD_REQUEST
+                }
+                VerificationTokenError::TokenAlreadyExists
+                | VerificationTokenError::Account(_)
+                | VerificationTokenError::Deletion(_) => StatusCode::CONFLICT,
+                VerificationTokenError::Password(reason) => match *reason {},
+                VerificationTokenError::Invitation(InvitationError::UserAlreadyInWorkspace) => {
+                    StatusCode::CONFLICT
+                }
+                VerificationTokenError::Invitation(InvitationError::NotInvited) => {
+                    StatusCode::FORBIDDEN
+                }
+                VerificationTokenError::Invitation(InvitationError::Expired) => {
+                    StatusCode::BAD_REQUEST
+                }
+                VerificationTokenError::Invitation(InvitationError::NotFound) => {
+                    StatusCode::NOT_FOUND
+                }
+                VerificationTokenError::ServerError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            },
+            ApiError::Auth(err) => match err {
+                AuthError::UsernameNotFound | AuthError::EmailNotFound => StatusCode::NOT_FOUND,
+                AuthError::PasswordIncorrect => StatusCode::UNAUTHORIZED,
+                AuthError::ServerError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            },
+            ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let status = self.status_code();
+        if status.is_server_error() {
+            log::error!("{}: {}", self.code(), self.detail());
+        } else {
+            log::debug!("{}: {}", self.code(), self.detail());
+        }
+
+        self.render(Locale::default())
+    }
+}
+
+impl From<DieselError> for ApiError {
+    fn from(err: DieselError) -> Self {
+        ApiError::Database(err)
+    }
+}
+
+impl From<VerificationTokenError> for ApiError {
+    fn from(err: VerificationTokenError) -> Self {
+        ApiError::Token(err)
+    }
+}
+
+impl From<AuthError> for ApiError {
+    fn from(err: AuthError) -> Self {
+        ApiError::Auth(err)
+    }
+}
+
+impl From<OidcError> for ApiError {
+    fn from(err: OidcError) -> Self {
+        match err {
+            OidcError::UnknownProvider(_) => ApiError::NotFound(keys::OIDC_PROVIDER_NOT_FOUND),
+            OidcError::Request(_) => ApiError::Internal(err.to_string()),
+            OidcError::Rejected(_) => {
+                log::warn!("External login failed: {}", err);
+                ApiError::Unauthorized(keys::OIDC_LOGIN_FAILED)
+            }
+        }
+    }
+}
+
+impl From<StorageError> for ApiError {
+    fn from(err: StorageError) -> Self {
+        match err {
+            StorageError::NotFound(_) => ApiError::NotFound(keys::ATTACHMENT_NOT_FOUND),
+            err => ApiError::Internal(err.to_string()),
+        }
+    }
+}
+
+impl From<ValidationErrors> for ApiError {
+    fn from(err: ValidationErrors) -> Self {
+        ApiError::Validation(err)
+    }
+}
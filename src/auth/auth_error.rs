@@ -2,6 +2,7 @@
 pub enum VerificationTokenError {
     Account(AccountVerification),
     Password(PasswordReset),
+    Deletion(AccountDeletion),
     ServerError(VerificationTokenServerError),
     TokenAlreadyExists,
     Expired,
@@ -25,28 +26,30 @@ pub enum AccountVerification {
 #[derive(Debug)]
 pub enum PasswordReset {}
 
+#[derive(Debug)]
+pub enum AccountDeletion {
+    AlreadyScheduled,
+}
+
 #[derive(Debug)]
 pub enum VerificationTokenServerError {
-    SettingExpirationDateError,
     TokenInsertionError,
     DatabaseError,
-    TokenGenerationError,
-    EmailSendingError,
     Other(String),
     WorkspaceInvitationError,
 }
-#[derive(Debug)]
-pub enum JWTInvalid {
-    Expired,
-    EmailNotFound,
-    ServerError,
-}
 
 #[derive(Debug)]
 pub enum AuthError {
     UsernameNotFound,
     EmailNotFound,
     PasswordIncorrect,
-    JWTError(JWTInvalid),
     ServerError(String),
 }
+
+impl From<diesel::result::Error> for VerificationTokenError {
+    fn from(err: diesel::result::Error) -> Self {
+        log::error!("Database error while handling token: {:?}", err);
+        VerificationTokenError::ServerError(VerificationTokenServerError::DatabaseError)
+    }
+}
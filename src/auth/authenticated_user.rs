@@ -0,0 +1,106 @@
+use std::future::{ready, Ready};
+
+use actix_web::dev::Payload;
+use actix_web::http::header::AUTHORIZATION;
+use actix_web::{FromRequest, HttpRequest};
+
+use crate::api_error::ApiError;
+use crate::auth::jwt::{self, Claims};
+use crate::i18n::keys;
+use crate::DPool;
+
+/// User resolved from the `Authorization: Bearer <jwt>` header, without a database lookup.
+pub struct AuthenticatedUser {
+    pub id: i32,
+    pub email: String,
+    pub claims: Claims,
+}
+
+/// Authenticated user holding the `ADMIN` role.
+pub struct AdminUser {
+    pub id: i32,
+    pub email: String,
+}
+
+/// Authenticated user holding the `ADMIN` or the `SUPPORT` role.
+pub struct StaffUser {
+    pub id: i32,
+    pub email: String,
+}
+
+fn authenticate(req: &HttpRequest) -> Result<AuthenticatedUser, ApiError> {
+    let token = req
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or(ApiError::Unauthorized(keys::MISSING_TOKEN))?;
+
+    let pool = req.app_data::<DPool>().cloned().ok_or(ApiError::Internal(
+        "Database pool is not configured".to_string(),
+    ))?;
+
+    let claims = jwt::verify(token, pool).ok_or(ApiError::Unauthorized(keys::SESSION_INVALID))?;
+    let id = claims
+        .user_id()
+        .ok_or(ApiError::Unauthorized(keys::SESSION_INVALID))?;
+
+    Ok(AuthenticatedUser {
+        id,
+        email: claims.email.clone(),
+        claims,
+    })
+}
+
+impl FromRequest for AuthenticatedUser {
+    type Error = ApiError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(authenticate(req))
+    }
+}
+
+fn authorize_admin(req: &HttpRequest) -> Result<AdminUser, ApiError> {
+    let user = authenticate(req)?;
+
+    if !user.claims.has_role("ADMIN") {
+        return Err(ApiError::Forbidden(keys::ADMIN_REQUIRED));
+    }
+
+    Ok(AdminUser {
+        id: user.id,
+        email: user.email,
+    })
+}
+
+impl FromRequest for AdminUser {
+    type Error = ApiError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(authorize_admin(req))
+    }
+}
+
+fn authorize_staff(req: &HttpRequest) -> Result<StaffUser, ApiError> {
+    let user = authenticate(req)?;
+
+    if !user.claims.is_staff() {
+        return Err(ApiError::Forbidden(keys::STAFF_REQUIRED));
+    }
+
+    Ok(StaffUser {
+        id: user.id,
+        email: user.email,
+    })
+}
+
+impl FromRequest for StaffUser {
+    type Error = ApiError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(authorize_staff(req))
+    }
+}
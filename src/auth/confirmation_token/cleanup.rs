@@ -0,0 +1,127 @@
+use actix_web::{get, post, HttpResponse};
+use chrono::Utc;
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+
+use crate::api_error::ApiError;
+use crate::auth::AdminUser;
+use crate::models::TokenCleanupRun;
+use crate::models_insertable::NewTokenCleanupRun;
+use crate::schema::confirmation_tokens as ct_data;
+use crate::schema::confirmation_tokens::dsl as ct_table;
+use crate::schema::oidc_login_states as oidc_states_data;
+use crate::schema::oidc_login_states::dsl as oidc_states_table;
+use crate::schema::password_reset_tokens as psr_data;
+use crate::schema::password_reset_tokens::dsl as psr_table;
+use crate::schema::revoked_tokens as revoked_tokens_data;
+use crate::schema::revoked_tokens::dsl as revoked_tokens_table;
+use crate::schema::sessions as sessions_data;
+use crate::schema::sessions::dsl as sessions_table;
+use crate::schema::token_cleanup_runs as cleanup_runs_data;
+use crate::schema::token_cleanup_runs::dsl as cleanup_runs_table;
+use crate::schema::workspace_invitations as workspace_invitations_data;
+use crate::schema::workspace_invitations::dsl as workspace_invitations_table;
+use crate::{est_conn, response::Response as Res, settings, DPool};
+
+const CLEANUP_RUNS_PAGE_SIZE: i64 = 50;
+
+/// Marks invitations past their lifetime as expired and deletes tokens that expired or were
+/// used more than `tokens.retention` ago, sessions that ended as long ago and denylisted JWTs
+/// that expired, and abandoned external logins. The counts are stored for the admin report.
+pub fn purge_expired_tokens(conn: &mut PgConnection) -> Result<TokenCleanupRun, DieselError> {
+    conn.transaction(|conn| {
+        let now = Utc::now().naive_utc();
+        let retained_since = now - settings::get().tokens.retention();
+
+        let expired_invitations = diesel::update(
+            workspace_invitations_table::workspace_invitations
+                .filter(workspace_invitations_data::expired_at.is_null())
+                .filter(workspace_invitations_data::confirmed_at.is_null())
+                .filter(workspace_invitations_data::expires_at.lt(now)),
+        )
+        .set(workspace_invitations_data::expired_at.eq(now))
+        .execute(conn)?;
+
+        let workspace_invitations = diesel::delete(
+            workspace_invitations_table::workspace_invitations.filter(
+                workspace_invitations_data::expired_at
+                    .lt(retained_since)
+                    .or(workspace_invitations_data::confirmed_at.lt(retained_since)),
+            ),
+        )
+        .execute(conn)?;
+
+        let confirmation_tokens = diesel::delete(
+            ct_table::confirmation_tokens.filter(
+                ct_data::expires_at
+                    .lt(retained_since)
+                    .or(ct_data::confirmed_at.lt(retained_since)),
+            ),
+        )
+        .execute(conn)?;
+
+        let password_reset_tokens = diesel::delete(
+            psr_table::password_reset_tokens.filter(
+                psr_data::expires_at
+                    .lt(retained_since)
+                    .or(psr_data::confirmed_at.lt(retained_since)),
+            ),
+        )
+        .execute(conn)?;
+
+        let sessions = diesel::delete(
+            sessions_table::sessions.filter(
+                sessions_data::expires_at
+                    .lt(retained_since)
+                    .or(sessions_data::revoked_at.lt(retained_since)),
+            ),
+        )
+        .execute(conn)?;
+
+        // an expired JWT is rejected anyway, it does not need the denylist anymore
+        let revoked_tokens = diesel::delete(
+            revoked_tokens_table::revoked_tokens.filter(revoked_tokens_data::expires_at.lt(now)),
+        )
+        .execute(conn)?;
+
+        let oidc_login_states = diesel::delete(
+            oidc_states_table::oidc_login_states.filter(oidc_states_data::expires_at.lt(now)),
+        )
+        .execute(conn)?;
+
+        diesel::insert_into(cleanup_runs_table::token_cleanup_runs)
+            .values(NewTokenCleanupRun {
+                confirmation_tokens: confirmation_tokens as i32,
+                password_reset_tokens: password_reset_tokens as i32,
+                workspace_invitations: workspace_invitations as i32,
+                expired_invitations: expired_invitations as i32,
+                revoked_tokens: revoked_tokens as i32,
+                sessions: sessions as i32,
+                oidc_login_states: oidc_login_states as i32,
+            })
+            .returning(TokenCleanupRun::as_returning())
+            .get_result(conn)
+    })
+}
+
+/// Latest cleanup runs with the number of rows each one purged.
+#[get("/admin/tokens/cleanup")]
+pub async fn list_cleanup_runs(_admin: AdminUser, pool: DPool) -> Result<HttpResponse, ApiError> {
+    let runs = cleanup_runs_table::token_cleanup_runs
+        .select(TokenCleanupRun::as_select())
+        .order(cleanup_runs_data::ran_at.desc())
+        .limit(CLEANUP_RUNS_PAGE_SIZE)
+        .load(&mut est_conn(pool))?;
+
+    Ok(HttpResponse::Ok().json(Res::new(runs)))
+}
+
+/// Runs the cleanup now instead of waiting for the next job interval.
+#[post("/admin/tokens/cleanup")]
+pub async fn run_cleanup(admin: AdminUser, pool: DPool) -> Result<HttpResponse, ApiError> {
+    let run = purge_expired_tokens(&mut est_conn(pool))?;
+
+    log::info!("{} ran token cleanup {}", admin.email, run.id);
+
+    Ok(HttpResponse::Ok().json(Res::new(run)))
+}
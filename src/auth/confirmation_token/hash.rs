@@ -0,0 +1,41 @@
+use diesel::result::Error as DieselError;
+use diesel::{Connection, PgConnection, RunQueryDsl};
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+
+/// Hex SHA-256 of a token, only this is stored so a database leak does not expose usable links.
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// Compares `token` with a stored hash in constant time.
+pub fn token_matches(token: &str, stored_hash: &str) -> bool {
+    hash_token(token)
+        .as_bytes()
+        .ct_eq(stored_hash.as_bytes())
+        .into()
+}
+
+/// Tables whose `token` column holds a hash, rows created before hashing hold the plain UUID.
+const TOKEN_TABLES: [&str; 4] = [
+    "confirmation_tokens",
+    "password_reset_tokens",
+    "workspace_invitations",
+    "account_deletion_requests",
+];
+
+/// Replaces plain tokens left from before hashing with their hash, links already sent keep working.
+/// UUIDs contain dashes and hex hashes never do, so running it again changes nothing.
+pub fn hash_plain_tokens(conn: &mut PgConnection) -> Result<usize, DieselError> {
+    conn.transaction(|conn| {
+        TOKEN_TABLES.iter().try_fold(0, |hashed, table| {
+            let updated = diesel::sql_query(format!(
+                "UPDATE {} SET token = encode(sha256(convert_to(token, 'UTF8')), 'hex') \
+                 WHERE token LIKE '%-%'",
+                table
+            ))
+            .execute(conn)?;
+            Ok(hashed + updated)
+        })
+    })
+}
@@ -1 +1,3 @@
+pub mod cleanup;
+pub mod hash;
 pub mod token;
@@ -1,9 +1,5 @@
-use std::env;
-
+use crate::auth::confirmation_token::hash::{hash_token, token_matches};
 use crate::auth::find_user::{Find, FindData};
-use crate::constants::{
-    DOMAIN, FRONTEND_DOMAIN, JWT_EXPIRATION_TIME, SMTP, WORKSPACE_INVITATION_EXPIRATION_TIME,
-};
 use crate::models::WorkspaceUser;
 use crate::schema::auth_users as user_data;
 use crate::schema::auth_users::dsl as user_table;
@@ -15,21 +11,23 @@ use chrono::{Duration, NaiveDateTime, Utc};
 use diesel::dsl::exists;
 use diesel::prelude::OptionalExtension;
 use diesel::query_dsl::methods::FilterDsl;
-use diesel::{ExpressionMethods, RunQueryDsl};
-use dotenvy::dotenv;
-use lettre::message::{MultiPart, SinglePart};
-use lettre::transport::smtp::authentication::Credentials;
-use lettre::Transport;
+use diesel::{Connection, ExpressionMethods, PgConnection, RunQueryDsl};
+use schema::account_deletion_requests as adr_data;
+use schema::account_deletion_requests::dsl as adr_table;
 use schema::confirmation_tokens as ct_data;
 use schema::password_reset_tokens as psr_data;
 use schema::workspace_users as workspace_users_data;
 use schema::workspace_users::dsl as workspace_users_table;
 
 use crate::auth::auth_error::{
-    AccountVerification, InvitationError, VerificationTokenError, VerificationTokenServerError,
+    AccountDeletion, AccountVerification, InvitationError, VerificationTokenError,
+    VerificationTokenServerError,
 };
-use crate::emails::{email_body_generator, EmailType};
-use crate::{constants::CONFIRMATION_TOKEN_EXIPIRATION_TIME, est_conn, DPool};
+use crate::emails::mailer::OutgoingEmail;
+use crate::emails::outbox;
+use crate::emails::{render, EmailContext, EmailType};
+use crate::i18n::{self, Locale};
+use crate::{est_conn, settings, DPool};
 use crate::{models, schema};
 
 pub enum TokenEmailType {
@@ -38,12 +36,27 @@ pub enum TokenEmailType {
     PasswordReset,
     PasswordResetResend,
     WorkspaceInvitation,
+    AccountDeletion,
 }
 
 pub enum TokenType {
     AccountVerification,
     PasswordReset(String),    //str email
     WorkspaceInvitation(i32), //i32 workspace_id
+    AccountDeletion,
+}
+
+impl TokenType {
+    /// How long a new token of this type stays valid, see `tokens` in the settings.
+    pub fn lifetime(&self) -> Duration {
+        let tokens = &settings::get().tokens;
+        match self {
+            TokenType::AccountVerification => tokens.account_verification(),
+            TokenType::PasswordReset(_) => tokens.password_reset(),
+            TokenType::WorkspaceInvitation(_) => tokens.workspace_invitation(),
+            TokenType::AccountDeletion => tokens.account_deletion(),
+        }
+    }
 }
 
 pub struct Cft {
@@ -54,12 +67,6 @@ pub struct Cft {
 }
 
 pub trait ConfirmationToken {
-    async fn new(
-        email: String,
-        resend: bool,
-        token_type: TokenType,
-        pool: DPool,
-    ) -> Result<String, VerificationTokenError>;
     async fn confirm(
         token: String,
         token_type: TokenType,
@@ -76,12 +83,13 @@ pub trait ConfirmationToken {
     ) -> Result<String, VerificationTokenError>;
 }
 
-impl ConfirmationToken for Cft {
-    async fn new(
+impl Cft {
+    /// Checks the preconditions and stores a new token, returns the token to put in the email.
+    fn insert(
+        conn: &mut PgConnection,
         u_email: String,
         resend: bool,
         token_type: TokenType,
-        pool: DPool,
     ) -> Result<String, VerificationTokenError> {
         let mut token_exists: bool = false;
 
@@ -91,7 +99,7 @@ impl ConfirmationToken for Cft {
                     token_exists = diesel::select(exists(
                         ct_table::confirmation_tokens.filter(ct_data::user_email.eq(&u_email)),
                     ))
-                    .get_result::<bool>(&mut est_conn(pool.clone()))
+                    .get_result::<bool>(conn)
                     .map_err(|_| {
                         VerificationTokenError::ServerError(
                             VerificationTokenServerError::DatabaseError,
@@ -101,31 +109,52 @@ impl ConfirmationToken for Cft {
             }
             TokenType::PasswordReset(_) => {}
             TokenType::WorkspaceInvitation(_) => {}
+            TokenType::AccountDeletion => {
+                let already_scheduled = diesel::select(exists(
+                    adr_table::account_deletion_requests
+                        .filter(adr_data::user_email.eq(&u_email))
+                        .filter(adr_data::scheduled_for.is_not_null())
+                        .filter(adr_data::cancelled_at.is_null())
+                        .filter(adr_data::completed_at.is_null()),
+                ))
+                .get_result::<bool>(conn)
+                .map_err(|_| {
+                    VerificationTokenError::ServerError(VerificationTokenServerError::DatabaseError)
+                })?;
+
+                if already_scheduled {
+                    return Err(VerificationTokenError::Deletion(
+                        AccountDeletion::AlreadyScheduled,
+                    ));
+                }
+            }
         }
 
         if token_exists {
             return Err(VerificationTokenError::TokenAlreadyExists);
         }
 
+        let created_at = Utc::now().naive_utc();
         let ctoken = Cft {
             user_email: u_email.clone(),
             token: uuid::Uuid::new_v4().to_string(),
-            created_at: Utc::now().naive_utc(),
-            expires_at: Utc::now().naive_utc() + chrono::Duration::seconds(JWT_EXPIRATION_TIME),
+            created_at,
+            expires_at: created_at + token_type.lifetime(),
         };
 
         let token_to_return = ctoken.token.clone();
+        let token_hash = hash_token(&ctoken.token);
 
         match token_type {
             TokenType::AccountVerification => {
                 match diesel::insert_into(ct_table::confirmation_tokens)
                     .values((
                         ct_data::user_email.eq(ctoken.user_email),
-                        ct_data::token.eq(ctoken.token),
+                        ct_data::token.eq(&token_hash),
                         ct_data::created_at.eq(ctoken.created_at),
                         ct_data::expires_at.eq(ctoken.expires_at),
                     ))
-                    .execute(&mut est_conn(pool))
+                    .execute(conn)
                 {
                     Ok(_) => Ok(token_to_return),
                     Err(_) => Err(VerificationTokenError::ServerError(
@@ -137,11 +166,11 @@ impl ConfirmationToken for Cft {
                 match diesel::insert_into(psr_table::password_reset_tokens)
                     .values((
                         psr_data::user_email.eq(ctoken.user_email),
-                        psr_data::token.eq(ctoken.token),
+                        psr_data::token.eq(&token_hash),
                         psr_data::created_at.eq(ctoken.created_at),
                         psr_data::expires_at.eq(ctoken.expires_at),
                     ))
-                    .execute(&mut est_conn(pool))
+                    .execute(conn)
                 {
                     Ok(_) => Ok(token_to_return),
                     Err(_) => Err(VerificationTokenError::ServerError(
@@ -153,25 +182,43 @@ impl ConfirmationToken for Cft {
                 match diesel::insert_into(workspace_invitations_table::workspace_invitations)
                     .values((
                         workspace_invitations_data::user_email.eq(ctoken.user_email),
-                        workspace_invitations_data::token.eq(ctoken.token),
+                        workspace_invitations_data::token.eq(&token_hash),
                         workspace_invitations_data::created_at.eq(ctoken.created_at),
                         workspace_invitations_data::expires_at.eq(ctoken.expires_at),
                         workspace_invitations_data::workspace_id.eq(workspace_id),
                     ))
-                    .execute(&mut est_conn(pool))
+                    .execute(conn)
                 {
                     Ok(_) => Ok(token_to_return),
                     Err(e) => {
-                        eprintln!("error in inviting to workspace: {:?}", e);
+                        log::error!("Error inviting to workspace: {:?}", e);
                         Err(VerificationTokenError::ServerError(
                             VerificationTokenServerError::WorkspaceInvitationError,
                         ))
                     }
                 }
             }
+            TokenType::AccountDeletion => {
+                match diesel::insert_into(adr_table::account_deletion_requests)
+                    .values((
+                        adr_data::user_email.eq(ctoken.user_email),
+                        adr_data::token.eq(&token_hash),
+                        adr_data::created_at.eq(ctoken.created_at),
+                        adr_data::expires_at.eq(ctoken.expires_at),
+                    ))
+                    .execute(conn)
+                {
+                    Ok(_) => Ok(token_to_return),
+                    Err(_) => Err(VerificationTokenError::ServerError(
+                        VerificationTokenServerError::TokenInsertionError,
+                    )),
+                }
+            }
         }
     }
+}
 
+impl ConfirmationToken for Cft {
     async fn confirm(
         _token: String,
         token_type: TokenType,
@@ -183,28 +230,49 @@ impl ConfirmationToken for Cft {
             Confirmation(models::ConfirmationToken),
             PasswordReset(models::PasswordResetTokens, String),
             WorkspaceInvitation(models::WorkspaceInvitation),
+            AccountDeletion(models::AccountDeletionRequest),
         }
 
         type Token = Result<Option<UnifiedToken>, diesel::result::Error>;
 
+        // rows are looked up by hash, the comparison is repeated in constant time
+        let token_hash = hash_token(&_token);
+
         let db_token: Token = match token_type {
             TokenType::AccountVerification => ct_table::confirmation_tokens
-                .filter(ct_data::token.eq(&_token))
+                .filter(ct_data::token.eq(&token_hash))
                 .first::<models::ConfirmationToken>(&mut conn)
                 .optional()
-                .map(|opt| opt.map(|ct| UnifiedToken::Confirmation(ct))),
+                .map(|opt| {
+                    opt.filter(|ct| token_matches(&_token, &ct.token))
+                        .map(UnifiedToken::Confirmation)
+                }),
             TokenType::PasswordReset(mail) => psr_table::password_reset_tokens
-                .filter(psr_data::token.eq(&_token))
+                .filter(psr_data::token.eq(&token_hash))
                 .first::<models::PasswordResetTokens>(&mut conn)
                 .optional()
-                .map(|opt| opt.map(|ct| UnifiedToken::PasswordReset(ct, mail))),
+                .map(|opt| {
+                    opt.filter(|ct| token_matches(&_token, &ct.token))
+                        .map(|ct| UnifiedToken::PasswordReset(ct, mail))
+                }),
             TokenType::WorkspaceInvitation(mail) => {
                 workspace_invitations_table::workspace_invitations
-                    .filter(workspace_invitations_data::token.eq(&_token))
+                    .filter(workspace_invitations_data::token.eq(&token_hash))
                     .first::<models::WorkspaceInvitation>(&mut conn)
                     .optional()
-                    .map(|opt| opt.map(|ct| UnifiedToken::WorkspaceInvitation(ct)))
+                    .map(|opt| {
+                        opt.filter(|ct| token_matches(&_token, &ct.token))
+                            .map(UnifiedToken::WorkspaceInvitation)
+                    })
             }
+            TokenType::AccountDeletion => adr_table::account_deletion_requests
+                .filter(adr_data::token.eq(&token_hash))
+                .first::<models::AccountDeletionRequest>(&mut conn)
+                .optional()
+                .map(|opt| {
+                    opt.filter(|ct| token_matches(&_token, &ct.token))
+                        .map(UnifiedToken::AccountDeletion)
+                }),
         };
 
         match db_token {
@@ -213,7 +281,7 @@ impl ConfirmationToken for Cft {
                 //     .filter(workspace_invitations_data::workspace_id.eq(tok.workspace_id))
                 //     .load::<models::WorkspaceInvitation>(&mut conn)
                 //     .map_err(|e| {
-                //         eprintln!("Error loading workspace invitations: {:?}", e);
+                //         log::error!("Error loading workspace invitations: {:?}", e);
                 //         VerificationTokenError::ServerError(
                 //             VerificationTokenServerError::DatabaseError,
                 //         )
@@ -233,7 +301,7 @@ impl ConfirmationToken for Cft {
                     {
                         Ok(user) => user,
                         Err(e) => {
-                            eprintln!("Error finding user: {:?}", e);
+                            log::error!("Error finding user: {:?}", e);
                             return Err(VerificationTokenError::ServerError(
                                 VerificationTokenServerError::DatabaseError,
                             ));
@@ -247,7 +315,7 @@ impl ConfirmationToken for Cft {
                 // {
                 //     Ok(workspace) => workspace,
                 //     Err(e) => {
-                //         eprintln!("Error loading workspace: {:?}", e);
+                //         log::error!("Error loading workspace: {:?}", e);
                 //         return Err(VerificationTokenError::ServerError(
                 //             VerificationTokenServerError::DatabaseError,
                 //         ));
@@ -262,7 +330,7 @@ impl ConfirmationToken for Cft {
                 {
                     Ok(workspace_users) => workspace_users,
                     Err(e) => {
-                        eprintln!("Error loading workspace users: {:?}", e);
+                        log::error!("Error loading workspace users: {:?}", e);
                         return Err(VerificationTokenError::ServerError(
                             VerificationTokenServerError::DatabaseError,
                         ));
@@ -275,10 +343,7 @@ impl ConfirmationToken for Cft {
                     ));
                 }
 
-                let current_time = Utc::now().naive_utc();
-                if current_time - Duration::seconds(WORKSPACE_INVITATION_EXPIRATION_TIME)
-                    > tok.created_at
-                {
+                if Utc::now().naive_utc() > tok.expires_at {
                     return Err(VerificationTokenError::Expired);
                 }
 
@@ -290,10 +355,7 @@ impl ConfirmationToken for Cft {
                         AccountVerification::AccountAlreadyVerified,
                     ));
                 }
-                let current_time = Utc::now().naive_utc();
-                if current_time - Duration::seconds(CONFIRMATION_TOKEN_EXIPIRATION_TIME)
-                    > tok.created_at
-                {
+                if Utc::now().naive_utc() > tok.expires_at {
                     Err(VerificationTokenError::Expired)
                 } else {
                     match diesel::update(
@@ -304,14 +366,14 @@ impl ConfirmationToken for Cft {
                     {
                         Ok(_) => {
                             match diesel::update(
-                                user_table::auth_users.filter(user_data::account_valid.eq(false)),
+                                user_table::auth_users.filter(user_data::email.eq(&tok.user_email)),
                             )
                             .set(user_data::account_valid.eq(true))
                             .execute(&mut conn)
                             {
                                 Ok(_) => Ok("Verificated account".to_string()),
                                 Err(e) => {
-                                    eprintln!("Error updating token verified status: {:?}", e);
+                                    log::error!("Error updating token verified status: {:?}", e);
                                     Err(VerificationTokenError::ServerError(
                                         VerificationTokenServerError::DatabaseError,
                                     ))
@@ -319,7 +381,7 @@ impl ConfirmationToken for Cft {
                             }
                         }
                         Err(e) => {
-                            eprintln!("Error updating token verified status: {:?}", e);
+                            log::error!("Error updating token verified status: {:?}", e);
                             Err(VerificationTokenError::ServerError(
                                 VerificationTokenServerError::DatabaseError,
                             ))
@@ -331,17 +393,14 @@ impl ConfirmationToken for Cft {
                 if tok.user_email != mail {
                     return Err(VerificationTokenError::NotFound);
                 }
-                let current_time = Utc::now().naive_utc();
-                if current_time - Duration::seconds(CONFIRMATION_TOKEN_EXIPIRATION_TIME)
-                    > tok.created_at
-                {
+                if Utc::now().naive_utc() > tok.expires_at {
                     return Err(VerificationTokenError::Expired);
                 }
                 match diesel::delete(psr_table::password_reset_tokens)
                     .filter(psr_data::token.eq(tok.token))
                     .execute(&mut conn)
                     .map_err(|e| {
-                        eprintln!("Error deleting used token: {e}");
+                        log::error!("Error deleting used token: {e}");
                         VerificationTokenError::ServerError(
                             VerificationTokenServerError::DatabaseError,
                         )
@@ -354,9 +413,41 @@ impl ConfirmationToken for Cft {
                     }
                 };
             }
+            Ok(Some(UnifiedToken::AccountDeletion(tok))) => {
+                if tok.cancelled_at.is_some() || tok.completed_at.is_some() {
+                    return Err(VerificationTokenError::NotFound);
+                }
+                if tok.confirmed_at.is_some() {
+                    return Err(VerificationTokenError::Deletion(
+                        AccountDeletion::AlreadyScheduled,
+                    ));
+                }
+                let current_time = Utc::now().naive_utc();
+                if current_time > tok.expires_at {
+                    return Err(VerificationTokenError::Expired);
+                }
+                match diesel::update(
+                    adr_table::account_deletion_requests.filter(adr_data::id.eq(tok.id)),
+                )
+                .set((
+                    adr_data::confirmed_at.eq(current_time),
+                    adr_data::scheduled_for
+                        .eq(current_time + settings::get().tokens.account_deletion_grace_period()),
+                ))
+                .execute(&mut conn)
+                {
+                    Ok(_) => Ok("Account deletion scheduled".to_string()),
+                    Err(e) => {
+                        log::error!("Error scheduling account deletion: {:?}", e);
+                        Err(VerificationTokenError::ServerError(
+                            VerificationTokenServerError::DatabaseError,
+                        ))
+                    }
+                }
+            }
             Ok(None) => Err(VerificationTokenError::NotFound),
             Err(e) => {
-                eprintln!("Database error while checking token: {:?}", e);
+                log::error!("Database error while checking token: {:?}", e);
                 Err(VerificationTokenError::ServerError(
                     VerificationTokenServerError::DatabaseError,
                 ))
@@ -373,92 +464,73 @@ impl ConfirmationToken for Cft {
         _resend: bool,
         _token_type: TokenType,
     ) -> Result<String, VerificationTokenError> {
-        dotenv().ok();
-        let google_smtp_password = env::var("AUTH_EMAIL_PASSWORD")
-            .expect("google smtp password needs to be set")
-            .to_string();
-
-        let google_smtp_name = env::var("AUTH_EMAIL_NAME")
-            .expect("google smtp name needs to be set")
-            .to_string();
-
-        let token = match _token {
-            Some(tok) => tok,
-            None => match Self::new(_u_email.clone(), _resend, _token_type, _pool).await {
-                Ok(tok) => tok,
-                Err(e) => {
-                    eprintln!("generating token in sending erro {:?}", e);
-                    return Err(VerificationTokenError::ServerError(
-                        VerificationTokenServerError::TokenGenerationError,
-                    ));
-                }
-            },
-        };
+        let locale = <FindData as Find>::find_auth_user_by_email(_u_email.clone(), _pool.clone())
+            .await
+            .ok()
+            .and_then(|user| Locale::from_tag(&user.locale))
+            .unwrap_or_default();
+        let expires_in = i18n::duration(locale, _token_type.lifetime());
+
+        let frontend_url = &settings::get().frontend_url;
+
+        // the token and its email are committed together, the outbox worker delivers it later
+        est_conn(_pool).transaction(|conn| {
+            let token = match _token {
+                Some(tok) => tok,
+                None => Self::insert(conn, _u_email.clone(), _resend, _token_type)?,
+            };
+
+            let (email_type, link) = match _email_type {
+                TokenEmailType::AccountVerification => (
+                    EmailType::AccountVerification,
+                    format!("{}/auth/validate/account/{}", frontend_url, token),
+                ),
+                TokenEmailType::AccountVerificationResend => (
+                    EmailType::AccountVerificationResend,
+                    format!("{}/auth/validate/account/{}", frontend_url, token),
+                ),
+                TokenEmailType::PasswordReset => (
+                    EmailType::ChangePassword,
+                    format!("{}/auth/reset/password/{}", frontend_url, token),
+                ),
+                TokenEmailType::PasswordResetResend => (
+                    EmailType::ChangePasswordResend,
+                    format!("{}/auth/reset/password/{}", frontend_url, token),
+                ),
+                TokenEmailType::WorkspaceInvitation => (
+                    EmailType::WorkspaceInvitation,
+                    format!("{}/invitation/{}", frontend_url, token),
+                ),
+                TokenEmailType::AccountDeletion => (
+                    EmailType::AccountDeletion,
+                    format!("{}/account/delete/{}", frontend_url, token),
+                ),
+            };
+
+            let rendered =
+                render(
+                    email_type,
+                    locale,
+                    &EmailContext {
+                        username: _username.clone(),
+                        link,
+                        expires_in,
+                    },
+                )
+                .map_err(|e| {
+                    VerificationTokenError::ServerError(VerificationTokenServerError::Other(
+                        format!("rendering {} email failed: {:?}", email_type.name(), e),
+                    ))
+                })?;
 
-        let email_body = match _email_type {
-            TokenEmailType::AccountVerification => {
-                email_body_generator(EmailType::AccountVerification(
-                    _username.clone(),
-                    format!("{}/auth/validate/account/{}", FRONTEND_DOMAIN, token),
-                ))
-            }
-            TokenEmailType::AccountVerificationResend => {
-                email_body_generator(EmailType::AccountVerificationResend(
-                    _username.clone(),
-                    format!("{}/auth/validate/account/{}", FRONTEND_DOMAIN, token),
-                ))
-            }
-            TokenEmailType::PasswordReset => email_body_generator(EmailType::ChangePassword(
-                _username.clone(),
-                format!("{}/auth/reset/password/{}", FRONTEND_DOMAIN, token),
-            )),
-            TokenEmailType::PasswordResetResend => {
-                todo!()
-            }
-            TokenEmailType::WorkspaceInvitation => {
-                email_body_generator(EmailType::WorkspaceInvitation(
-                    _username.clone(),
-                    format!("{}/invitation/{}", FRONTEND_DOMAIN, token),
-                ))
-            }
-        };
+            let email = OutgoingEmail {
+                to_name: _username,
+                to_email: _u_email,
+                content: rendered,
+            };
 
-        let email = lettre::Message::builder()
-            .from(
-                format!("Electro-Vision <{}>", google_smtp_name)
-                    .parse()
-                    .unwrap(),
-            )
-            .to(format!("{} <{}>", _username, _u_email).parse().unwrap())
-            .subject(match _email_type {
-                TokenEmailType::AccountVerification | TokenEmailType::AccountVerificationResend => {
-                    "Electro-Vision account verification"
-                }
-                TokenEmailType::PasswordReset | TokenEmailType::PasswordResetResend => {
-                    "Electro-Vision password reset"
-                }
-                TokenEmailType::WorkspaceInvitation => "Electro-Vision workspace invitation",
-            })
-            .multipart(
-                MultiPart::alternative().singlepart(SinglePart::html(email_body.to_string())),
-            )
-            .unwrap();
-
-        let creds = Credentials::new(google_smtp_name, google_smtp_password);
-
-        let mailer = lettre::SmtpTransport::relay(SMTP)
-            .unwrap()
-            .credentials(creds)
-            .build();
-
-        match mailer.send(&email) {
-            Ok(_) => Ok("Email sent successfully".to_string()),
-            Err(e) => {
-                eprintln!("{:?}", e);
-                Err(VerificationTokenError::ServerError(
-                    VerificationTokenServerError::EmailSendingError,
-                ))
-            }
-        }
+            outbox::enqueue(conn, email_type, &email)?;
+            Ok("Email queued".to_string())
+        })
     }
 }
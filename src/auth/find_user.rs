@@ -11,7 +11,6 @@ use schema::workspaces::dsl as workspaces_table;
 pub struct FindData {}
 
 pub trait Find {
-    async fn exists_by_email(email: String, pool: DPool) -> Result<bool, DieselError>;
     async fn find_auth_user_by_email(email: String, pool: DPool) -> Result<User, DieselError>;
     async fn find_workspace_by_owner_email(
         email: String,
@@ -52,7 +51,7 @@ impl Find for FindData {
         {
             Ok(workspace) => workspace,
             Err(e) => {
-                eprintln!("err find: {:?}", e);
+                log::error!("Error loading owned workspaces: {:?}", e);
                 return Err(e);
             }
         };
@@ -60,25 +59,6 @@ impl Find for FindData {
         Ok(workspaces)
     }
 
-    async fn exists_by_email(_email: String, pool: DPool) -> Result<bool, DieselError> {
-        use schema::auth_users::dsl::*;
-        let conn = &mut est_conn(pool.clone());
-        let is_found = diesel::select(diesel::dsl::exists(
-            auth_users.select(email).filter(email.eq(_email.clone())),
-        ))
-        .get_result::<bool>(conn);
-        match is_found {
-            Ok(true) => {
-                println!("User found.");
-                Ok(true)
-            }
-            Ok(false) => {
-                println!("User not found.");
-                Ok(false)
-            }
-            Err(e) => Err(e),
-        }
-    }
     async fn find_auth_user_by_email(_email: String, pool: DPool) -> Result<User, DieselError> {
         let conn = &mut est_conn(pool.clone());
         let user_data = users_table::auth_users
@@ -1,8 +1,42 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Claims {
+    /// User id.
+    pub sub: String,
+    pub iss: String,
+    pub aud: String,
     pub iat: usize,
     pub exp: usize,
+    /// Unique per token, revoked tokens are denylisted by it.
+    pub jti: String,
+    /// Id of the session the token belongs to.
+    pub sid: i32,
     pub email: String,
+    /// Global roles from `user_roles`.
+    pub roles: Vec<String>,
+    /// Workspaces the user belonged to when the token was issued. Permission checks still
+    /// read `workspace_users`, a membership can change before the token expires.
+    pub workspaces: Vec<WorkspaceMembership>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceMembership {
+    pub id: i32,
+    pub role: String,
+}
+
+impl Claims {
+    pub fn user_id(&self) -> Option<i32> {
+        self.sub.parse().ok()
+    }
+
+    pub fn has_role(&self, role: &str) -> bool {
+        self.roles.iter().any(|r| r == role)
+    }
+
+    /// Holds the `ADMIN` or the `SUPPORT` role.
+    pub fn is_staff(&self) -> bool {
+        self.has_role("ADMIN") || self.has_role("SUPPORT")
+    }
 }
@@ -1,14 +1,22 @@
-use jsonwebtoken::{decode, errors::Error, Algorithm, DecodingKey, TokenData, Validation};
+use jsonwebtoken::errors::{Error, ErrorKind};
+use jsonwebtoken::{decode, decode_header, TokenData, Validation};
 
-use crate::auth::jwt::Claims;
+use crate::auth::jwt::{keys, Claims};
+use crate::settings;
 
 pub fn jwt_decode(token: String) -> Result<TokenData<Claims>, Error> {
-    let secret_key = dotenv::var("JWT_SECRET").expect("JWT_SECRET must be set");
-    let mut validation = Validation::new(Algorithm::HS256);
-    validation.set_required_spec_claims(&["iat", "exp"]);
-    decode::<Claims>(
-        &token,
-        &DecodingKey::from_secret(secret_key.as_ref()),
-        &validation,
-    )
+    let keys = keys::get().map_err(|_| Error::from(ErrorKind::InvalidKeyFormat))?;
+
+    // tokens without a kid or with one of a removed key
+    let key = decode_header(&token)?
+        .kid
+        .and_then(|kid| keys.find(&kid))
+        .ok_or_else(|| Error::from(ErrorKind::InvalidToken))?;
+
+    let settings = settings::get();
+    let mut validation = Validation::new(key.algorithm);
+    validation.set_required_spec_claims(&["exp", "sub", "iss", "aud"]);
+    validation.set_issuer(&[&settings.jwt.issuer]);
+    validation.set_audience(&[&settings.jwt.audience]);
+    decode::<Claims>(&token, &key.decoding, &validation)
 }
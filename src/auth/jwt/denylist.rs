@@ -0,0 +1,32 @@
+//! Revoked tokens, e.g. after a logout. Rows are kept until the token expires on its own,
+//! the cleanup job deletes them after that.
+
+use chrono::DateTime;
+use diesel::dsl::exists;
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+
+use crate::auth::jwt::Claims;
+use crate::schema::revoked_tokens as revoked_tokens_data;
+use crate::schema::revoked_tokens::dsl as revoked_tokens_table;
+
+pub fn revoke(conn: &mut PgConnection, claims: &Claims) -> Result<(), DieselError> {
+    let expires_at = DateTime::from_timestamp(claims.exp as i64, 0)
+        .unwrap_or_default()
+        .naive_utc();
+
+    diesel::insert_into(revoked_tokens_table::revoked_tokens)
+        .values((
+            revoked_tokens_data::jti.eq(&claims.jti),
+            revoked_tokens_data::user_id.eq(claims.user_id()),
+            revoked_tokens_data::expires_at.eq(expires_at),
+        ))
+        .on_conflict_do_nothing()
+        .execute(conn)?;
+
+    Ok(())
+}
+
+pub fn is_revoked(conn: &mut PgConnection, jti: &str) -> Result<bool, DieselError> {
+    diesel::select(exists(revoked_tokens_table::revoked_tokens.find(jti))).get_result(conn)
+}
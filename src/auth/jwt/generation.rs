@@ -1,25 +1,53 @@
-use crate::auth::jwt::Claims;
-use crate::constants::JWT_EXPIRATION_TIME;
+use crate::auth::jwt::{keys, Claims, WorkspaceMembership};
+use crate::models::AuthUser as User;
+use crate::schema::{roles, user_roles, workspace_roles, workspace_users};
+use crate::settings;
 use chrono::Utc;
-use dotenv;
-use jsonwebtoken::{encode, EncodingKey, Header};
+use diesel::prelude::*;
+use jsonwebtoken::{encode, Header};
+use uuid::Uuid;
 
-pub fn generate(email: &str) -> Result<String, Box<dyn std::error::Error>> {
+pub fn generate(
+    conn: &mut PgConnection,
+    user: &User,
+    session_id: i32,
+) -> Result<String, Box<dyn std::error::Error>> {
     let now = Utc::now();
-    let expiration = now + chrono::Duration::seconds(JWT_EXPIRATION_TIME);
-    let secret_key = dotenv::var("JWT_SECRET").expect("JWT secret must be set");
+    let settings = settings::get();
+    let expiration = now + chrono::Duration::seconds(settings.jwt.lifetime);
+    let key = keys::get()?.signing();
+
+    let roles = user_roles::table
+        .inner_join(roles::table)
+        .filter(user_roles::user_id.eq(user.id))
+        .select(roles::name)
+        .load::<String>(conn)?;
+
+    let workspaces = workspace_users::table
+        .inner_join(workspace_roles::table)
+        .filter(workspace_users::user_id.eq(user.id))
+        .select((workspace_users::workspace_id, workspace_roles::name))
+        .load::<(i32, String)>(conn)?
+        .into_iter()
+        .map(|(id, role)| WorkspaceMembership { id, role })
+        .collect();
 
     let claims = Claims {
+        sub: user.id.to_string(),
+        iss: settings.jwt.issuer.clone(),
+        aud: settings.jwt.audience.clone(),
         iat: now.timestamp() as usize,
         exp: expiration.timestamp() as usize,
-        email: email.to_string(),
+        jti: Uuid::new_v4().to_string(),
+        sid: session_id,
+        email: user.email.clone(),
+        roles,
+        workspaces,
     };
 
-    let token = encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(secret_key.as_ref()),
-    )?;
+    let mut header = Header::new(key.algorithm);
+    header.kid = Some(key.kid.clone());
+    let token = encode(&header, &claims, &key.encoding)?;
 
     Ok(token)
 }
@@ -0,0 +1,19 @@
+use actix_web::http::header::{CacheControl, CacheDirective};
+use actix_web::{get, HttpResponse};
+
+use crate::api_error::ApiError;
+use crate::auth::jwt::keys;
+use crate::constants::JWKS_MAX_AGE;
+
+/// Public keys verifying our tokens, for other services.
+#[get("/.well-known/jwks.json")]
+pub async fn jwks() -> Result<HttpResponse, ApiError> {
+    let keys = keys::get().map_err(|e| ApiError::Internal(e.to_string()))?;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(CacheControl(vec![
+            CacheDirective::Public,
+            CacheDirective::MaxAge(JWKS_MAX_AGE),
+        ]))
+        .json(keys.jwks()))
+}
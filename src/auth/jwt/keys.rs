@@ -0,0 +1,190 @@
+//! Keys signing and verifying JWTs. Every `<kid>.pem` private key in `jwt.keys_dir` verifies
+//! tokens carrying its `kid`, `jwt.signing_key` names the one signing new tokens. RSA keys
+//! sign with RS256, Ed25519 keys with EdDSA.
+//!
+//! Rotating: add a key, point `signing_key` at it and delete the old one once the tokens it
+//! signed have expired.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use jsonwebtoken::jwk::{
+    AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm,
+    OctetKeyPairParameters, OctetKeyPairType, PublicKeyUse, RSAKeyParameters, RSAKeyType,
+};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey};
+use ring::rsa::PublicKeyComponents;
+use ring::signature::{Ed25519KeyPair, KeyPair, RsaKeyPair};
+
+use crate::settings::JwtSettings;
+
+static KEYS: OnceLock<JwtKeys> = OnceLock::new();
+
+#[derive(Debug)]
+pub enum JwtKeyError {
+    Io(PathBuf, std::io::Error),
+    Invalid(PathBuf, String),
+    NoSigningKey(String),
+    NotLoaded,
+}
+
+impl fmt::Display for JwtKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JwtKeyError::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+            JwtKeyError::Invalid(path, e) => write!(f, "{}: {}", path.display(), e),
+            JwtKeyError::NoSigningKey(kid) => write!(f, "signing key {} not found", kid),
+            JwtKeyError::NotLoaded => write!(f, "JWT keys are not loaded"),
+        }
+    }
+}
+
+impl std::error::Error for JwtKeyError {}
+
+pub struct JwtKey {
+    pub kid: String,
+    pub algorithm: Algorithm,
+    pub encoding: EncodingKey,
+    pub decoding: DecodingKey,
+    jwk: Jwk,
+}
+
+pub struct JwtKeys {
+    signing_kid: String,
+    keys: BTreeMap<String, JwtKey>,
+}
+
+impl JwtKeys {
+    pub fn load(settings: &JwtSettings) -> Result<Self, JwtKeyError> {
+        let dir = &settings.keys_dir;
+        let entries = fs::read_dir(dir).map_err(|e| JwtKeyError::Io(dir.clone(), e))?;
+
+        let mut keys = BTreeMap::new();
+        for entry in entries {
+            let path = entry.map_err(|e| JwtKeyError::Io(dir.clone(), e))?.path();
+            if path.extension().is_none_or(|ext| ext != "pem") {
+                continue;
+            }
+            let Some(kid) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            let key = load_key(kid.to_string(), &path)?;
+            keys.insert(key.kid.clone(), key);
+        }
+
+        let signing_kid = settings.signing_key.clone().unwrap_or_default();
+        if !keys.contains_key(&signing_kid) {
+            return Err(JwtKeyError::NoSigningKey(format!(
+                "{}.pem in {}",
+                signing_kid,
+                dir.display()
+            )));
+        }
+
+        Ok(Self { signing_kid, keys })
+    }
+
+    pub fn signing(&self) -> &JwtKey {
+        &self.keys[&self.signing_kid]
+    }
+
+    pub fn find(&self, kid: &str) -> Option<&JwtKey> {
+        self.keys.get(kid)
+    }
+
+    pub fn kids(&self) -> impl Iterator<Item = &str> {
+        self.keys.keys().map(String::as_str)
+    }
+
+    /// Public halves of all keys.
+    pub fn jwks(&self) -> JwkSet {
+        JwkSet {
+            keys: self.keys.values().map(|key| key.jwk.clone()).collect(),
+        }
+    }
+}
+
+fn load_key(kid: String, path: &Path) -> Result<JwtKey, JwtKeyError> {
+    let invalid = |e: String| JwtKeyError::Invalid(path.to_path_buf(), e);
+
+    let bytes = fs::read(path).map_err(|e| JwtKeyError::Io(path.to_path_buf(), e))?;
+    let pem = pem::parse(&bytes).map_err(|e| invalid(e.to_string()))?;
+    let der = pem.contents();
+
+    let (algorithm, encoding, parameters) = match pem.tag() {
+        // PKCS#8, `openssl genpkey -algorithm ed25519` or `-algorithm rsa`
+        "PRIVATE KEY" => match Ed25519KeyPair::from_pkcs8_maybe_unchecked(der) {
+            Ok(pair) => (
+                Algorithm::EdDSA,
+                EncodingKey::from_ed_der(der),
+                AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+                    key_type: OctetKeyPairType::OctetKeyPair,
+                    curve: EllipticCurve::Ed25519,
+                    x: URL_SAFE_NO_PAD.encode(pair.public_key().as_ref()),
+                }),
+            ),
+            Err(_) => {
+                let pair = RsaKeyPair::from_pkcs8(der).map_err(|e| invalid(e.to_string()))?;
+                rsa_key(&pair, &bytes).map_err(invalid)?
+            }
+        },
+        // PKCS#1, `openssl genrsa`
+        "RSA PRIVATE KEY" => {
+            let pair = RsaKeyPair::from_der(der).map_err(|e| invalid(e.to_string()))?;
+            rsa_key(&pair, &bytes).map_err(invalid)?
+        }
+        tag => return Err(invalid(format!("expected a private key, found {}", tag))),
+    };
+
+    let jwk = Jwk {
+        common: CommonParameters {
+            public_key_use: Some(PublicKeyUse::Signature),
+            key_algorithm: Some(match algorithm {
+                Algorithm::EdDSA => KeyAlgorithm::EdDSA,
+                _ => KeyAlgorithm::RS256,
+            }),
+            key_id: Some(kid.clone()),
+            ..Default::default()
+        },
+        algorithm: parameters,
+    };
+    let decoding = DecodingKey::from_jwk(&jwk).map_err(|e| invalid(e.to_string()))?;
+
+    Ok(JwtKey {
+        kid,
+        algorithm,
+        encoding,
+        decoding,
+        jwk,
+    })
+}
+
+fn rsa_key(
+    pair: &RsaKeyPair,
+    pem: &[u8],
+) -> Result<(Algorithm, EncodingKey, AlgorithmParameters), String> {
+    let components = PublicKeyComponents::<Vec<u8>>::from(pair.public());
+    Ok((
+        Algorithm::RS256,
+        EncodingKey::from_rsa_pem(pem).map_err(|e| e.to_string())?,
+        AlgorithmParameters::RSA(RSAKeyParameters {
+            key_type: RSAKeyType::RSA,
+            n: URL_SAFE_NO_PAD.encode(components.n),
+            e: URL_SAFE_NO_PAD.encode(components.e),
+        }),
+    ))
+}
+
+pub fn init(keys: JwtKeys) {
+    // a second init keeps the keys from the first one
+    let _ = KEYS.set(keys);
+}
+
+pub fn get() -> Result<&'static JwtKeys, JwtKeyError> {
+    KEYS.get().ok_or(JwtKeyError::NotLoaded)
+}
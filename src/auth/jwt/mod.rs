@@ -1,8 +1,11 @@
 mod claims;
 mod decoding;
+pub mod denylist;
 mod generation;
+pub mod jwks;
+pub mod keys;
 mod verify;
-pub use claims::Claims;
+pub use claims::{Claims, WorkspaceMembership};
 pub use decoding::jwt_decode;
 pub use generation::generate;
 pub use verify::verify;
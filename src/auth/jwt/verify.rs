@@ -1,59 +1,31 @@
-use crate::{constants::JWT_EXPIRATION_TIME, est_conn, DPool};
-use chrono::Utc;
-use diesel::RunQueryDsl;
-use diesel::{query_dsl::methods::FilterDsl, result::Error as DieselError, ExpressionMethods};
-use std::usize;
+use crate::auth::jwt::{denylist, Claims};
+use crate::auth::sessions;
+use crate::{est_conn, DPool};
 
-fn verify_email(users_email: String, pool: DPool) -> Result<bool, DieselError> {
-    use crate::schema::auth_users::dsl::*;
-
-    let conn = &mut est_conn(pool.clone());
-
-    let exists = diesel::select(diesel::dsl::exists(
-        auth_users.filter(email.eq(users_email)),
-    ))
-    .get_result(conn);
-
-    match exists {
-        Ok(true) => Ok(true),
-        Ok(false) => Ok(false),
-        Err(e) => Err(e),
-    }
-}
+/// Claims of a valid token that was not revoked and whose session is active. Signature, expiry,
+/// issuer and audience are checked while decoding.
+pub fn verify(token: &str, pool: DPool) -> Option<Claims> {
+    let claims = match super::jwt_decode(token.to_string()) {
+        Ok(data) => data.claims,
+        Err(e) => {
+            log::debug!("Error verifying token: {:?}", e);
+            return None;
+        }
+    };
 
-fn verify_date(iat: usize, exp: usize) -> bool {
-    if exp - iat != JWT_EXPIRATION_TIME as usize {
-        return false;
-    }
-    if Utc::now().timestamp() as usize > exp {
-        return false;
-    }
-    true
-}
-// need to add date and time verification
-pub fn verify(token: &str, pool: DPool) -> bool {
-    match super::jwt_decode(token.to_string()) {
-        Ok(token) => {
-            if !verify_date(token.claims.iat, token.claims.exp) {
-                println!("token has expired");
-                return false;
-            }
+    let conn = &mut est_conn(pool);
+    let active = denylist::is_revoked(conn, &claims.jti)
+        .and_then(|revoked| Ok(!revoked && sessions::touch(conn, claims.sid)?));
 
-            match verify_email(token.claims.email, pool) {
-                Ok(true) => true,
-                Ok(false) => {
-                    eprintln!("Email not found");
-                    false
-                }
-                Err(e) => {
-                    eprintln!("Database error: {:?}", e);
-                    false
-                }
-            }
+    match active {
+        Ok(true) => Some(claims),
+        Ok(false) => {
+            log::debug!("Token {} or its session is revoked", claims.jti);
+            None
         }
         Err(e) => {
-            eprintln!("Error verifying token: {:?}", e);
-            false
+            log::error!("Error checking token revocation: {:?}", e);
+            None
         }
     }
 }
@@ -1,38 +1,49 @@
-use crate::auth::jwt::generate;
+use crate::api_error::ApiError;
+use crate::auth::auth_error::AuthError;
+use crate::auth::password;
+use crate::auth::sessions::{self, ClientInfo};
 use crate::auth::ResponseUser;
 use crate::auth::UserWithRoles;
+use crate::buisness_logic::audit::{self, AuditAction, AuditEvent, AuditTarget};
 use crate::constants::APPLICATION_JSON;
+use crate::i18n::keys;
 use crate::models::AuthUser as User;
+use crate::validation::ValidatedJson;
 use crate::{est_conn, response, schema, DPool};
 use actix_web::web;
-use actix_web::{post, web::Json, HttpResponse};
+use actix_web::{post, HttpResponse};
 use diesel::prelude::*;
 use diesel::result::Error;
 use serde::Deserialize;
+use validator::Validate;
 
-type LoginUserError = response::Response<String>;
 type LoginResponse = response::Response<ResponseUser>;
 pub enum LoginMethodIdentifier {
     Username(String),
     Email(String),
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Validate)]
 pub struct RequestLoginUsername {
+    #[validate(length(min = 1))]
     username: String,
+    #[validate(length(min = 1))]
     password: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Validate)]
 pub struct RequestLoginEmail {
+    #[validate(email)]
     email: String,
+    #[validate(length(min = 1))]
     password: String,
 }
 
+/// The user and their global roles, the token is issued once the password is verified.
 pub async fn list_user(
     identifier: LoginMethodIdentifier,
     pool: DPool,
-) -> Result<UserWithRoles, Error> {
+) -> Result<(User, Vec<String>), Error> {
     use crate::schema::auth_users::dsl::*;
 
     let user_result = match identifier {
@@ -58,78 +69,148 @@ pub async fn list_user(
         .load::<String>(&mut est_conn(pool))
         .unwrap_or_else(|_| vec![]);
 
-    let token = match generate(&usr.email) {
-        Ok(t) => t,
-        Err(_) => {
-            eprintln!("Error generating jwt");
-            return Err(Error::NotFound);
+    Ok((usr, user_roles_result))
+}
+
+/// Stores a fresh hash when the current one is bcrypt or uses outdated Argon2 parameters.
+/// A failed upgrade does not fail the login, the next one tries again.
+fn rehash_password(plain_password: &str, user_id: i32, current_hash: &str, pool: DPool) {
+    use crate::schema::auth_users as user_data;
+
+    if !password::needs_rehash(current_hash) {
+        return;
+    }
+
+    let new_hash = match password::hash(plain_password) {
+        Ok(h) => h,
+        Err(e) => {
+            log::error!("Error rehashing password of user {}: {}", user_id, e);
+            return;
         }
     };
-    Ok(UserWithRoles::new(usr, user_roles_result, token))
+
+    // only replace the hash that was just verified, a concurrent password change wins
+    let current = user_data::table
+        .find(user_id)
+        .filter(user_data::password.eq(current_hash));
+    if let Err(e) = diesel::update(current)
+        .set(user_data::password.eq(new_hash))
+        .execute(&mut est_conn(pool))
+    {
+        log::error!(
+            "Error storing rehashed password of user {}: {:?}",
+            user_id,
+            e
+        );
+    }
 }
 
-#[post("/auth/login/username")]
-pub async fn login_username(request: Json<RequestLoginUsername>, pool: DPool) -> HttpResponse {
-    let user_username = request.username.clone();
-    let user = web::block(move || list_user(LoginMethodIdentifier::Username(user_username), pool))
-        .await
-        .unwrap();
-
-    match user.await {
-        Ok(usr) => match bcrypt::verify(&request.password, &usr.password) {
-            Ok(valid) if valid => HttpResponse::Ok()
-                .content_type(APPLICATION_JSON)
-                .json(LoginResponse::new(ResponseUser::new(usr))),
-            Ok(_) => HttpResponse::BadRequest()
-                .json(LoginUserError::new("password is incorrect".to_string())),
-            Err(_) => HttpResponse::InternalServerError()
-                .json(LoginUserError::new("Failed to verify password".to_string())),
-        },
-        Err(Error::NotFound) => {
-            eprintln!("User with provided username was not found");
-            HttpResponse::NotFound().json(LoginUserError::new(format!(
-                "User with username {} was not found",
-                request.username,
-            )))
+/// Names the account by id only, audit events outlive the address of a deleted account.
+fn record_failed_login(pool: DPool, user_id: Option<i32>, reason: &str, client: &ClientInfo) {
+    let mut event = AuditEvent::new(AuditAction::LoginFailed)
+        .ip(client.ip.clone())
+        .param("reason", reason);
+    if let Some(user_id) = user_id {
+        event = event.target(AuditTarget::User, user_id);
+    }
+    audit::record_or_log(&mut est_conn(pool), event);
+}
+
+/// Hashing is slow, the password is verified and rehashed off the async workers.
+async fn verify_password(
+    plain_password: String,
+    (usr, roles): (User, Vec<String>),
+    client: ClientInfo,
+    pool: DPool,
+) -> Result<HttpResponse, ApiError> {
+    // a disabled account is refused before any hashing is spent on it
+    if usr.disabled_at.is_some() {
+        record_failed_login(pool, Some(usr.id), "disabled", &client);
+        return Err(ApiError::Forbidden(keys::ACCOUNT_DISABLED));
+    }
+
+    let (user_id, current_hash, rehash_pool) = (usr.id, usr.password.clone(), pool.clone());
+    let verified = web::block(move || {
+        let verified = password::verify(&plain_password, &current_hash)?;
+        if verified {
+            rehash_password(&plain_password, user_id, &current_hash, rehash_pool);
         }
-        Err(e) => {
-            eprintln!("Error matching users in login_username {:?}", e);
-            HttpResponse::InternalServerError().json(LoginUserError {
-                response: "Error ".to_string(),
-            })
+        Ok::<_, password::PasswordError>(verified)
+    })
+    .await
+    .map_err(|e| ApiError::Internal(e.to_string()))?;
+    match verified {
+        Ok(true) => {}
+        Ok(false) => {
+            record_failed_login(pool, Some(usr.id), "wrong_password", &client);
+            return Err(AuthError::PasswordIncorrect.into());
         }
+        Err(e) => return Err(AuthError::ServerError(e.to_string()).into()),
     }
+
+    let ip = client.ip.clone();
+    let conn = &mut est_conn(pool);
+    let token = sessions::issue_token(conn, &usr, client)
+        .map_err(|e| AuthError::ServerError(format!("Error generating jwt: {:?}", e)))?;
+    audit::record_or_log(
+        conn,
+        AuditEvent::new(AuditAction::Login)
+            .actor(usr.id)
+            .target(AuditTarget::User, usr.id)
+            .ip(ip)
+            .param("method", "password"),
+    );
+
+    Ok(HttpResponse::Ok()
+        .content_type(APPLICATION_JSON)
+        .json(LoginResponse::new(ResponseUser::new(UserWithRoles::new(
+            usr, roles, token,
+        )))))
+}
+
+#[post("/auth/login/username")]
+pub async fn login_username(
+    request: ValidatedJson<RequestLoginUsername>,
+    client: ClientInfo,
+    pool: DPool,
+) -> Result<HttpResponse, ApiError> {
+    let user_username = request.username.clone();
+    let pool_clone = pool.clone();
+    let usr =
+        web::block(move || list_user(LoginMethodIdentifier::Username(user_username), pool_clone))
+            .await
+            .map_err(|e| ApiError::Internal(e.to_string()))?
+            .await
+            .map_err(|e| match e {
+                Error::NotFound => {
+                    record_failed_login(pool.clone(), None, "unknown_user", &client);
+                    AuthError::UsernameNotFound.into()
+                }
+                e => ApiError::Database(e),
+            })?;
+
+    verify_password(request.password.clone(), usr, client, pool).await
 }
 
 #[post("/auth/login/email")]
-pub async fn login_email(request: Json<RequestLoginEmail>, pool: DPool) -> HttpResponse {
+pub async fn login_email(
+    request: ValidatedJson<RequestLoginEmail>,
+    client: ClientInfo,
+    pool: DPool,
+) -> Result<HttpResponse, ApiError> {
     let user_email = request.email.clone();
-    let user = web::block(move || list_user(LoginMethodIdentifier::Email(user_email), pool))
+    let pool_clone = pool.clone();
+    let usr = web::block(move || list_user(LoginMethodIdentifier::Email(user_email), pool_clone))
         .await
-        .unwrap();
-
-    match user.await {
-        Ok(usr) => match bcrypt::verify(&request.password, &usr.password) {
-            Ok(valid) if valid => HttpResponse::Ok()
-                .content_type(APPLICATION_JSON)
-                .json(LoginResponse::new(ResponseUser::new(usr))),
-            Ok(_) => HttpResponse::BadRequest()
-                .json(LoginUserError::new("password is incorrect".to_string())),
-            Err(_) => HttpResponse::InternalServerError()
-                .json(LoginUserError::new("Failed to verify password".to_string())),
-        },
-        Err(Error::NotFound) => {
-            eprintln!("User with provided email was not found");
-            HttpResponse::NotFound().json(LoginUserError::new(format!(
-                "User with email {} was not found",
-                request.email,
-            )))
-        }
-        Err(e) => {
-            eprintln!("Error matching users in login_username {:?}", e);
-            HttpResponse::InternalServerError().json(LoginUserError {
-                response: "Error ".to_string(),
-            })
-        }
-    }
+        .map_err(|e| ApiError::Internal(e.to_string()))?
+        .await
+        .map_err(|e| match e {
+            Error::NotFound => {
+                record_failed_login(pool.clone(), None, "unknown_user", &client);
+                AuthError::EmailNotFound.into()
+            }
+            e => ApiError::Database(e),
+        })?;
+
+    verify_password(request.password.clone(), usr, client, pool).await
 }
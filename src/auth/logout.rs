@@ -0,0 +1,24 @@
+use actix_web::{post, HttpResponse};
+
+use crate::api_error::ApiError;
+use crate::auth::jwt::denylist;
+use crate::auth::sessions;
+use crate::auth::AuthenticatedUser;
+use crate::i18n::{self, keys, Locale};
+use crate::response::Response as Res;
+use crate::{est_conn, DPool};
+
+/// Revokes the token of the request and ends its session, the token is rejected from now on
+/// even before it expires.
+#[post("/auth/logout")]
+pub async fn logout(
+    user: AuthenticatedUser,
+    pool: DPool,
+    locale: Locale,
+) -> Result<HttpResponse, ApiError> {
+    let conn = &mut est_conn(pool);
+    denylist::revoke(conn, &user.claims)?;
+    sessions::revoke(conn, user.id, user.claims.sid)?;
+
+    Ok(HttpResponse::Ok().json(Res::new(i18n::t(locale, keys::LOGGED_OUT))))
+}
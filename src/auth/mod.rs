@@ -1,14 +1,22 @@
 pub mod auth_error;
+mod authenticated_user;
+pub mod public_user;
 mod response_user;
 mod user_with_roles;
 pub mod verify_session;
+pub use authenticated_user::{AdminUser, AuthenticatedUser, StaffUser};
+pub use public_user::{PublicUser, Viewer};
 pub use response_user::ResponseUser;
 pub use user_with_roles::UserWithRoles;
 pub mod confirmation_token;
 pub mod find_user;
 pub mod jwt;
 pub mod login;
+pub mod logout;
+pub mod oidc;
+pub mod password;
 pub mod register;
 pub mod resend_verification_email;
 pub mod reset_password;
+pub mod sessions;
 pub mod validate_account;
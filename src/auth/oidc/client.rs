@@ -0,0 +1,276 @@
+use std::fmt;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use reqwest::Url;
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::constants::{OIDC_HTTP_TIMEOUT, OIDC_SCOPES};
+use crate::settings::{self, OidcProvider};
+
+static HTTP: OnceLock<reqwest::Client> = OnceLock::new();
+
+#[derive(Debug)]
+pub enum OidcError {
+    /// Not configured or without a `client_id`.
+    UnknownProvider(String),
+    /// The provider could not be reached.
+    Request(reqwest::Error),
+    /// The provider refused the login or answered with something we do not accept.
+    Rejected(String),
+}
+
+impl fmt::Display for OidcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OidcError::UnknownProvider(name) => write!(f, "unknown provider {}", name),
+            OidcError::Request(e) => write!(f, "provider request failed: {}", e),
+            OidcError::Rejected(e) => write!(f, "login rejected: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for OidcError {}
+
+impl From<reqwest::Error> for OidcError {
+    fn from(e: reqwest::Error) -> Self {
+        OidcError::Request(e)
+    }
+}
+
+impl From<jsonwebtoken::errors::Error> for OidcError {
+    fn from(e: jsonwebtoken::errors::Error) -> Self {
+        OidcError::Rejected(format!("id token: {}", e))
+    }
+}
+
+fn http() -> &'static reqwest::Client {
+    HTTP.get_or_init(|| {
+        reqwest::Client::builder()
+            .timeout(Duration::from_secs(OIDC_HTTP_TIMEOUT))
+            .build()
+            .expect("failed to build the OIDC http client")
+    })
+}
+
+/// 32 random bytes, base64url encoded, for `state`, `nonce` and the PKCE verifier.
+pub fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    SystemRandom::new()
+        .fill(&mut bytes)
+        .expect("system random number generator failed");
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// PKCE `S256` challenge of the verifier.
+pub fn code_challenge(code_verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()))
+}
+
+#[derive(Deserialize)]
+struct Discovery {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    jwks_uri: String,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+/// Claims of the ID token we use.
+#[derive(Deserialize)]
+pub struct IdTokenClaims {
+    iss: String,
+    pub sub: String,
+    #[serde(default)]
+    nonce: Option<String>,
+    #[serde(default)]
+    pub email: Option<String>,
+    #[serde(default)]
+    email_verified: Option<bool>,
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Microsoft tenant, part of the issuer of multi-tenant apps.
+    #[serde(default)]
+    tid: Option<String>,
+}
+
+pub struct Provider {
+    pub name: String,
+    settings: OidcProvider,
+    discovery: Discovery,
+}
+
+impl Provider {
+    /// Reads the provider's endpoints from its discovery document.
+    pub async fn discover(name: &str) -> Result<Self, OidcError> {
+        let settings = settings::get()
+            .oidc
+            .enabled()
+            .find(|(provider, _)| provider.as_str() == name)
+            .map(|(_, settings)| settings.clone())
+            .ok_or_else(|| OidcError::UnknownProvider(name.to_string()))?;
+
+        let url = format!(
+            "{}/.well-known/openid-configuration",
+            settings.issuer.trim_end_matches('/')
+        );
+        let discovery = http()
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<Discovery>()
+            .await?;
+
+        Ok(Self {
+            name: name.to_string(),
+            settings,
+            discovery,
+        })
+    }
+
+    fn client_id(&self) -> &str {
+        self.settings.client_id.as_deref().unwrap_or_default()
+    }
+
+    fn redirect_uri(&self) -> String {
+        format!(
+            "{}/{}",
+            settings::get().oidc.redirect_url.trim_end_matches('/'),
+            self.name
+        )
+    }
+
+    /// Where the browser is sent to log in.
+    pub fn authorization_url(
+        &self,
+        state: &str,
+        nonce: &str,
+        code_verifier: &str,
+    ) -> Result<String, OidcError> {
+        let url = Url::parse_with_params(
+            &self.discovery.authorization_endpoint,
+            &[
+                ("response_type", "code"),
+                ("client_id", self.client_id()),
+                ("redirect_uri", &self.redirect_uri()),
+                ("scope", OIDC_SCOPES),
+                ("state", state),
+                ("nonce", nonce),
+                ("code_challenge", &code_challenge(code_verifier)),
+                ("code_challenge_method", "S256"),
+            ],
+        )
+        .map_err(|e| OidcError::Rejected(format!("authorization endpoint: {}", e)))?;
+
+        Ok(url.into())
+    }
+
+    /// Redeems the authorization code and returns the claims of the validated ID token.
+    pub async fn exchange(
+        &self,
+        code: &str,
+        code_verifier: &str,
+        nonce: &str,
+    ) -> Result<IdTokenClaims, OidcError> {
+        let redirect_uri = self.redirect_uri();
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", &redirect_uri),
+            ("client_id", self.client_id()),
+            ("code_verifier", code_verifier),
+        ];
+        if let Some(secret) = &self.settings.client_secret {
+            form.push(("client_secret", secret.expose()));
+        }
+
+        let response = http()
+            .post(&self.discovery.token_endpoint)
+            .form(&form)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(OidcError::Rejected(format!(
+                "token endpoint answered {}: {}",
+                status, body
+            )));
+        }
+        let tokens = response.json::<TokenResponse>().await?;
+
+        self.validate(&tokens.id_token, nonce).await
+    }
+
+    async fn validate(&self, id_token: &str, nonce: &str) -> Result<IdTokenClaims, OidcError> {
+        let header = decode_header(id_token)?;
+        // the token came straight from the provider over TLS, still only keys it publishes
+        // may sign it, never a shared secret
+        if matches!(
+            header.alg,
+            Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512
+        ) {
+            return Err(OidcError::Rejected(format!(
+                "id token signed with {:?}",
+                header.alg
+            )));
+        }
+
+        let jwks = http()
+            .get(&self.discovery.jwks_uri)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<JwkSet>()
+            .await?;
+        let jwk = match &header.kid {
+            Some(kid) => jwks.find(kid),
+            None if jwks.keys.len() == 1 => jwks.keys.first(),
+            None => None,
+        }
+        .ok_or_else(|| OidcError::Rejected(format!("no key {:?} in the JWKS", header.kid)))?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_audience(&[self.client_id()]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+        let claims =
+            decode::<IdTokenClaims>(id_token, &DecodingKey::from_jwk(jwk)?, &validation)?.claims;
+
+        // checked here, multi-tenant Microsoft apps get an issuer with a `{tenantid}` placeholder
+        let issuer = self
+            .discovery
+            .issuer
+            .replace("{tenantid}", claims.tid.as_deref().unwrap_or_default());
+        if claims.iss != issuer {
+            return Err(OidcError::Rejected(format!(
+                "issuer {} instead of {}",
+                claims.iss, issuer
+            )));
+        }
+        if claims.nonce.as_deref() != Some(nonce) {
+            return Err(OidcError::Rejected("nonce mismatch".to_string()));
+        }
+
+        Ok(claims)
+    }
+
+    /// The email the provider vouches for, if any.
+    pub fn verified_email<'a>(&self, claims: &'a IdTokenClaims) -> Option<&'a str> {
+        match claims.email_verified {
+            Some(true) => claims.email.as_deref(),
+            _ if self.settings.trust_email => claims.email.as_deref(),
+            _ => None,
+        }
+    }
+}
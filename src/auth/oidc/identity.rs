@@ -0,0 +1,133 @@
+use chrono::Utc;
+use diesel::prelude::*;
+
+use crate::api_error::ApiError;
+use crate::auth::oidc::client::{IdTokenClaims, Provider};
+use crate::auth::{password, sessions};
+use crate::i18n::{keys, Locale};
+use crate::models::AuthUser as User;
+use crate::schema::auth_users as auth_users_data;
+use crate::schema::auth_users::dsl as auth_users_table;
+use crate::schema::linked_identities as identities_data;
+use crate::schema::linked_identities::dsl as identities_table;
+use crate::schema::roles as roles_data;
+use crate::schema::user_roles as user_roles_data;
+
+const MAX_USERNAME_LENGTH: usize = 50;
+
+/// The user the provider account is linked to. The first login links it to the account with
+/// the same verified email, or creates a verified account for it.
+pub fn resolve(
+    conn: &mut PgConnection,
+    provider: &Provider,
+    claims: &IdTokenClaims,
+    locale: Locale,
+) -> Result<User, ApiError> {
+    conn.transaction(|conn| {
+        let now = Utc::now().naive_utc();
+
+        let linked = identities_table::linked_identities
+            .filter(identities_data::provider.eq(&provider.name))
+            .filter(identities_data::subject.eq(&claims.sub))
+            .inner_join(auth_users_table::auth_users)
+            .select((identities_data::id, User::as_select()))
+            .first::<(i32, User)>(conn)
+            .optional()?;
+        if let Some((identity_id, user)) = linked {
+            // deleted accounts keep their row, anonymized and no longer valid
+            if !user.account_valid {
+                return Err(ApiError::Forbidden(keys::ACCOUNT_INACTIVE));
+            }
+            diesel::update(identities_table::linked_identities.find(identity_id))
+                .set(identities_data::last_login_at.eq(now))
+                .execute(conn)?;
+            return Ok(user);
+        }
+
+        let email = provider
+            .verified_email(claims)
+            .ok_or(ApiError::Forbidden(keys::OIDC_EMAIL_NOT_VERIFIED))?;
+
+        let existing = auth_users_table::auth_users
+            .filter(auth_users_data::email.eq(email))
+            .select(User::as_select())
+            .first::<User>(conn)
+            .optional()?;
+        let user = match existing {
+            Some(user) if user.account_valid => user,
+            // whoever registered the address never proved owning it, their password goes
+            Some(user) => {
+                sessions::revoke_all(conn, user.id, None)?;
+                diesel::update(auth_users_table::auth_users.find(user.id))
+                    .set((
+                        auth_users_data::account_valid.eq(true),
+                        auth_users_data::password.eq(unusable_password()?),
+                    ))
+                    .returning(User::as_returning())
+                    .get_result(conn)?
+            }
+            None => create_user(conn, email, claims, locale)?,
+        };
+
+        diesel::insert_into(identities_table::linked_identities)
+            .values((
+                identities_data::user_id.eq(user.id),
+                identities_data::provider.eq(&provider.name),
+                identities_data::subject.eq(&claims.sub),
+                identities_data::email.eq(email),
+            ))
+            .execute(conn)?;
+        log::info!("Linked {} identity to user {}", provider.name, user.id);
+
+        Ok(user)
+    })
+}
+
+fn create_user(
+    conn: &mut PgConnection,
+    email: &str,
+    claims: &IdTokenClaims,
+    locale: Locale,
+) -> Result<User, ApiError> {
+    let username = claims
+        .name
+        .as_deref()
+        .filter(|name| !name.trim().is_empty())
+        .unwrap_or_else(|| email.split('@').next().unwrap_or(email))
+        .trim()
+        .chars()
+        .take(MAX_USERNAME_LENGTH)
+        .collect::<String>();
+
+    let user = diesel::insert_into(auth_users_table::auth_users)
+        .values((
+            auth_users_data::username.eq(username),
+            auth_users_data::email.eq(email),
+            auth_users_data::password.eq(unusable_password()?),
+            auth_users_data::created_at.eq(Utc::now().naive_utc()),
+            auth_users_data::account_valid.eq(true),
+            auth_users_data::locale.eq(locale.code()),
+        ))
+        .returning(User::as_returning())
+        .get_result(conn)?;
+
+    let role_id = roles_data::table
+        .filter(roles_data::name.eq("USER"))
+        .select(roles_data::id)
+        .first::<i32>(conn)?;
+    diesel::insert_into(user_roles_data::table)
+        .values((
+            user_roles_data::user_id.eq(user.id),
+            user_roles_data::role_id.eq(role_id),
+        ))
+        .execute(conn)?;
+
+    log::info!("Created user {} on first external login", user.id);
+
+    Ok(user)
+}
+
+// accounts created by a provider log in through it, a password can be set with a reset
+fn unusable_password() -> Result<String, ApiError> {
+    password::unusable_hash().map_err(|e| ApiError::Internal(e.to_string()))
+}
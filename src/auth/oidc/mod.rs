@@ -0,0 +1,151 @@
+//! Login with an OpenID Connect provider (Google, Microsoft, ...) using the authorization code
+//! flow with PKCE. The frontend sends the browser to the URL from `authorize`, the provider
+//! sends it back to `oidc.redirect_url/{provider}`, and the page there posts the code and state
+//! to `callback`, which answers like the password login.
+
+mod client;
+mod identity;
+
+pub use client::OidcError;
+
+use actix_web::web::Path;
+use actix_web::{get, post, HttpResponse};
+use chrono::{Duration, Utc};
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::api_error::ApiError;
+use crate::auth::sessions::{self, ClientInfo};
+use crate::auth::{ResponseUser, UserWithRoles};
+use crate::buisness_logic::audit::{self, AuditAction, AuditEvent, AuditTarget};
+use crate::constants::OIDC_STATE_LIFETIME;
+use crate::i18n::{keys, Locale};
+use crate::schema::oidc_login_states as states_data;
+use crate::schema::oidc_login_states::dsl as states_table;
+use crate::validation::ValidatedJson;
+use crate::{est_conn, response::Response as Res, schema, settings, DPool};
+use client::{random_token, Provider};
+
+#[derive(Serialize)]
+struct AuthorizeResponse {
+    url: String,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct CallbackRequest {
+    #[validate(length(min = 1))]
+    code: String,
+    #[validate(length(min = 1, max = 64))]
+    state: String,
+}
+
+/// Names of the providers users can log in with.
+#[get("/auth/oidc/providers")]
+pub async fn list_providers() -> HttpResponse {
+    let providers = settings::get()
+        .oidc
+        .enabled()
+        .map(|(name, _)| name.clone())
+        .collect::<Vec<_>>();
+
+    HttpResponse::Ok().json(Res::new(providers))
+}
+
+/// Starts a login, the returned URL is valid for `OIDC_STATE_LIFETIME`.
+#[get("/auth/oidc/{provider}/authorize")]
+pub async fn authorize(path: Path<String>, pool: DPool) -> Result<HttpResponse, ApiError> {
+    let provider = Provider::discover(&path.into_inner()).await?;
+
+    let state = random_token();
+    let nonce = random_token();
+    let code_verifier = random_token();
+    let url = provider.authorization_url(&state, &nonce, &code_verifier)?;
+
+    diesel::insert_into(states_table::oidc_login_states)
+        .values((
+            states_data::state.eq(state),
+            states_data::provider.eq(&provider.name),
+            states_data::code_verifier.eq(code_verifier),
+            states_data::nonce.eq(nonce),
+            states_data::expires_at
+                .eq(Utc::now().naive_utc() + Duration::seconds(OIDC_STATE_LIFETIME)),
+        ))
+        .execute(&mut est_conn(pool))?;
+
+    Ok(HttpResponse::Ok().json(Res::new(AuthorizeResponse { url })))
+}
+
+/// Finishes a login started by `authorize`, each state is accepted once.
+#[post("/auth/oidc/{provider}/callback")]
+pub async fn callback(
+    path: Path<String>,
+    request: ValidatedJson<CallbackRequest>,
+    client: ClientInfo,
+    pool: DPool,
+    locale: Locale,
+) -> Result<HttpResponse, ApiError> {
+    let name = path.into_inner();
+
+    let (code_verifier, nonce) = diesel::delete(
+        states_table::oidc_login_states
+            .filter(states_data::state.eq(&request.state))
+            .filter(states_data::provider.eq(&name))
+            .filter(states_data::expires_at.gt(Utc::now().naive_utc())),
+    )
+    .returning((states_data::code_verifier, states_data::nonce))
+    .get_result::<(String, String)>(&mut est_conn(pool.clone()))
+    .map_err(|e| match e {
+        DieselError::NotFound => ApiError::BadRequest(keys::OIDC_STATE_INVALID),
+        e => ApiError::Database(e),
+    })?;
+
+    let provider = Provider::discover(&name).await?;
+    let claims = provider
+        .exchange(&request.code, &code_verifier, &nonce)
+        .await?;
+
+    let mut conn = est_conn(pool);
+    let usr = identity::resolve(&mut conn, &provider, &claims, locale)?;
+    let refused = match usr.disabled_at {
+        Some(_) => Some(("disabled", keys::ACCOUNT_DISABLED)),
+        None if !usr.account_valid => Some(("inactive", keys::ACCOUNT_INACTIVE)),
+        None => None,
+    };
+    if let Some((reason, key)) = refused {
+        audit::record_or_log(
+            &mut conn,
+            AuditEvent::new(AuditAction::LoginFailed)
+                .target(AuditTarget::User, usr.id)
+                .ip(client.ip.clone())
+                .param("provider", name)
+                .param("reason", reason),
+        );
+        return Err(ApiError::Forbidden(key));
+    }
+    let ip = client.ip.clone();
+    let token = sessions::issue_token(&mut conn, &usr, client)
+        .map_err(|e| ApiError::Internal(format!("Error generating jwt: {:?}", e)))?;
+    audit::record_or_log(
+        &mut conn,
+        AuditEvent::new(AuditAction::Login)
+            .actor(usr.id)
+            .target(AuditTarget::User, usr.id)
+            .ip(ip)
+            .param("method", "oidc")
+            .param("provider", name),
+    );
+
+    let roles = schema::user_roles::table
+        .inner_join(schema::roles::table)
+        .filter(schema::user_roles::user_id.eq(usr.id))
+        .select(schema::roles::name)
+        .load::<String>(&mut conn)?;
+
+    Ok(
+        HttpResponse::Ok().json(Res::new(ResponseUser::new(UserWithRoles::new(
+            usr, roles, token,
+        )))),
+    )
+}
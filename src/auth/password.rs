@@ -0,0 +1,98 @@
+//! Password hashing. New hashes are Argon2id with the parameters from the settings,
+//! bcrypt hashes from before the switch still verify and are upgraded on login.
+
+use std::fmt;
+
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{self, PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::{Algorithm, Argon2, Params, Version};
+
+use crate::settings;
+
+#[derive(Debug)]
+pub enum PasswordError {
+    Argon2(password_hash::Error),
+    Bcrypt(bcrypt::BcryptError),
+}
+
+impl fmt::Display for PasswordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PasswordError::Argon2(e) => write!(f, "argon2: {}", e),
+            PasswordError::Bcrypt(e) => write!(f, "bcrypt: {}", e),
+        }
+    }
+}
+
+impl From<password_hash::Error> for PasswordError {
+    fn from(e: password_hash::Error) -> Self {
+        PasswordError::Argon2(e)
+    }
+}
+
+impl From<argon2::Error> for PasswordError {
+    fn from(e: argon2::Error) -> Self {
+        PasswordError::Argon2(e.into())
+    }
+}
+
+fn hasher() -> Result<Argon2<'static>, PasswordError> {
+    let params = settings::get().password.params()?;
+    Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+}
+
+// $2a$, $2b$, $2x$ and $2y$
+fn is_bcrypt(hash: &str) -> bool {
+    hash.starts_with("$2")
+}
+
+pub fn hash(password: &str) -> Result<String, PasswordError> {
+    let salt = SaltString::generate(&mut OsRng);
+    Ok(hasher()?
+        .hash_password(password.as_bytes(), &salt)?
+        .to_string())
+}
+
+/// Hash of a random password nobody knows, for accounts that log in through a provider or
+/// wait for a password reset.
+pub fn unusable_hash() -> Result<String, PasswordError> {
+    hash(SaltString::generate(&mut OsRng).as_str())
+}
+
+pub fn verify(password: &str, hash: &str) -> Result<bool, PasswordError> {
+    if is_bcrypt(hash) {
+        return bcrypt::verify(password, hash).map_err(PasswordError::Bcrypt);
+    }
+
+    // nothing matches a hash that cannot be read, e.g. the empty one of old anonymized accounts
+    let Ok(parsed) = PasswordHash::new(hash) else {
+        return Ok(false);
+    };
+    // the parameters are read from the hash, not the settings
+    match Argon2::default().verify_password(password.as_bytes(), &parsed) {
+        Ok(()) => Ok(true),
+        Err(password_hash::Error::Password) => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// True for bcrypt hashes and Argon2 hashes made with other parameters than the current ones.
+pub fn needs_rehash(hash: &str) -> bool {
+    let Ok(parsed) = PasswordHash::new(hash) else {
+        return true;
+    };
+    if parsed.algorithm != Algorithm::Argon2id.ident()
+        || parsed.version != Some(Version::V0x13.into())
+    {
+        return true;
+    }
+
+    match (Params::try_from(&parsed), settings::get().password.params()) {
+        (Ok(used), Ok(wanted)) => {
+            used.m_cost() != wanted.m_cost()
+                || used.t_cost() != wanted.t_cost()
+                || used.p_cost() != wanted.p_cost()
+        }
+        _ => true,
+    }
+}
@@ -0,0 +1,173 @@
+//! What other people see of a user. `models::AuthUser` holds the password hash and is never
+//! serialized, responses carry a `PublicUser` built for whoever is looking.
+
+use chrono::NaiveDateTime;
+use diesel::dsl::exists;
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+use serde::Serialize;
+
+use crate::auth::AuthenticatedUser;
+use crate::models::AuthUser as User;
+use crate::schema::workspace_users as workspace_users_data;
+
+/// Who is looking at a user, from the most to the least privileged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Viewer {
+    /// The user themselves.
+    Own,
+    /// Holds the `ADMIN` or the `SUPPORT` role.
+    Staff,
+    /// Shares a workspace with the user.
+    WorkspacePeer,
+    Other,
+}
+
+impl Viewer {
+    #[cfg(test)]
+    pub const ALL: [Viewer; 4] = [
+        Viewer::Own,
+        Viewer::Staff,
+        Viewer::WorkspacePeer,
+        Viewer::Other,
+    ];
+
+    /// How `viewer` relates to the user `user_id`. Workspaces are read from the database, the
+    /// memberships in the token can be stale.
+    pub fn of(
+        conn: &mut PgConnection,
+        viewer: &AuthenticatedUser,
+        user_id: i32,
+    ) -> Result<Viewer, DieselError> {
+        if viewer.id == user_id {
+            return Ok(Viewer::Own);
+        }
+        if viewer.claims.is_staff() {
+            return Ok(Viewer::Staff);
+        }
+
+        let viewer_workspaces = workspace_users_data::table
+            .filter(workspace_users_data::user_id.eq(viewer.id))
+            .select(workspace_users_data::workspace_id)
+            .load::<i32>(conn)?;
+        let shares_workspace = diesel::select(exists(
+            workspace_users_data::table
+                .filter(workspace_users_data::user_id.eq(user_id))
+                .filter(workspace_users_data::workspace_id.eq_any(viewer_workspaces)),
+        ))
+        .get_result::<bool>(conn)?;
+
+        Ok(if shares_workspace {
+            Viewer::WorkspacePeer
+        } else {
+            Viewer::Other
+        })
+    }
+
+    /// Sees the account details, not only who the user is.
+    pub fn sees_account(self) -> bool {
+        matches!(self, Viewer::Own | Viewer::Staff)
+    }
+}
+
+/// Fields a viewer may not see are left out of the JSON.
+#[derive(Debug, Serialize)]
+pub struct PublicUser {
+    pub id: i32,
+    pub username: String,
+    /// Own, staff and workspace peers.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    /// Own and staff from here on.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<NaiveDateTime>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account_valid: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+    /// Staff only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disabled_at: Option<Option<NaiveDateTime>>,
+}
+
+impl PublicUser {
+    pub fn new(user: User, viewer: Viewer) -> Self {
+        let account = viewer.sees_account();
+
+        Self {
+            id: user.id,
+            username: user.username,
+            email: (viewer != Viewer::Other).then_some(user.email),
+            created_at: account.then_some(user.created_at),
+            account_valid: account.then_some(user.account_valid),
+            locale: account.then_some(user.locale),
+            disabled_at: (viewer == Viewer::Staff).then_some(user.disabled_at),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use serde::Serialize;
+
+    use super::{PublicUser, Viewer};
+    use crate::auth::{ResponseUser, UserWithRoles};
+    use crate::models::AuthUser as User;
+    use crate::response::Response;
+
+    const HASH: &str = "$argon2id$v=19$m=19456,t=2,p=1$c29tZXNhbHQ$c29tZWhhc2hzb21laGFzaA";
+
+    fn user() -> User {
+        User {
+            id: 7,
+            username: "tomek".to_string(),
+            email: "tomek@example.com".to_string(),
+            password: HASH.to_string(),
+            created_at: Utc::now().naive_utc(),
+            account_valid: true,
+            locale: "pl".to_string(),
+            disabled_at: None,
+        }
+    }
+
+    fn assert_no_password(response: impl Serialize) {
+        let json = serde_json::to_string(&response).unwrap();
+        assert!(!json.contains(HASH), "password hash serialized: {}", json);
+        assert!(
+            !json.contains("password"),
+            "password field serialized: {}",
+            json
+        );
+    }
+
+    #[test]
+    fn response_types_never_serialize_the_password_hash() {
+        for viewer in Viewer::ALL {
+            assert_no_password(PublicUser::new(user(), viewer));
+        }
+        assert_no_password(Response::new(vec![PublicUser::new(user(), Viewer::Staff)]));
+        assert_no_password(ResponseUser::new(UserWithRoles::new(
+            user(),
+            vec!["USER".to_string()],
+            "token".to_string(),
+        )));
+    }
+
+    #[test]
+    fn viewers_see_only_their_fields() {
+        let other = serde_json::to_value(PublicUser::new(user(), Viewer::Other)).unwrap();
+        assert_eq!(other.as_object().unwrap().len(), 2);
+
+        let peer = serde_json::to_value(PublicUser::new(user(), Viewer::WorkspacePeer)).unwrap();
+        assert_eq!(peer["email"], "tomek@example.com");
+        assert!(peer.get("locale").is_none());
+
+        let own = serde_json::to_value(PublicUser::new(user(), Viewer::Own)).unwrap();
+        assert!(own.get("locale").is_some());
+        assert!(own.get("disabled_at").is_none());
+
+        let staff = serde_json::to_value(PublicUser::new(user(), Viewer::Staff)).unwrap();
+        assert!(staff.as_object().unwrap().contains_key("disabled_at"));
+    }
+}
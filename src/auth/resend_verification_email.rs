@@ -1,6 +1,10 @@
+use crate::api_error::ApiError;
+use crate::i18n::{self, keys, Locale};
 use crate::response::Response as Res;
-use actix_web::{post, web::Json, HttpResponse};
+use crate::validation::ValidatedJson;
+use actix_web::{post, HttpResponse};
 use serde::Deserialize;
+use validator::Validate;
 
 use crate::{auth::confirmation_token::token::TokenType, DPool};
 
@@ -8,41 +12,32 @@ use crate::auth::find_user::{Find, FindData};
 
 use super::confirmation_token::token::{Cft, ConfirmationToken};
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Validate)]
 struct ResendVerificationEmailRequest {
+    #[validate(email)]
     email: String,
 }
 
 #[post("/auth/resend/verification_email")]
 pub async fn resend_verification_email(
-    request: Json<ResendVerificationEmailRequest>,
+    request: ValidatedJson<ResendVerificationEmailRequest>,
     pool: DPool,
-) -> HttpResponse {
-    let user_data = FindData::find_auth_user_by_email(request.email.clone(), pool.clone()).await;
+    locale: Locale,
+) -> Result<HttpResponse, ApiError> {
+    let usr = FindData::find_auth_user_by_email(request.email.clone(), pool.clone())
+        .await
+        .map_err(ApiError::or_not_found(keys::USER_NOT_FOUND))?;
 
-    match user_data {
-        Err(_) => {
-            HttpResponse::InternalServerError().json(Res::new("Error while fetching user data"))
-        }
-        Ok(usr) => {
-            match <Cft as ConfirmationToken>::send(
-                usr.username,
-                usr.email,
-                pool,
-                crate::auth::confirmation_token::token::TokenEmailType::AccountVerificationResend,
-                None,
-                true,
-                TokenType::AccountVerification,
-            )
-            .await
-            {
-                Ok(_) => HttpResponse::Ok().json(Res::new("Email resent successfully")),
-                Err(_) => {
-                    eprintln!("Error while resending verification email");
-                    HttpResponse::InternalServerError()
-                        .json(Res::new("Server error while resending email"))
-                }
-            }
-        }
-    }
+    <Cft as ConfirmationToken>::send(
+        usr.username,
+        usr.email,
+        pool,
+        crate::auth::confirmation_token::token::TokenEmailType::AccountVerificationResend,
+        None,
+        true,
+        TokenType::AccountVerification,
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(Res::new(i18n::t(locale, keys::VERIFICATION_EMAIL_RESENT))))
 }
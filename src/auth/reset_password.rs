@@ -1,15 +1,20 @@
-use crate::auth::auth_error::{AccountVerification, VerificationTokenError};
+use crate::api_error::ApiError;
 use crate::auth::confirmation_token::token::TokenEmailType;
 use crate::auth::find_user::Find;
+use crate::auth::sessions::ClientInfo;
+use crate::buisness_logic::audit::{self, AuditAction, AuditEvent, AuditTarget};
 use crate::est_conn;
+use crate::i18n::{self, keys, Locale};
 use crate::models::AuthUser as User;
 use crate::response::Response as Res;
 use crate::schema::auth_users as user_data;
 use crate::schema::auth_users::dsl as user_table;
-use actix_web::{post, put, web::Json, web::Path, HttpResponse};
+use crate::validation::{validate_password, ValidatedJson};
+use actix_web::{post, put, web::Path, HttpResponse};
 use diesel::query_dsl::methods::FilterDsl;
-use diesel::{ExpressionMethods, RunQueryDsl};
+use diesel::{Connection, ExpressionMethods, RunQueryDsl};
 use serde::Deserialize;
+use validator::Validate;
 
 use crate::{
     auth::confirmation_token::token::{Cft, TokenType},
@@ -19,15 +24,20 @@ use crate::{
 
 use super::confirmation_token::token::ConfirmationToken;
 use super::find_user::FindData;
+use super::password;
+use super::sessions;
 
-#[derive(Deserialize)] // Add Deserialize
+#[derive(Deserialize, Validate)] // Add Deserialize
 struct EmailResetPasswordRequest {
+    #[validate(custom(function = "validate_password"))]
     new_password: String,
+    #[validate(email)]
     email: String,
 }
 
-#[derive(Deserialize)] // Add Deserialize
+#[derive(Deserialize, Validate)] // Add Deserialize
 struct ResetPasswordRequest {
+    #[validate(email)]
     email: String,
 }
 
@@ -36,78 +46,72 @@ struct Token {
     token: String,
 }
 
-async fn change_password(email: String, password: String, pool: DPool) -> Result<(), ()> {
-    let hashed_password = match bcrypt::hash(password, bcrypt::DEFAULT_COST) {
-        Ok(hp) => hp,
-        Err(_) => return Err(()),
-    };
-    match diesel::update(user_table::auth_users.filter(user_data::email.eq(email)))
-        .set(user_data::password.eq(hashed_password))
-        .execute(&mut est_conn(pool))
-    {
-        Ok(_) => Ok(()),
-        Err(_) => Err(()),
-    }
+async fn change_password(
+    email: String,
+    password: String,
+    ip: Option<String>,
+    pool: DPool,
+) -> Result<(), ApiError> {
+    let hashed_password = password::hash(&password)
+        .map_err(|e| ApiError::Internal(format!("Error hashing password: {}", e)))?;
+
+    // whoever knew the old password is logged out everywhere
+    est_conn(pool).transaction::<_, ApiError, _>(|conn| {
+        let user_ids = diesel::update(user_table::auth_users.filter(user_data::email.eq(email)))
+            .set(user_data::password.eq(hashed_password))
+            .returning(user_data::id)
+            .get_results::<i32>(conn)?;
+
+        for user_id in user_ids {
+            sessions::revoke_all(conn, user_id, None)?;
+            audit::record(
+                conn,
+                AuditEvent::new(AuditAction::PasswordReset)
+                    .actor(user_id)
+                    .target(AuditTarget::User, user_id)
+                    .ip(ip.clone()),
+            )?;
+        }
+        Ok(())
+    })
 }
 
 #[put("/auth/reset/password/{token}")]
 pub async fn email_reset_password(
     pool: DPool,
-    req: Json<EmailResetPasswordRequest>,
+    req: ValidatedJson<EmailResetPasswordRequest>,
     token: Path<Token>,
-) -> HttpResponse {
-    let pool_clone: DPool = pool.clone();
-    match <Cft as ConfirmationToken>::confirm(
+    client: ClientInfo,
+    locale: Locale,
+) -> Result<HttpResponse, ApiError> {
+    <Cft as ConfirmationToken>::confirm(
         token.token.clone(),
         TokenType::PasswordReset(req.email.clone()),
-        pool_clone,
+        pool.clone(),
     )
-    .await
-    {
-        Ok(_) => match change_password(req.email.clone(), req.new_password.clone(), pool).await {
-            Ok(_) => HttpResponse::Ok()
-                .content_type(APPLICATION_JSON)
-                .json(Res::new("Password verified successfully")),
-            Err(_) => {
-                HttpResponse::InternalServerError().json(Res::new("An unexpected error occurred"))
-            }
-        },
-
-        Err(e) => match e {
-            VerificationTokenError::NotFound => {
-                HttpResponse::BadRequest().json(Res::new("Token not found"))
-            }
-            VerificationTokenError::Expired => {
-                HttpResponse::BadRequest().json(Res::new("Token expired"))
-            }
-            VerificationTokenError::Account(AccountVerification::AccountAlreadyVerified) => {
-                HttpResponse::BadRequest().json(Res::new("Account already verified"))
-            }
-            VerificationTokenError::TokenAlreadyExists => {
-                HttpResponse::BadRequest().json(Res::new("Token already exists"))
-            }
-            VerificationTokenError::ServerError(_) => {
-                HttpResponse::InternalServerError().json(Res::new("An unexpected error occurred"))
-            }
-            _ => HttpResponse::BadRequest().json(Res::new("Bad Request")),
-        },
-    }
+    .await?;
+
+    change_password(req.email.clone(), req.new_password.clone(), client.ip, pool).await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(APPLICATION_JSON)
+        .json(Res::new(i18n::t(locale, keys::PASSWORD_CHANGED))))
 }
 
 #[post("/auth/reset/password")]
-pub async fn reset_password(pool: DPool, request: Json<ResetPasswordRequest>) -> HttpResponse {
-    let pool_clone = pool.clone();
-    let user: User = match <FindData as Find>::find_auth_user_by_email(
-        request.email.clone(),
-        pool_clone,
-    )
-    .await
-    {
-        Ok(u) => u,
-        Err(_) => return HttpResponse::InternalServerError().json(Res::new("Unknown error")),
-    };
+pub async fn reset_password(
+    pool: DPool,
+    request: ValidatedJson<ResetPasswordRequest>,
+    client: ClientInfo,
+    locale: Locale,
+) -> Result<HttpResponse, ApiError> {
+    let user: User =
+        <FindData as Find>::find_auth_user_by_email(request.email.clone(), pool.clone())
+            .await
+            .map_err(ApiError::or_not_found(keys::USER_NOT_FOUND))?;
 
-    match <Cft as ConfirmationToken>::send(
+    let user_id = user.id;
+    <Cft as ConfirmationToken>::send(
         user.username,
         request.email.clone(),
         pool.clone(),
@@ -116,26 +120,126 @@ pub async fn reset_password(pool: DPool, request: Json<ResetPasswordRequest>) ->
         false,
         TokenType::PasswordReset(request.email.clone()),
     )
-    .await
-    {
-        Ok(_) => HttpResponse::Ok().json(Res::new("Email send with verification link")),
-        Err(e) => {
-            match e {
-                VerificationTokenError::NotFound => {
-                    HttpResponse::BadRequest().json(Res::new("Token not found"))
-                }
-                VerificationTokenError::Expired => {
-                    HttpResponse::BadRequest().json(Res::new("Token expired"))
-                }
-                VerificationTokenError::TokenAlreadyExists => {
-                    HttpResponse::BadRequest().json(Res::new("Token already exists"))
-                }
-                VerificationTokenError::ServerError(_) => HttpResponse::InternalServerError()
-                    .json(Res::new("An unexpected error occured")),
-                // any other error can not ocurr due to password not being account
-                _ => HttpResponse::InternalServerError()
-                    .json(Res::new("An unexpected error occured")),
-            }
-        }
+    .await?;
+
+    audit::record_or_log(
+        &mut est_conn(pool),
+        AuditEvent::new(AuditAction::PasswordResetRequested)
+            .target(AuditTarget::User, user_id)
+            .ip(client.ip),
+    );
+
+    Ok(HttpResponse::Ok().json(Res::new(i18n::t(locale, keys::PASSWORD_RESET_EMAIL_SENT))))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use actix_web::web::Data;
+    use actix_web::{test, App};
+    use clap::Parser;
+    use diesel::r2d2::{ConnectionManager, Pool, TestCustomizer};
+    use diesel::{ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl};
+
+    use super::{email_reset_password, reset_password};
+    use crate::auth::confirmation_token::hash::hash_token;
+    use crate::auth::password;
+    use crate::constants::{EMAIL_TEMPLATES_DIR, LOCALES_DIR};
+    use crate::emails::{self, mailer, outbox};
+    use crate::schema::auth_users as user_data;
+    use crate::schema::password_reset_tokens as psr_data;
+    use crate::{i18n, settings};
+
+    const EMAIL: &str = "reset-flow@example.com";
+
+    /// Nothing the test writes is committed: the pool has one connection, held in a test
+    /// transaction. Needs the database from `.env`.
+    fn setup() -> Pool<ConnectionManager<PgConnection>> {
+        dotenv::dotenv().ok();
+        let settings = settings::Settings::load(&settings::Cli::parse_from(["test"])).unwrap();
+        let pool = Pool::builder()
+            .max_size(1)
+            .connection_customizer(Box::new(TestCustomizer))
+            .build(ConnectionManager::new(settings.database_url()))
+            .unwrap();
+        settings::init(settings);
+        i18n::init(Path::new(LOCALES_DIR)).unwrap();
+        emails::init(Path::new(EMAIL_TEMPLATES_DIR)).unwrap();
+        mailer::init(Box::new(mailer::MemoryMailer::default()));
+        pool
+    }
+
+    #[actix_web::test]
+    async fn password_reset_mails_a_link_that_changes_the_password() {
+        let pool = setup();
+        diesel::insert_into(user_data::table)
+            .values((
+                user_data::username.eq("reset-flow"),
+                user_data::email.eq(EMAIL),
+                user_data::password.eq(password::hash("Old-password1!").unwrap()),
+                user_data::created_at.eq(chrono::Utc::now().naive_utc()),
+                user_data::account_valid.eq(true),
+                user_data::locale.eq("en"),
+            ))
+            .execute(&mut pool.get().unwrap())
+            .unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(pool.clone()))
+                .service(reset_password)
+                .service(email_reset_password),
+        )
+        .await;
+
+        let response = test::call_service(
+            &app,
+            test::TestRequest::post()
+                .uri("/auth/reset/password")
+                .set_json(serde_json::json!({ "email": EMAIL }))
+                .to_request(),
+        )
+        .await;
+        assert!(response.status().is_success());
+
+        let memory = mailer::memory().unwrap();
+        outbox::deliver_due(&mut pool.get().unwrap(), memory).unwrap();
+        let email = memory
+            .sent()
+            .into_iter()
+            .find(|email| email.to_email == EMAIL)
+            .expect("no password reset email sent");
+        assert_eq!(email.to_name, "reset-flow");
+        assert_eq!(
+            email.content.subject,
+            i18n::t(i18n::Locale::En, i18n::keys::EMAIL_SUBJECT_PASSWORD_RESET)
+        );
+
+        let prefix = format!("{}/auth/reset/password/", settings::get().frontend_url);
+        let token = email.content.text.split(prefix.as_str()).nth(1).unwrap();
+        let token = token.split_whitespace().next().unwrap();
+        assert!(email.content.html.contains(token));
+        let stored = psr_data::table
+            .filter(psr_data::user_email.eq(EMAIL))
+            .select(psr_data::token)
+            .first::<String>(&mut pool.get().unwrap())
+            .unwrap();
+        assert_eq!(stored, hash_token(token));
+
+        let response = test::call_service(
+            &app,
+            test::TestRequest::put()
+                .uri(&format!("/auth/reset/password/{}", token))
+                .set_json(serde_json::json!({ "email": EMAIL, "new_password": "New-password1!" }))
+                .to_request(),
+        )
+        .await;
+        assert!(response.status().is_success());
+        let hash = user_data::table
+            .filter(user_data::email.eq(EMAIL))
+            .select(user_data::password)
+            .first::<String>(&mut pool.get().unwrap())
+            .unwrap();
+        assert!(password::verify("New-password1!", &hash).unwrap());
     }
 }
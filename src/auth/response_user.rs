@@ -1,15 +1,13 @@
-use chrono::NaiveDateTime;
-use serde::{Deserialize, Serialize};
+use serde::Serialize;
 
+use super::public_user::{PublicUser, Viewer};
 use super::UserWithRoles;
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Answer to logging in or registering, the user's own view of their account.
+#[derive(Debug, Serialize)]
 pub struct ResponseUser {
-    pub id: i32,
-    pub username: String,
-    pub email: String,
-    pub created_at: NaiveDateTime,
-    pub account_valid: bool,
+    #[serde(flatten)]
+    pub user: PublicUser,
     pub roles: Vec<String>,
     pub token: String,
 }
@@ -17,11 +15,7 @@ pub struct ResponseUser {
 impl ResponseUser {
     pub fn new(user: UserWithRoles) -> Self {
         Self {
-            id: user.id,
-            username: user.username,
-            email: user.email,
-            created_at: user.created_at,
-            account_valid: user.account_valid,
+            user: PublicUser::new(user.user, Viewer::Own),
             roles: user.roles,
             token: user.token,
         }
@@ -0,0 +1,54 @@
+use std::future::{ready, Ready};
+use std::net::{IpAddr, SocketAddr};
+
+use actix_web::dev::Payload;
+use actix_web::http::header::USER_AGENT;
+use actix_web::{FromRequest, HttpRequest};
+
+use crate::api_error::ApiError;
+use crate::constants::{DEVICE_NAME_HEADER, MAX_DEVICE_NAME_LENGTH, MAX_USER_AGENT_LENGTH};
+
+/// Where a session was started from, shown to the user in their session list.
+pub struct ClientInfo {
+    /// Name the client gives itself in the `X-Device-Name` header, e.g. "Work laptop".
+    pub device: Option<String>,
+    pub user_agent: Option<String>,
+    /// Taken from `Forwarded`/`X-Forwarded-For` when present, so only informative. `None` when
+    /// the client sent something that is not an address.
+    pub ip: Option<String>,
+}
+
+fn header(req: &HttpRequest, name: &str, max_length: usize) -> Option<String> {
+    req.headers()
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(|value| value.chars().take(max_length).collect())
+}
+
+/// Clients control the forwarding headers, only a valid address is kept. It also fits the
+/// varchar(45) columns it is stored in.
+fn parse_ip(value: &str) -> Option<String> {
+    value
+        .parse::<IpAddr>()
+        .or_else(|_| value.parse::<SocketAddr>().map(|addr| addr.ip()))
+        .ok()
+        .map(|ip| ip.to_string())
+}
+
+impl FromRequest for ClientInfo {
+    type Error = ApiError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(Ok(ClientInfo {
+            device: header(req, DEVICE_NAME_HEADER, MAX_DEVICE_NAME_LENGTH),
+            user_agent: header(req, USER_AGENT.as_str(), MAX_USER_AGENT_LENGTH),
+            ip: req
+                .connection_info()
+                .realip_remote_addr()
+                .and_then(parse_ip),
+        }))
+    }
+}
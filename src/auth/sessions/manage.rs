@@ -0,0 +1,75 @@
+use actix_web::web::Path;
+use actix_web::{delete, get, HttpResponse};
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+use crate::api_error::ApiError;
+use crate::auth::sessions;
+use crate::auth::AuthenticatedUser;
+use crate::i18n::{self, keys, Locale};
+use crate::models::Session;
+use crate::{est_conn, response::Response as Res, DPool};
+
+#[derive(Serialize)]
+struct SessionResponse {
+    id: i32,
+    device: Option<String>,
+    user_agent: Option<String>,
+    ip: Option<String>,
+    created_at: NaiveDateTime,
+    last_seen_at: NaiveDateTime,
+    /// The session of the token making the request.
+    current: bool,
+}
+
+impl SessionResponse {
+    fn new(session: Session, current_id: i32) -> Self {
+        Self {
+            id: session.id,
+            device: session.device,
+            user_agent: session.user_agent,
+            ip: session.ip,
+            created_at: session.created_at,
+            last_seen_at: session.last_seen_at,
+            current: session.id == current_id,
+        }
+    }
+}
+
+#[get("/auth/sessions")]
+pub async fn list_sessions(user: AuthenticatedUser, pool: DPool) -> Result<HttpResponse, ApiError> {
+    let sessions = sessions::active(&mut est_conn(pool), user.id)?
+        .into_iter()
+        .map(|session| SessionResponse::new(session, user.claims.sid))
+        .collect::<Vec<_>>();
+
+    Ok(HttpResponse::Ok().json(Res::new(sessions)))
+}
+
+/// Logs the user out on one device, the current one included.
+#[delete("/auth/sessions/{id}")]
+pub async fn revoke_session(
+    user: AuthenticatedUser,
+    pool: DPool,
+    path: Path<i32>,
+    locale: Locale,
+) -> Result<HttpResponse, ApiError> {
+    if sessions::revoke(&mut est_conn(pool), user.id, path.into_inner())? == 0 {
+        return Err(ApiError::NotFound(keys::SESSION_NOT_FOUND));
+    }
+
+    Ok(HttpResponse::Ok().json(Res::new(i18n::t(locale, keys::SESSION_REVOKED))))
+}
+
+/// Logs the user out everywhere but on the device making the request.
+#[delete("/auth/sessions")]
+pub async fn revoke_other_sessions(
+    user: AuthenticatedUser,
+    pool: DPool,
+    locale: Locale,
+) -> Result<HttpResponse, ApiError> {
+    let revoked = sessions::revoke_all(&mut est_conn(pool), user.id, Some(user.claims.sid))?;
+    log::info!("User {} revoked {} other sessions", user.id, revoked);
+
+    Ok(HttpResponse::Ok().json(Res::new(i18n::t(locale, keys::OTHER_SESSIONS_REVOKED))))
+}
@@ -0,0 +1,109 @@
+//! Devices a user is logged in on. Every issued JWT carries the id of its session as `sid`,
+//! revoking the session rejects all of its tokens.
+
+mod client_info;
+pub mod manage;
+
+pub use client_info::ClientInfo;
+
+use chrono::{Duration, Utc};
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+
+use crate::auth::jwt;
+use crate::constants::SESSION_LAST_SEEN_INTERVAL;
+use crate::models::{AuthUser as User, Session};
+use crate::models_insertable::NewSession;
+use crate::schema::sessions as sessions_data;
+use crate::schema::sessions::dsl as sessions_table;
+use crate::settings;
+
+/// Starts a session for `user` on the client and returns its first token.
+pub fn issue_token(
+    conn: &mut PgConnection,
+    user: &User,
+    client: ClientInfo,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let expires_at = Utc::now().naive_utc() + Duration::seconds(settings::get().jwt.lifetime);
+
+    let session_id = diesel::insert_into(sessions_table::sessions)
+        .values(NewSession {
+            user_id: user.id,
+            device: client.device,
+            user_agent: client.user_agent,
+            ip: client.ip,
+            expires_at,
+        })
+        .returning(sessions_data::id)
+        .get_result::<i32>(conn)?;
+
+    jwt::generate(conn, user, session_id)
+}
+
+/// False when the session was revoked or has expired. Otherwise records the activity, at
+/// most once per `SESSION_LAST_SEEN_INTERVAL` to spare a write on every request.
+pub fn touch(conn: &mut PgConnection, session_id: i32) -> Result<bool, DieselError> {
+    let now = Utc::now().naive_utc();
+
+    let Some(session) = sessions_table::sessions
+        .find(session_id)
+        .filter(sessions_data::revoked_at.is_null())
+        .filter(sessions_data::expires_at.gt(now))
+        .select(Session::as_select())
+        .first(conn)
+        .optional()?
+    else {
+        return Ok(false);
+    };
+
+    if now - session.last_seen_at > Duration::seconds(SESSION_LAST_SEEN_INTERVAL) {
+        diesel::update(sessions_table::sessions.find(session_id))
+            .set(sessions_data::last_seen_at.eq(now))
+            .execute(conn)?;
+    }
+
+    Ok(true)
+}
+
+/// Sessions of the user that were neither revoked nor expired, the most recently used first.
+pub fn active(conn: &mut PgConnection, user_id: i32) -> Result<Vec<Session>, DieselError> {
+    sessions_table::sessions
+        .filter(sessions_data::user_id.eq(user_id))
+        .filter(sessions_data::revoked_at.is_null())
+        .filter(sessions_data::expires_at.gt(Utc::now().naive_utc()))
+        .order(sessions_data::last_seen_at.desc())
+        .select(Session::as_select())
+        .load(conn)
+}
+
+/// Returns the number of revoked sessions, 0 when the session is not an active one of the user.
+pub fn revoke(
+    conn: &mut PgConnection,
+    user_id: i32,
+    session_id: i32,
+) -> Result<usize, DieselError> {
+    diesel::update(
+        sessions_table::sessions
+            .find(session_id)
+            .filter(sessions_data::user_id.eq(user_id))
+            .filter(sessions_data::revoked_at.is_null()),
+    )
+    .set(sessions_data::revoked_at.eq(Utc::now().naive_utc()))
+    .execute(conn)
+}
+
+/// Revokes every session of the user but `except`, e.g. the one making the request.
+pub fn revoke_all(
+    conn: &mut PgConnection,
+    user_id: i32,
+    except: Option<i32>,
+) -> Result<usize, DieselError> {
+    diesel::update(
+        sessions_table::sessions
+            .filter(sessions_data::user_id.eq(user_id))
+            .filter(sessions_data::revoked_at.is_null())
+            .filter(sessions_data::id.ne_all(except.into_iter().collect::<Vec<_>>())),
+    )
+    .set(sessions_data::revoked_at.eq(Utc::now().naive_utc()))
+    .execute(conn)
+}
@@ -1,18 +1,26 @@
+use crate::api_error::ApiError;
+use crate::i18n::{self, keys, Locale};
 use crate::response::Response as Res;
+use crate::validation::ValidatedJson;
 use crate::{auth::jwt, DPool};
-use actix_web::web::Json;
 use actix_web::{post, HttpResponse};
 use serde::Deserialize;
+use validator::Validate;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Validate)]
 struct VerifyRequest {
+    #[validate(length(min = 1))]
     token: String,
 }
 
 #[post("/auth/validate/session")]
-pub async fn verify_session(req: Json<VerifyRequest>, pool: DPool) -> HttpResponse {
-    if !jwt::verify(&req.token, pool) {
-        return HttpResponse::Unauthorized().json(Res::new("Token invalid"));
+pub async fn verify_session(
+    req: ValidatedJson<VerifyRequest>,
+    pool: DPool,
+    locale: Locale,
+) -> Result<HttpResponse, ApiError> {
+    if jwt::verify(&req.token, pool).is_none() {
+        return Err(ApiError::Unauthorized(keys::SESSION_INVALID));
     }
-    return HttpResponse::Ok().json(Res::new("Token Valid"));
+    Ok(HttpResponse::Ok().json(Res::new(i18n::t(locale, keys::SESSION_VALID))))
 }
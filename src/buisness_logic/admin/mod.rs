@@ -0,0 +1,5 @@
+//! Platform administration. Support staff (`SUPPORT`) look around and help users in, account
+//! and role changes need `ADMIN`. Every call is recorded in the audit log.
+
+pub mod users;
+pub mod workspaces;
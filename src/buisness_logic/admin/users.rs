@@ -0,0 +1,430 @@
+use std::collections::BTreeMap;
+
+use actix_web::web::{Path, Query};
+use actix_web::{delete, get, post, put, HttpResponse};
+use chrono::{NaiveDateTime, Utc};
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::api_error::ApiError;
+use crate::auth::confirmation_token::token::{Cft, ConfirmationToken, TokenEmailType, TokenType};
+use crate::auth::sessions::{self, ClientInfo};
+use crate::auth::{password, AdminUser, PublicUser, StaffUser, Viewer};
+use crate::buisness_logic::audit::{self, AuditAction, AuditEvent, AuditTarget};
+use crate::i18n::{self, keys, Locale};
+use crate::models::AuthUser as User;
+use crate::schema::auth_users as auth_users_data;
+use crate::schema::auth_users::dsl as auth_users_table;
+use crate::schema::roles as roles_data;
+use crate::schema::user_roles as user_roles_data;
+use crate::validation::ValidatedJson;
+use crate::{est_conn, response::Response as Res, DPool};
+
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 100;
+
+#[derive(Deserialize)]
+struct SearchQuery {
+    /// Part of the username or the email.
+    q: Option<String>,
+    role: Option<String>,
+    disabled: Option<bool>,
+    limit: Option<i64>,
+    /// Id of the last user of the previous page.
+    before_id: Option<i32>,
+}
+
+#[derive(Deserialize, Validate)]
+struct DisableRequest {
+    #[validate(length(max = 500))]
+    reason: Option<String>,
+}
+
+#[derive(Serialize)]
+struct UserView {
+    #[serde(flatten)]
+    user: PublicUser,
+    roles: Vec<String>,
+}
+
+impl UserView {
+    fn new(user: User, roles: Vec<String>) -> Self {
+        Self {
+            user: PublicUser::new(user, Viewer::Staff),
+            roles,
+        }
+    }
+}
+
+fn find_user(conn: &mut PgConnection, user_id: i32) -> Result<User, ApiError> {
+    auth_users_table::auth_users
+        .find(user_id)
+        .select(User::as_select())
+        .first(conn)
+        .map_err(ApiError::or_not_found(keys::USER_NOT_FOUND))
+}
+
+fn roles_of(
+    conn: &mut PgConnection,
+    user_ids: &[i32],
+) -> Result<BTreeMap<i32, Vec<String>>, DieselError> {
+    let mut roles = BTreeMap::<i32, Vec<String>>::new();
+    for (user_id, role) in user_roles_data::table
+        .inner_join(roles_data::table)
+        .filter(user_roles_data::user_id.eq_any(user_ids))
+        .select((user_roles_data::user_id, roles_data::name))
+        .order(roles_data::name)
+        .load::<(i32, String)>(conn)?
+    {
+        roles.entry(user_id).or_default().push(role);
+    }
+    Ok(roles)
+}
+
+fn message(locale: Locale, key: &'static str) -> HttpResponse {
+    HttpResponse::Ok().json(Res::new(i18n::t(locale, key)))
+}
+
+/// Newest first, e.g. `/admin/users?q=kowalski&role=SUPPORT&disabled=false&limit=20`.
+#[get("/admin/users")]
+pub async fn search_users(
+    staff: StaffUser,
+    query: Query<SearchQuery>,
+    client: ClientInfo,
+    pool: DPool,
+) -> Result<HttpResponse, ApiError> {
+    let conn = &mut est_conn(pool);
+
+    let mut users = auth_users_table::auth_users
+        .select(User::as_select())
+        .order(auth_users_data::id.desc())
+        .limit(
+            query
+                .limit
+                .unwrap_or(DEFAULT_PAGE_SIZE)
+                .clamp(1, MAX_PAGE_SIZE),
+        )
+        .into_boxed();
+
+    if let Some(q) = query.q.as_deref().map(str::trim).filter(|q| !q.is_empty()) {
+        let pattern = format!(
+            "%{}%",
+            q.replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_")
+        );
+        users = users.filter(
+            auth_users_data::username
+                .ilike(pattern.clone())
+                .or(auth_users_data::email.ilike(pattern)),
+        );
+    }
+    if let Some(role) = &query.role {
+        users = users.filter(
+            auth_users_data::id.eq_any(
+                user_roles_data::table
+                    .inner_join(roles_data::table)
+                    .filter(roles_data::name.eq(role.to_uppercase()))
+                    .select(user_roles_data::user_id),
+            ),
+        );
+    }
+    match query.disabled {
+        Some(true) => users = users.filter(auth_users_data::disabled_at.is_not_null()),
+        Some(false) => users = users.filter(auth_users_data::disabled_at.is_null()),
+        None => {}
+    }
+    if let Some(before_id) = query.before_id {
+        users = users.filter(auth_users_data::id.lt(before_id));
+    }
+
+    let users = users.load(conn)?;
+    let mut roles = roles_of(conn, &users.iter().map(|user| user.id).collect::<Vec<_>>())?;
+
+    audit::record(
+        conn,
+        AuditEvent::new(AuditAction::UsersSearched)
+            .actor(staff.id)
+            .ip(client.ip)
+            // the query is often an email address, which must not outlive the account here
+            .param("role", query.role.clone())
+            .param("results", users.len()),
+    )?;
+
+    let users = users
+        .into_iter()
+        .map(|user| {
+            let user_roles = roles.remove(&user.id).unwrap_or_default();
+            UserView::new(user, user_roles)
+        })
+        .collect::<Vec<_>>();
+
+    Ok(HttpResponse::Ok().json(Res::new(users)))
+}
+
+#[get("/admin/users/{id}")]
+pub async fn get_user(
+    staff: StaffUser,
+    path: Path<i32>,
+    client: ClientInfo,
+    pool: DPool,
+) -> Result<HttpResponse, ApiError> {
+    let conn = &mut est_conn(pool);
+
+    let user = find_user(conn, path.into_inner())?;
+    let roles = roles_of(conn, &[user.id])?
+        .remove(&user.id)
+        .unwrap_or_default();
+
+    audit::record(
+        conn,
+        AuditEvent::new(AuditAction::UserViewed)
+            .actor(staff.id)
+            .target(AuditTarget::User, user.id)
+            .ip(client.ip),
+    )?;
+
+    Ok(HttpResponse::Ok().json(Res::new(UserView::new(user, roles))))
+}
+
+/// Blocks logging in and logs the user out everywhere.
+#[post("/admin/users/{id}/disable")]
+pub async fn disable_user(
+    admin: AdminUser,
+    path: Path<i32>,
+    request: ValidatedJson<DisableRequest>,
+    client: ClientInfo,
+    pool: DPool,
+    locale: Locale,
+) -> Result<HttpResponse, ApiError> {
+    let user_id = path.into_inner();
+    if user_id == admin.id {
+        return Err(ApiError::Conflict(keys::CANNOT_MODIFY_OWN_ACCOUNT));
+    }
+
+    est_conn(pool).transaction::<_, ApiError, _>(|conn| {
+        let user = find_user(conn, user_id)?;
+        if user.disabled_at.is_none() {
+            diesel::update(auth_users_table::auth_users.find(user.id))
+                .set(auth_users_data::disabled_at.eq(Utc::now().naive_utc()))
+                .execute(conn)?;
+        }
+        sessions::revoke_all(conn, user.id, None)?;
+
+        audit::record(
+            conn,
+            AuditEvent::new(AuditAction::UserDisabled)
+                .actor(admin.id)
+                .target(AuditTarget::User, user.id)
+                .ip(client.ip)
+                .param("reason", request.reason.clone()),
+        )?;
+        Ok(())
+    })?;
+
+    log::info!("{} disabled user {}", admin.email, user_id);
+
+    Ok(message(locale, keys::USER_DISABLED))
+}
+
+#[post("/admin/users/{id}/enable")]
+pub async fn enable_user(
+    admin: AdminUser,
+    path: Path<i32>,
+    client: ClientInfo,
+    pool: DPool,
+    locale: Locale,
+) -> Result<HttpResponse, ApiError> {
+    est_conn(pool).transaction::<_, ApiError, _>(|conn| {
+        let user = find_user(conn, path.into_inner())?;
+        diesel::update(auth_users_table::auth_users.find(user.id))
+            .set(auth_users_data::disabled_at.eq(None::<NaiveDateTime>))
+            .execute(conn)?;
+
+        audit::record(
+            conn,
+            AuditEvent::new(AuditAction::UserEnabled)
+                .actor(admin.id)
+                .target(AuditTarget::User, user.id)
+                .ip(client.ip),
+        )?;
+        Ok(())
+    })?;
+
+    Ok(message(locale, keys::USER_ENABLED))
+}
+
+/// Marks the email as verified without the user following the link.
+#[post("/admin/users/{id}/verify")]
+pub async fn verify_user(
+    staff: StaffUser,
+    path: Path<i32>,
+    client: ClientInfo,
+    pool: DPool,
+    locale: Locale,
+) -> Result<HttpResponse, ApiError> {
+    est_conn(pool).transaction::<_, ApiError, _>(|conn| {
+        let user = find_user(conn, path.into_inner())?;
+        if user.account_valid {
+            return Err(ApiError::Conflict(keys::ACCOUNT_ALREADY_VERIFIED));
+        }
+        diesel::update(auth_users_table::auth_users.find(user.id))
+            .set(auth_users_data::account_valid.eq(true))
+            .execute(conn)?;
+
+        audit::record(
+            conn,
+            AuditEvent::new(AuditAction::UserVerified)
+                .actor(staff.id)
+                .target(AuditTarget::User, user.id)
+                .ip(client.ip),
+        )?;
+        Ok(())
+    })?;
+
+    Ok(message(locale, keys::ACCOUNT_VERIFIED))
+}
+
+/// Replaces the password with one nobody knows, logs the user out everywhere and emails them
+/// a link to set a new one.
+#[post("/admin/users/{id}/password-reset")]
+pub async fn force_password_reset(
+    staff: StaffUser,
+    path: Path<i32>,
+    client: ClientInfo,
+    pool: DPool,
+    locale: Locale,
+) -> Result<HttpResponse, ApiError> {
+    let unusable = password::unusable_hash()
+        .map_err(|e| ApiError::Internal(format!("Error hashing password: {}", e)))?;
+
+    let user = est_conn(pool.clone()).transaction::<_, ApiError, _>(|conn| {
+        let user = find_user(conn, path.into_inner())?;
+        diesel::update(auth_users_table::auth_users.find(user.id))
+            .set(auth_users_data::password.eq(unusable))
+            .execute(conn)?;
+        sessions::revoke_all(conn, user.id, None)?;
+
+        audit::record(
+            conn,
+            AuditEvent::new(AuditAction::PasswordResetForced)
+                .actor(staff.id)
+                .target(AuditTarget::User, user.id)
+                .ip(client.ip),
+        )?;
+        Ok(user)
+    })?;
+
+    log::info!(
+        "{} forced a password reset of user {}",
+        staff.email,
+        user.id
+    );
+
+    <Cft as ConfirmationToken>::send(
+        user.username,
+        user.email.clone(),
+        pool,
+        TokenEmailType::PasswordReset,
+        None,
+        false,
+        TokenType::PasswordReset(user.email),
+    )
+    .await?;
+
+    Ok(message(locale, keys::PASSWORD_RESET_FORCED))
+}
+
+/// Takes effect in the user's next token, e.g. after logging in again.
+#[put("/admin/users/{id}/roles/{role}")]
+pub async fn grant_role(
+    admin: AdminUser,
+    path: Path<(i32, String)>,
+    client: ClientInfo,
+    pool: DPool,
+    locale: Locale,
+) -> Result<HttpResponse, ApiError> {
+    let (user_id, role) = path.into_inner();
+    let role = role.to_uppercase();
+
+    est_conn(pool).transaction::<_, ApiError, _>(|conn| {
+        let user = find_user(conn, user_id)?;
+        let role_id = roles_data::table
+            .filter(roles_data::name.eq(&role))
+            .select(roles_data::id)
+            .first::<i32>(conn)
+            .map_err(ApiError::or_not_found(keys::ROLE_NOT_FOUND))?;
+
+        let granted = diesel::insert_into(user_roles_data::table)
+            .values((
+                user_roles_data::user_id.eq(user.id),
+                user_roles_data::role_id.eq(role_id),
+            ))
+            .on_conflict_do_nothing()
+            .execute(conn)?;
+
+        if granted > 0 {
+            audit::record(
+                conn,
+                AuditEvent::new(AuditAction::RoleGranted)
+                    .actor(admin.id)
+                    .target(AuditTarget::User, user.id)
+                    .ip(client.ip)
+                    .param("role", role.clone()),
+            )?;
+        }
+        Ok(())
+    })?;
+
+    Ok(message(locale, keys::ROLE_GRANTED))
+}
+
+/// Tokens carry the roles, so the user is logged out everywhere to drop the revoked one.
+#[delete("/admin/users/{id}/roles/{role}")]
+pub async fn revoke_role(
+    admin: AdminUser,
+    path: Path<(i32, String)>,
+    client: ClientInfo,
+    pool: DPool,
+    locale: Locale,
+) -> Result<HttpResponse, ApiError> {
+    let (user_id, role) = path.into_inner();
+    let role = role.to_uppercase();
+    if user_id == admin.id && role == "ADMIN" {
+        return Err(ApiError::Conflict(keys::CANNOT_MODIFY_OWN_ACCOUNT));
+    }
+
+    est_conn(pool).transaction::<_, ApiError, _>(|conn| {
+        let user = find_user(conn, user_id)?;
+        let revoked = diesel::delete(
+            user_roles_data::table
+                .filter(user_roles_data::user_id.eq(user.id))
+                .filter(
+                    user_roles_data::role_id.eq_any(
+                        roles_data::table
+                            .filter(roles_data::name.eq(&role))
+                            .select(roles_data::id),
+                    ),
+                ),
+        )
+        .execute(conn)?;
+        if revoked == 0 {
+            return Err(ApiError::NotFound(keys::ROLE_NOT_ASSIGNED));
+        }
+        sessions::revoke_all(conn, user.id, None)?;
+
+        audit::record(
+            conn,
+            AuditEvent::new(AuditAction::RoleRevoked)
+                .actor(admin.id)
+                .target(AuditTarget::User, user.id)
+                .ip(client.ip)
+                .param("role", role.clone()),
+        )?;
+        Ok(())
+    })?;
+
+    Ok(message(locale, keys::ROLE_REVOKED))
+}
@@ -0,0 +1,62 @@
+use actix_web::web::Path;
+use actix_web::{get, HttpResponse};
+use diesel::prelude::*;
+use serde::Serialize;
+
+use crate::api_error::ApiError;
+use crate::auth::sessions::ClientInfo;
+use crate::auth::StaffUser;
+use crate::buisness_logic::audit::{self, AuditAction, AuditEvent, AuditTarget};
+use crate::buisness_logic::workspace::list_workspace_users::{
+    get_workspace_users, WorkspaceUserResponse,
+};
+use crate::i18n::keys;
+use crate::models::Workspace;
+use crate::schema::tasks as tasks_data;
+use crate::schema::workspaces::dsl as workspaces_table;
+use crate::{est_conn, response::Response as Res, DPool};
+
+#[derive(Serialize)]
+struct WorkspaceView {
+    #[serde(flatten)]
+    workspace: Workspace,
+    members: Vec<WorkspaceUserResponse>,
+    task_count: i64,
+}
+
+/// Any workspace with its members, whether or not the staff member belongs to it.
+#[get("/admin/workspaces/{id}")]
+pub async fn get_workspace(
+    staff: StaffUser,
+    path: Path<i32>,
+    client: ClientInfo,
+    pool: DPool,
+) -> Result<HttpResponse, ApiError> {
+    let conn = &mut est_conn(pool);
+
+    let workspace = workspaces_table::workspaces
+        .find(path.into_inner())
+        .select(Workspace::as_select())
+        .first(conn)
+        .map_err(ApiError::or_not_found(keys::WORKSPACE_NOT_FOUND))?;
+    let members = get_workspace_users(conn, workspace.id).await?;
+    let task_count = tasks_data::table
+        .filter(tasks_data::workspace_id.eq(workspace.id))
+        .count()
+        .get_result::<i64>(conn)?;
+
+    audit::record(
+        conn,
+        AuditEvent::new(AuditAction::WorkspaceViewed)
+            .actor(staff.id)
+            .target(AuditTarget::Workspace, workspace.id)
+            .workspace(workspace.id)
+            .ip(client.ip),
+    )?;
+
+    Ok(HttpResponse::Ok().json(Res::new(WorkspaceView {
+        workspace,
+        members,
+        task_count,
+    })))
+}
@@ -0,0 +1,227 @@
+use std::sync::OnceLock;
+
+use actix_web::http::header::{
+    Charset, ContentDisposition, DispositionParam, DispositionType, ExtendedValue,
+    X_CONTENT_TYPE_OPTIONS,
+};
+use actix_web::web::{Path, Query};
+use actix_web::{get, HttpResponse};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use diesel::prelude::*;
+use ring::hmac;
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+
+use super::can_read;
+use super::upload::THUMBNAIL_MIME_TYPE;
+use crate::api_error::ApiError;
+use crate::auth::AuthenticatedUser;
+use crate::i18n::keys;
+use crate::models::Attachment;
+use crate::schema::attachments as attachments_data;
+use crate::settings;
+use crate::storage;
+use crate::{est_conn, response::Response as Res, DPool};
+
+/// Signs the local download links, made from `storage.url_secret`. Without it the key is random
+/// and links stop working when the API restarts.
+static URL_KEY: OnceLock<hmac::Key> = OnceLock::new();
+
+/// Which file of an attachment a link downloads.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Variant {
+    #[default]
+    Original,
+    /// See `images::process`.
+    Thumbnail,
+}
+
+impl Variant {
+    fn name(self) -> &'static str {
+        match self {
+            Variant::Original => "original",
+            Variant::Thumbnail => "thumbnail",
+        }
+    }
+
+    fn storage_key(self, attachment: &Attachment) -> Option<&str> {
+        match self {
+            Variant::Original => Some(&attachment.storage_key),
+            Variant::Thumbnail => attachment.thumbnail_key.as_deref(),
+        }
+    }
+
+    fn mime_type(self, attachment: &Attachment) -> &str {
+        match self {
+            Variant::Original => &attachment.mime_type,
+            Variant::Thumbnail => THUMBNAIL_MIME_TYPE,
+        }
+    }
+}
+
+/// The metadata with links the file and its thumbnail can be downloaded from until
+/// `url_expires_at`. The links go straight to the S3 bucket, or to `/attachments/{id}/content` of
+/// this API with local storage.
+#[derive(Serialize)]
+pub struct AttachmentResponse {
+    #[serde(flatten)]
+    attachment: Attachment,
+    url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thumbnail_url: Option<String>,
+    url_expires_at: NaiveDateTime,
+}
+
+impl AttachmentResponse {
+    pub fn new(attachment: Attachment) -> Result<Self, ApiError> {
+        let expires_at = Utc::now() + settings::get().storage.url_lifetime();
+        let url = download_url(&attachment, Variant::Original, expires_at)?;
+        let thumbnail_url = match attachment.thumbnail_key {
+            Some(_) => Some(download_url(&attachment, Variant::Thumbnail, expires_at)?),
+            None => None,
+        };
+
+        Ok(Self {
+            attachment,
+            url,
+            thumbnail_url,
+            url_expires_at: expires_at.naive_utc(),
+        })
+    }
+}
+
+fn download_url(
+    attachment: &Attachment,
+    variant: Variant,
+    expires_at: DateTime<Utc>,
+) -> Result<String, ApiError> {
+    let key = variant
+        .storage_key(attachment)
+        .ok_or(ApiError::NotFound(keys::ATTACHMENT_NOT_FOUND))?;
+    let presigned = storage::get()?.presigned_url(
+        key,
+        &content_disposition(attachment, variant).to_string(),
+        expires_at - Utc::now(),
+    );
+
+    Ok(presigned.unwrap_or_else(|| {
+        let expires = expires_at.timestamp();
+        let signature = hmac::sign(
+            url_key(),
+            signed_message(attachment.id, variant, expires).as_bytes(),
+        );
+        format!(
+            "/attachments/{}/content?variant={}&expires={}&signature={}",
+            attachment.id,
+            variant.name(),
+            expires,
+            hex::encode(signature)
+        )
+    }))
+}
+
+fn url_key() -> &'static hmac::Key {
+    URL_KEY.get_or_init(|| match &settings::get().storage.url_secret {
+        Some(secret) => hmac::Key::new(hmac::HMAC_SHA256, secret.expose().as_bytes()),
+        None => {
+            let mut secret = [0u8; 32];
+            SystemRandom::new()
+                .fill(&mut secret)
+                .expect("Failed to generate the download link key");
+            hmac::Key::new(hmac::HMAC_SHA256, &secret)
+        }
+    })
+}
+
+fn signed_message(attachment_id: i32, variant: Variant, expires: i64) -> String {
+    format!("{}:{}:{}", attachment_id, variant.name(), expires)
+}
+
+/// Images are shown in the browser, everything else is downloaded. SVG can carry scripts, so it
+/// is downloaded as well.
+fn content_disposition(attachment: &Attachment, variant: Variant) -> ContentDisposition {
+    let mime_type = variant.mime_type(attachment);
+    let inline = mime_type.starts_with("image/") && mime_type != "image/svg+xml";
+    ContentDisposition {
+        disposition: match inline {
+            true => DispositionType::Inline,
+            false => DispositionType::Attachment,
+        },
+        parameters: vec![DispositionParam::FilenameExt(ExtendedValue {
+            charset: Charset::Ext("UTF-8".to_string()),
+            language_tag: None,
+            value: attachment.file_name.as_bytes().to_vec(),
+        })],
+    }
+}
+
+fn find_attachment(conn: &mut PgConnection, id: i32) -> Result<Attachment, ApiError> {
+    attachments_data::table
+        .find(id)
+        .select(Attachment::as_select())
+        .first(conn)
+        .map_err(ApiError::or_not_found(keys::ATTACHMENT_NOT_FOUND))
+}
+
+/// Metadata and a download link, for anyone `can_read` lets see the attachment.
+#[get("/attachments/{id}")]
+pub async fn get_attachment(
+    user: AuthenticatedUser,
+    path: Path<i32>,
+    pool: DPool,
+) -> Result<HttpResponse, ApiError> {
+    let conn = &mut est_conn(pool);
+    let attachment = find_attachment(conn, path.into_inner())?;
+
+    // an attachment the user may not see does not exist for them
+    if !can_read(conn, &user, &attachment)? {
+        return Err(ApiError::NotFound(keys::ATTACHMENT_NOT_FOUND));
+    }
+
+    Ok(HttpResponse::Ok().json(Res::new(AttachmentResponse::new(attachment)?)))
+}
+
+#[derive(Deserialize)]
+struct ContentQuery {
+    #[serde(default)]
+    variant: Variant,
+    expires: i64,
+    signature: String,
+}
+
+/// Serves the file of a link from `GET /attachments/{id}`. The signature is the authorization,
+/// so the link works in an `<img>` tag without the session token.
+#[get("/attachments/{id}/content")]
+pub async fn get_attachment_content(
+    path: Path<i32>,
+    query: Query<ContentQuery>,
+    pool: DPool,
+) -> Result<HttpResponse, ApiError> {
+    let id = path.into_inner();
+    let valid = query.expires >= Utc::now().timestamp()
+        && hex::decode(&query.signature).is_ok_and(|signature| {
+            hmac::verify(
+                url_key(),
+                signed_message(id, query.variant, query.expires).as_bytes(),
+                &signature,
+            )
+            .is_ok()
+        });
+    if !valid {
+        return Err(ApiError::BadRequest(keys::ATTACHMENT_URL_INVALID));
+    }
+
+    let attachment = find_attachment(&mut est_conn(pool), id)?;
+    let key = query
+        .variant
+        .storage_key(&attachment)
+        .ok_or(ApiError::NotFound(keys::ATTACHMENT_NOT_FOUND))?;
+    let content = storage::get()?.get(key).await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(query.variant.mime_type(&attachment))
+        .insert_header(content_disposition(&attachment, query.variant))
+        .insert_header((X_CONTENT_TYPE_OPTIONS, "nosniff"))
+        .body(content))
+}
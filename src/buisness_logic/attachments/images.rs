@@ -0,0 +1,80 @@
+//! Uploaded images, cleaned up before they are stored, and their smaller renditions.
+
+use std::io::Cursor;
+
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::PngEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageDecoder, ImageFormat, ImageReader, ImageResult, Limits};
+
+use crate::constants::{
+    IMAGE_DECODE_MAX_SIZE, IMAGE_MAX_SIZE, IMAGE_QUALITY, THUMBNAIL_QUALITY, THUMBNAIL_SIZE,
+};
+
+/// Formats `process` accepts, as the clients name them.
+pub const IMAGE_MIME_TYPES: [&str; 3] = ["image/jpeg", "image/png", "image/webp"];
+/// Formats `process` writes, the only ones profile and attendance photos may have.
+pub const PHOTO_MIME_TYPES: [&str; 2] = ["image/jpeg", "image/png"];
+
+pub struct ProcessedImage {
+    pub content: Vec<u8>,
+    pub format: ImageFormat,
+    pub thumbnail: Vec<u8>,
+}
+
+/// The format `content` is in going by its magic bytes, whatever the client claimed. Only the
+/// formats of `IMAGE_MIME_TYPES` are recognized.
+pub fn detect(content: &[u8]) -> Option<ImageFormat> {
+    match content {
+        [0xFF, 0xD8, 0xFF, ..] => Some(ImageFormat::Jpeg),
+        [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, ..] => Some(ImageFormat::Png),
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Some(ImageFormat::WebP),
+        _ => None,
+    }
+}
+
+/// Decodes the image and writes it again, which leaves EXIF, GPS and any other metadata behind.
+/// The rotation EXIF asked for is applied first and images over `IMAGE_MAX_SIZE` pixels on their
+/// longer side are scaled down. PNG stays PNG and everything else becomes a JPEG, unless it is
+/// transparent, since the encoder only writes lossless WebP. Decoding is slow, call it off the
+/// async workers.
+pub fn process(content: &[u8], format: ImageFormat) -> ImageResult<ProcessedImage> {
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(IMAGE_DECODE_MAX_SIZE);
+    limits.max_image_height = Some(IMAGE_DECODE_MAX_SIZE);
+    let mut reader = ImageReader::with_format(Cursor::new(content), format);
+    reader.limits(limits);
+
+    let mut decoder = reader.into_decoder()?;
+    let orientation = decoder.orientation()?;
+    let mut image = DynamicImage::from_decoder(decoder)?;
+    image.apply_orientation(orientation);
+    if image.width() > IMAGE_MAX_SIZE || image.height() > IMAGE_MAX_SIZE {
+        image = image.resize(IMAGE_MAX_SIZE, IMAGE_MAX_SIZE, FilterType::Lanczos3);
+    }
+
+    let format = match format == ImageFormat::Png || image.color().has_alpha() {
+        true => ImageFormat::Png,
+        false => ImageFormat::Jpeg,
+    };
+    let mut processed = Vec::new();
+    match format {
+        ImageFormat::Png => image.write_with_encoder(PngEncoder::new(&mut processed))?,
+        _ => JpegEncoder::new_with_quality(&mut processed, IMAGE_QUALITY)
+            .encode_image(&image.to_rgb8())?,
+    }
+
+    Ok(ProcessedImage {
+        content: processed,
+        format,
+        thumbnail: thumbnail(&image)?,
+    })
+}
+
+/// A JPEG at most `THUMBNAIL_SIZE` pixels on its longer side.
+fn thumbnail(image: &DynamicImage) -> ImageResult<Vec<u8>> {
+    let mut thumbnail = Vec::new();
+    JpegEncoder::new_with_quality(&mut thumbnail, THUMBNAIL_QUALITY)
+        .encode_image(&image.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE).to_rgb8())?;
+    Ok(thumbnail)
+}
@@ -0,0 +1,210 @@
+//! Files kept in bytea columns before `storage`. Databases created from `schema-up.sql` never had
+//! the columns, `schema-upgrade-attachments.sql` adds the attachment columns next to them in
+//! older ones and `move_legacy_files` empties and drops them.
+
+use diesel::prelude::*;
+use diesel::sql_types::{Bool, Bytea, Integer, Nullable, Text};
+use sha2::{Digest, Sha256};
+
+use super::images;
+use super::upload::DEFAULT_MIME_TYPE;
+use crate::api_error::ApiError;
+use crate::models_insertable::NewAttachment;
+use crate::schema::attachments as attachments_data;
+use crate::storage;
+
+/// Rows are read this many at a time, the files are held in memory until stored.
+const BATCH_SIZE: i64 = 20;
+
+struct LegacyColumn {
+    table: &'static str,
+    /// The bytea column, dropped once it is empty.
+    blob: &'static str,
+    key: &'static str,
+    owner: &'static str,
+    /// `NULL` keeps the attachment out of workspaces.
+    workspace: &'static str,
+    /// Column of `table` the attachment id goes to, tasks list theirs in `task_attachments`.
+    attachment_id: Option<&'static str>,
+}
+
+const LEGACY_COLUMNS: [LegacyColumn; 5] = [
+    LegacyColumn {
+        table: "tasks",
+        blob: "description_multimedia",
+        key: "id",
+        owner: "assigner_id",
+        workspace: "workspace_id",
+        attachment_id: None,
+    },
+    LegacyColumn {
+        table: "problems",
+        blob: "problem_multimedia",
+        key: "id",
+        owner: "worker_id",
+        workspace: "NULL",
+        attachment_id: Some("attachment_id"),
+    },
+    LegacyColumn {
+        table: "full_users",
+        blob: "photo",
+        key: "user_id",
+        owner: "user_id",
+        workspace: "NULL",
+        attachment_id: Some("photo_attachment_id"),
+    },
+    LegacyColumn {
+        table: "attendance",
+        blob: "checkin_photo",
+        key: "id",
+        owner: "user_id",
+        workspace: "NULL",
+        attachment_id: Some("checkin_photo_attachment_id"),
+    },
+    LegacyColumn {
+        table: "attendance",
+        blob: "checkout_photo",
+        key: "id",
+        owner: "user_id",
+        workspace: "NULL",
+        attachment_id: Some("checkout_photo_attachment_id"),
+    },
+];
+
+#[derive(QueryableByName)]
+struct ColumnExists {
+    #[diesel(sql_type = Bool)]
+    exists: bool,
+}
+
+#[derive(QueryableByName)]
+struct LegacyFile {
+    #[diesel(sql_type = Integer)]
+    id: i32,
+    #[diesel(sql_type = Integer)]
+    owner_id: i32,
+    #[diesel(sql_type = Nullable<Integer>)]
+    workspace_id: Option<i32>,
+    #[diesel(sql_type = Bytea)]
+    content: Vec<u8>,
+}
+
+/// Copies every file left in a bytea column into storage as an attachment of the row's user and
+/// drops the column once it is empty. Files are stored as they are, without `images::process`.
+/// Without the columns there is nothing to do, so running it again changes nothing.
+pub async fn move_legacy_files(conn: &mut PgConnection) -> Result<usize, ApiError> {
+    let mut moved = 0;
+    for column in &LEGACY_COLUMNS {
+        let exists = diesel::sql_query(
+            "SELECT EXISTS (SELECT 1 FROM information_schema.columns \
+             WHERE table_name = $1 AND column_name = $2) AS exists",
+        )
+        .bind::<Text, _>(column.table)
+        .bind::<Text, _>(column.blob)
+        .get_result::<ColumnExists>(conn)?
+        .exists;
+        if !exists {
+            continue;
+        }
+
+        loop {
+            let files = diesel::sql_query(format!(
+                "SELECT {key} AS id, {owner} AS owner_id, {workspace} AS workspace_id, \
+                 {blob} AS content FROM {table} WHERE {blob} IS NOT NULL LIMIT {limit}",
+                key = column.key,
+                owner = column.owner,
+                workspace = column.workspace,
+                blob = column.blob,
+                table = column.table,
+                limit = BATCH_SIZE,
+            ))
+            .load::<LegacyFile>(conn)?;
+            if files.is_empty() {
+                break;
+            }
+            for file in files {
+                move_file(conn, column, file).await?;
+                moved += 1;
+            }
+        }
+
+        diesel::sql_query(format!(
+            "ALTER TABLE {} DROP COLUMN {}",
+            column.table, column.blob
+        ))
+        .execute(conn)?;
+    }
+    Ok(moved)
+}
+
+async fn move_file(
+    conn: &mut PgConnection,
+    column: &LegacyColumn,
+    file: LegacyFile,
+) -> Result<(), ApiError> {
+    let format = images::detect(&file.content);
+    let mime_type = format.map_or(DEFAULT_MIME_TYPE, |format| format.to_mime_type());
+    let file_name = format!(
+        "{}-{}.{}",
+        column.blob,
+        file.id,
+        format.map_or("bin", |format| format.extensions_str()[0])
+    );
+    let checksum = hex::encode(Sha256::digest(&file.content));
+    let size_bytes = file.content.len() as i64;
+
+    let storage = storage::get()?;
+    let storage_key = storage::new_key();
+    storage.put(&storage_key, file.content, mime_type).await?;
+
+    let linked = conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        let attachment_id = diesel::insert_into(attachments_data::table)
+            .values(NewAttachment {
+                owner_id: file.owner_id,
+                workspace_id: file.workspace_id,
+                storage_key: &storage_key,
+                thumbnail_key: None,
+                file_name: &file_name,
+                mime_type,
+                size_bytes,
+                checksum: &checksum,
+            })
+            .returning(attachments_data::id)
+            .get_result::<i32>(conn)?;
+
+        match column.attachment_id {
+            Some(attachment_column) => diesel::sql_query(format!(
+                "UPDATE {} SET {} = $1, {} = NULL WHERE {} = $2",
+                column.table, attachment_column, column.blob, column.key
+            ))
+            .bind::<Integer, _>(attachment_id)
+            .bind::<Integer, _>(file.id)
+            .execute(conn)?,
+            None => {
+                diesel::sql_query(
+                    "INSERT INTO task_attachments (task_id, attachment_id, position) \
+                     SELECT $1, $2, COALESCE(MAX(position) + 1, 0) FROM task_attachments \
+                     WHERE task_id = $1",
+                )
+                .bind::<Integer, _>(file.id)
+                .bind::<Integer, _>(attachment_id)
+                .execute(conn)?;
+                diesel::sql_query(format!(
+                    "UPDATE {} SET {} = NULL WHERE {} = $1",
+                    column.table, column.blob, column.key
+                ))
+                .bind::<Integer, _>(file.id)
+                .execute(conn)?
+            }
+        };
+        Ok(())
+    });
+    if let Err(e) = linked {
+        // nothing refers to the file without its row
+        if let Err(e) = storage.delete(&storage_key).await {
+            log::error!("Error deleting stored file {}: {}", storage_key, e);
+        }
+        return Err(e.into());
+    }
+    Ok(())
+}
@@ -0,0 +1,224 @@
+//! Files uploaded once and referenced by id from tasks, problems, profile photos and attendance.
+//! The contents live in `storage`, the `attachments` row keeps what is known about them.
+
+pub mod download;
+pub mod images;
+pub mod legacy;
+pub mod upload;
+
+use diesel::dsl::{exists, sql};
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+use diesel::sql_types::BigInt;
+
+use crate::api_error::ApiError;
+use crate::auth::AuthenticatedUser;
+use crate::i18n::keys;
+use crate::models::Attachment;
+use crate::schema::attachments as attachments_data;
+use crate::schema::auth_users as auth_users_data;
+use crate::schema::full_users as full_users_data;
+use crate::schema::problems as problems_data;
+use crate::schema::workspace_users as workspace_users_data;
+use crate::schema::workspaces as workspaces_data;
+use crate::settings;
+use crate::storage;
+
+/// Owns the workspace or belongs to it.
+pub fn is_workspace_member(
+    conn: &mut PgConnection,
+    user_id: i32,
+    workspace_id: i32,
+) -> Result<bool, DieselError> {
+    diesel::select(
+        exists(
+            workspaces_data::table
+                .filter(workspaces_data::id.eq(workspace_id))
+                .filter(workspaces_data::owner_id.eq(user_id)),
+        )
+        .or(exists(
+            workspace_users_data::table
+                .filter(workspace_users_data::workspace_id.eq(workspace_id))
+                .filter(workspace_users_data::user_id.eq(user_id)),
+        )),
+    )
+    .get_result(conn)
+}
+
+pub fn require_workspace_member(
+    conn: &mut PgConnection,
+    user_id: i32,
+    workspace_id: i32,
+) -> Result<(), ApiError> {
+    match is_workspace_member(conn, user_id, workspace_id)? {
+        true => Ok(()),
+        false => Err(ApiError::Forbidden(keys::NOT_WORKSPACE_MEMBER)),
+    }
+}
+
+/// Checks that `owner_id` uploaded the attachment before a record in `workspace_id` points at it.
+/// An attachment uploaded outside of any workspace moves into the record's one.
+pub fn attach(
+    conn: &mut PgConnection,
+    attachment_id: i32,
+    owner_id: i32,
+    workspace_id: Option<i32>,
+) -> Result<(), ApiError> {
+    let (current, size_bytes) = attachments_data::table
+        .find(attachment_id)
+        .filter(attachments_data::owner_id.eq(owner_id))
+        .select((attachments_data::workspace_id, attachments_data::size_bytes))
+        .first::<(Option<i32>, i64)>(conn)
+        .map_err(ApiError::or_not_found(keys::ATTACHMENT_NOT_FOUND))?;
+
+    match (current, workspace_id) {
+        (Some(current), Some(target)) if current != target => {
+            Err(ApiError::Conflict(keys::ATTACHMENT_IN_OTHER_WORKSPACE))
+        }
+        (None, Some(target)) => conn.transaction(|conn| {
+            check_quota(conn, target, size_bytes)?;
+            diesel::update(attachments_data::table.find(attachment_id))
+                .set(attachments_data::workspace_id.eq(target))
+                .execute(conn)?;
+            Ok(())
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// `attach` for profile and attendance photos, which have to be images `images::process`
+/// cleaned up on upload.
+pub fn attach_photo(
+    conn: &mut PgConnection,
+    attachment_id: i32,
+    owner_id: i32,
+) -> Result<(), ApiError> {
+    attach(conn, attachment_id, owner_id, None)?;
+    let mime_type = attachments_data::table
+        .find(attachment_id)
+        .select(attachments_data::mime_type)
+        .first::<String>(conn)?;
+    match images::PHOTO_MIME_TYPES.contains(&mime_type.as_str()) {
+        true => Ok(()),
+        false => Err(ApiError::BadRequest(keys::ATTACHMENT_NOT_A_PHOTO)),
+    }
+}
+
+/// Bytes the attachments of the workspace take, thumbnails are not counted.
+pub fn workspace_usage(conn: &mut PgConnection, workspace_id: i32) -> Result<i64, DieselError> {
+    attachments_data::table
+        .filter(attachments_data::workspace_id.eq(workspace_id))
+        .select(sql::<BigInt>("COALESCE(SUM(size_bytes), 0)::bigint"))
+        .get_result(conn)
+}
+
+/// Refuses `size_bytes` more than `storage.workspace_quota` allows. Call it in the transaction
+/// adding the bytes, the workspace row stays locked until it ends so uploads running at the same
+/// time are counted one after the other.
+pub fn check_quota(
+    conn: &mut PgConnection,
+    workspace_id: i32,
+    size_bytes: i64,
+) -> Result<(), ApiError> {
+    workspaces_data::table
+        .find(workspace_id)
+        .select(workspaces_data::id)
+        .for_update()
+        .first::<i32>(conn)
+        .map_err(ApiError::or_not_found(keys::WORKSPACE_NOT_FOUND))?;
+
+    match workspace_usage(conn, workspace_id)? + size_bytes
+        > settings::get().storage.workspace_quota
+    {
+        true => Err(ApiError::PayloadTooLarge(keys::WORKSPACE_QUOTA_EXCEEDED)),
+        false => Ok(()),
+    }
+}
+
+/// Bytes the attachments the user keeps outside of workspaces take, thumbnails are not counted.
+pub fn user_usage(conn: &mut PgConnection, user_id: i32) -> Result<i64, DieselError> {
+    attachments_data::table
+        .filter(attachments_data::owner_id.eq(user_id))
+        .filter(attachments_data::workspace_id.is_null())
+        .select(sql::<BigInt>("COALESCE(SUM(size_bytes), 0)::bigint"))
+        .get_result(conn)
+}
+
+/// `check_quota` for uploads outside of workspaces, against `storage.user_quota`. The user row
+/// is locked instead.
+pub fn check_user_quota(
+    conn: &mut PgConnection,
+    user_id: i32,
+    size_bytes: i64,
+) -> Result<(), ApiError> {
+    auth_users_data::table
+        .find(user_id)
+        .select(auth_users_data::id)
+        .for_update()
+        .first::<i32>(conn)
+        .map_err(ApiError::or_not_found(keys::USER_NOT_FOUND))?;
+
+    match user_usage(conn, user_id)? + size_bytes > settings::get().storage.user_quota {
+        true => Err(ApiError::PayloadTooLarge(keys::USER_QUOTA_EXCEEDED)),
+        false => Ok(()),
+    }
+}
+
+/// Deletes the files of an attachment whose row is gone. A file left behind is only logged,
+/// nothing refers to it any more.
+pub async fn delete_stored_files(attachment: &Attachment) {
+    let storage = match storage::get() {
+        Ok(storage) => storage,
+        Err(e) => return log::error!("Error deleting stored files: {}", e),
+    };
+    for key in std::iter::once(&attachment.storage_key).chain(&attachment.thumbnail_key) {
+        if let Err(e) = storage.delete(key).await {
+            log::error!("Error deleting stored file {}: {}", key, e);
+        }
+    }
+}
+
+/// The uploader and staff see every attachment. Otherwise it is shared with the members of its
+/// workspace, with whoever can see the profile it is the photo of, and with the mentor of the
+/// problem it was reported with.
+pub fn can_read(
+    conn: &mut PgConnection,
+    viewer: &AuthenticatedUser,
+    attachment: &Attachment,
+) -> Result<bool, DieselError> {
+    if attachment.owner_id == viewer.id || viewer.claims.is_staff() {
+        return Ok(true);
+    }
+    if let Some(workspace_id) = attachment.workspace_id {
+        if is_workspace_member(conn, viewer.id, workspace_id)? {
+            return Ok(true);
+        }
+    }
+
+    let is_profile_photo = diesel::select(exists(
+        full_users_data::table.filter(full_users_data::photo_attachment_id.eq(attachment.id)),
+    ))
+    .get_result::<bool>(conn)?;
+    if is_profile_photo {
+        let viewer_workspaces = workspace_users_data::table
+            .filter(workspace_users_data::user_id.eq(viewer.id))
+            .select(workspace_users_data::workspace_id)
+            .load::<i32>(conn)?;
+        let shares_workspace = diesel::select(exists(
+            workspace_users_data::table
+                .filter(workspace_users_data::user_id.eq(attachment.owner_id))
+                .filter(workspace_users_data::workspace_id.eq_any(viewer_workspaces)),
+        ))
+        .get_result::<bool>(conn)?;
+        if shares_workspace {
+            return Ok(true);
+        }
+    }
+
+    diesel::select(exists(
+        problems_data::table
+            .filter(problems_data::attachment_id.eq(attachment.id))
+            .filter(problems_data::mentor_id.eq(viewer.id)),
+    ))
+    .get_result(conn)
+}
@@ -0,0 +1,235 @@
+use actix_multipart::{Field, Multipart};
+use actix_web::{post, web, HttpResponse};
+use diesel::prelude::*;
+use futures_util::TryStreamExt;
+use image::ImageFormat;
+use sha2::{Digest, Sha256};
+
+use super::download::AttachmentResponse;
+use super::{check_quota, check_user_quota, images, require_workspace_member};
+use crate::api_error::ApiError;
+use crate::auth::AuthenticatedUser;
+use crate::i18n::keys;
+use crate::models::Attachment;
+use crate::models_insertable::NewAttachment;
+use crate::schema::attachments as attachments_data;
+use crate::settings;
+use crate::storage;
+use crate::{est_conn, response::Response as Res, DPool};
+
+pub const DEFAULT_MIME_TYPE: &str = "application/octet-stream";
+pub const THUMBNAIL_MIME_TYPE: &str = "image/jpeg";
+const DEFAULT_FILE_NAME: &str = "file";
+const MAX_FILE_NAME_LENGTH: usize = 255; // attachments.file_name is varchar(255)
+const MAX_MIME_TYPE_LENGTH: usize = 100; // attachments.mime_type is varchar(100)
+
+struct UploadedFile {
+    file_name: String,
+    mime_type: String,
+    content: Vec<u8>,
+}
+
+/// Stores a file sent as the `file` field of a multipart form. The optional `workspace_id` field
+/// shares it with the members of that workspace and counts it against its quota, otherwise only
+/// the uploader sees it until a record refers to it and it counts against theirs. JPEG, PNG and WebP images are stored as `images::process` leaves them.
+#[post("/attachments")]
+pub async fn upload_attachment(
+    user: AuthenticatedUser,
+    mut payload: Multipart,
+    pool: DPool,
+) -> Result<HttpResponse, ApiError> {
+    let max_size = settings::get().storage.max_upload_size;
+    let mut file = None;
+    let mut workspace_id = None;
+
+    while let Some(mut field) = payload.try_next().await.map_err(upload_invalid)? {
+        match field.name() {
+            Some("file") => {
+                let file_name = field
+                    .content_disposition()
+                    .and_then(|disposition| disposition.get_filename())
+                    .map_or_else(|| DEFAULT_FILE_NAME.to_string(), sanitize_file_name);
+                let mime_type = field
+                    .content_type()
+                    .map(|mime| mime.essence_str().to_string())
+                    .filter(|mime| mime.len() <= MAX_MIME_TYPE_LENGTH)
+                    .unwrap_or_else(|| DEFAULT_MIME_TYPE.to_string());
+                let content = read_field(&mut field, max_size).await?;
+                file = Some(UploadedFile {
+                    file_name,
+                    mime_type,
+                    content,
+                });
+            }
+            Some("workspace_id") => {
+                let value = read_field(&mut field, max_size).await?;
+                workspace_id = Some(
+                    std::str::from_utf8(&value)
+                        .ok()
+                        .and_then(|value| value.trim().parse::<i32>().ok())
+                        .ok_or(ApiError::BadRequest(keys::ATTACHMENT_UPLOAD_INVALID))?,
+                );
+            }
+            // unknown fields are skipped
+            _ => while field.try_next().await.map_err(upload_invalid)?.is_some() {},
+        }
+    }
+    let file = file.ok_or(ApiError::BadRequest(keys::ATTACHMENT_FILE_MISSING))?;
+    // the content decides, a JPEG sent as application/octet-stream is still processed
+    let format = images::detect(&file.content);
+    if format.is_none() && images::IMAGE_MIME_TYPES.contains(&file.mime_type.as_str()) {
+        return Err(ApiError::BadRequest(keys::ATTACHMENT_IMAGE_INVALID));
+    }
+
+    let conn = &mut est_conn(pool);
+    if let Some(workspace_id) = workspace_id {
+        require_workspace_member(conn, user.id, workspace_id)?;
+    }
+    // checked again when the row is inserted, this only saves processing and storing a file for
+    // nothing
+    let size_bytes = file.content.len() as i64;
+    conn.transaction(|conn| check_upload_quota(conn, user.id, workspace_id, size_bytes))?;
+
+    let (file, thumbnail) = match format {
+        Some(format) => web::block(move || process_image(file, format))
+            .await
+            .map_err(|e| ApiError::Internal(e.to_string()))??,
+        None => (file, None),
+    };
+    let checksum = hex::encode(Sha256::digest(&file.content));
+    let size_bytes = file.content.len() as i64;
+
+    let storage = storage::get()?;
+    let storage_key = storage::new_key();
+    storage
+        .put(&storage_key, file.content, &file.mime_type)
+        .await?;
+    let thumbnail_key = match thumbnail {
+        Some(thumbnail) => {
+            let key = storage::new_key();
+            match storage.put(&key, thumbnail, THUMBNAIL_MIME_TYPE).await {
+                Ok(()) => Some(key),
+                Err(e) => {
+                    log::error!("Error storing the thumbnail of {}: {}", storage_key, e);
+                    None
+                }
+            }
+        }
+        None => None,
+    };
+
+    let inserted = conn.transaction(|conn| {
+        check_upload_quota(conn, user.id, workspace_id, size_bytes)?;
+        diesel::insert_into(attachments_data::table)
+            .values(NewAttachment {
+                owner_id: user.id,
+                workspace_id,
+                storage_key: &storage_key,
+                thumbnail_key: thumbnail_key.as_deref(),
+                file_name: &file.file_name,
+                mime_type: &file.mime_type,
+                size_bytes,
+                checksum: &checksum,
+            })
+            .returning(Attachment::as_returning())
+            .get_result::<Attachment>(conn)
+            .map_err(ApiError::from)
+    });
+    let attachment = match inserted {
+        Ok(attachment) => attachment,
+        Err(err) => {
+            // nothing refers to the files without their row
+            for key in std::iter::once(&storage_key).chain(&thumbnail_key) {
+                if let Err(e) = storage.delete(key).await {
+                    log::error!("Error deleting stored file {}: {}", key, e);
+                }
+            }
+            return Err(err);
+        }
+    };
+
+    Ok(HttpResponse::Ok().json(Res::new(AttachmentResponse::new(attachment)?)))
+}
+
+fn check_upload_quota(
+    conn: &mut PgConnection,
+    user_id: i32,
+    workspace_id: Option<i32>,
+    size_bytes: i64,
+) -> Result<(), ApiError> {
+    match workspace_id {
+        Some(workspace_id) => check_quota(conn, workspace_id, size_bytes),
+        None => check_user_quota(conn, user_id, size_bytes),
+    }
+}
+
+/// Swaps the upload for what `images::process` made of it, the original with its metadata is
+/// never stored.
+fn process_image(
+    file: UploadedFile,
+    format: ImageFormat,
+) -> Result<(UploadedFile, Option<Vec<u8>>), ApiError> {
+    let processed = images::process(&file.content, format).map_err(|e| {
+        log::debug!("Invalid image upload: {}", e);
+        ApiError::BadRequest(keys::ATTACHMENT_IMAGE_INVALID)
+    })?;
+    let file_name = match processed.format == format {
+        true => file.file_name,
+        false => with_extension(&file.file_name, processed.format.extensions_str()[0]),
+    };
+
+    Ok((
+        UploadedFile {
+            file_name,
+            mime_type: processed.format.to_mime_type().to_string(),
+            content: processed.content,
+        },
+        Some(processed.thumbnail),
+    ))
+}
+
+fn upload_invalid(err: actix_multipart::MultipartError) -> ApiError {
+    log::debug!("Invalid upload: {}", err);
+    ApiError::BadRequest(keys::ATTACHMENT_UPLOAD_INVALID)
+}
+
+/// Reads the field into memory, refusing it as soon as it grows past `max_size` bytes.
+async fn read_field(field: &mut Field, max_size: usize) -> Result<Vec<u8>, ApiError> {
+    let mut content = Vec::new();
+    while let Some(chunk) = field.try_next().await.map_err(upload_invalid)? {
+        if content.len() + chunk.len() > max_size {
+            return Err(ApiError::PayloadTooLarge(keys::ATTACHMENT_TOO_LARGE));
+        }
+        content.extend_from_slice(&chunk);
+    }
+    Ok(content)
+}
+
+/// Keeps the last path segment without control characters, browsers send full paths at times.
+fn sanitize_file_name(name: &str) -> String {
+    let name = name
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| !c.is_control())
+        .take(MAX_FILE_NAME_LENGTH)
+        .collect::<String>();
+    match name.trim() {
+        "" | "." | ".." => DEFAULT_FILE_NAME.to_string(),
+        name => name.to_string(),
+    }
+}
+
+/// Replaces the extension of a name from `sanitize_file_name`, or adds one.
+fn with_extension(name: &str, extension: &str) -> String {
+    let stem = match name.rsplit_once('.') {
+        Some((stem, _)) if !stem.is_empty() => stem,
+        _ => name,
+    };
+    let stem = stem
+        .chars()
+        .take(MAX_FILE_NAME_LENGTH - extension.len() - 1)
+        .collect::<String>();
+    format!("{}.{}", stem, extension)
+}
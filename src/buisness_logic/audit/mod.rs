@@ -0,0 +1,162 @@
+//! Who did what. Events are only ever inserted, a trigger rejects updates and deletes. Call
+//! `record` inside the transaction of the change it describes so a rolled back change leaves no
+//! event behind, `record_or_log` when there is no such transaction, e.g. for a failed login.
+
+pub mod read;
+
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+use serde_json::{Map, Value};
+
+use crate::models_insertable::NewAuditEvent;
+use crate::schema::audit_events::dsl as audit_events_table;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    UsersSearched,
+    UserViewed,
+    UserDisabled,
+    UserEnabled,
+    UserVerified,
+    PasswordResetForced,
+    RoleGranted,
+    RoleRevoked,
+    WorkspaceViewed,
+    Login,
+    LoginFailed,
+    PasswordResetRequested,
+    PasswordReset,
+    AccountDeletionRequested,
+    AccountDeletionCancelled,
+    AccountDeleted,
+    WorkspaceCreated,
+    InvitationSent,
+    InvitationAccepted,
+    TaskCreated,
+    TaskStatusChanged,
+    TaskAttachmentAdded,
+    TaskAttachmentCaptionChanged,
+    TaskAttachmentsReordered,
+    TaskAttachmentRemoved,
+}
+
+impl AuditAction {
+    pub fn name(self) -> &'static str {
+        match self {
+            AuditAction::UsersSearched => "admin.users_searched",
+            AuditAction::UserViewed => "admin.user_viewed",
+            AuditAction::UserDisabled => "admin.user_disabled",
+            AuditAction::UserEnabled => "admin.user_enabled",
+            AuditAction::UserVerified => "admin.user_verified",
+            AuditAction::PasswordResetForced => "admin.password_reset_forced",
+            AuditAction::RoleGranted => "admin.role_granted",
+            AuditAction::RoleRevoked => "admin.role_revoked",
+            AuditAction::WorkspaceViewed => "admin.workspace_viewed",
+            AuditAction::Login => "auth.login",
+            AuditAction::LoginFailed => "auth.login_failed",
+            AuditAction::PasswordResetRequested => "auth.password_reset_requested",
+            AuditAction::PasswordReset => "auth.password_reset",
+            AuditAction::AccountDeletionRequested => "account.deletion_requested",
+            AuditAction::AccountDeletionCancelled => "account.deletion_cancelled",
+            AuditAction::AccountDeleted => "account.deleted",
+            AuditAction::WorkspaceCreated => "workspace.created",
+            AuditAction::InvitationSent => "workspace.invitation_sent",
+            AuditAction::InvitationAccepted => "workspace.invitation_accepted",
+            AuditAction::TaskCreated => "task.created",
+            AuditAction::TaskStatusChanged => "task.status_changed",
+            AuditAction::TaskAttachmentAdded => "task.attachment_added",
+            AuditAction::TaskAttachmentCaptionChanged => "task.attachment_caption_changed",
+            AuditAction::TaskAttachmentsReordered => "task.attachments_reordered",
+            AuditAction::TaskAttachmentRemoved => "task.attachment_removed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditTarget {
+    User,
+    Workspace,
+    Task,
+}
+
+impl AuditTarget {
+    pub fn name(self) -> &'static str {
+        match self {
+            AuditTarget::User => "user",
+            AuditTarget::Workspace => "workspace",
+            AuditTarget::Task => "task",
+        }
+    }
+}
+
+pub struct AuditEvent {
+    pub action: AuditAction,
+    pub actor_id: Option<i32>,
+    pub target: Option<(AuditTarget, i32)>,
+    pub workspace_id: Option<i32>,
+    pub ip: Option<String>,
+    pub data: Map<String, Value>,
+}
+
+impl AuditEvent {
+    pub fn new(action: AuditAction) -> Self {
+        Self {
+            action,
+            actor_id: None,
+            target: None,
+            workspace_id: None,
+            ip: None,
+            data: Map::new(),
+        }
+    }
+
+    pub fn actor(mut self, actor_id: i32) -> Self {
+        self.actor_id = Some(actor_id);
+        self
+    }
+
+    pub fn target(mut self, target: AuditTarget, id: i32) -> Self {
+        self.target = Some((target, id));
+        self
+    }
+
+    pub fn workspace(mut self, workspace_id: i32) -> Self {
+        self.workspace_id = Some(workspace_id);
+        self
+    }
+
+    pub fn ip(mut self, ip: Option<String>) -> Self {
+        self.ip = ip;
+        self
+    }
+
+    pub fn param(mut self, name: &str, value: impl Into<Value>) -> Self {
+        self.data.insert(name.to_string(), value.into());
+        self
+    }
+}
+
+pub fn record(conn: &mut PgConnection, event: AuditEvent) -> Result<(), DieselError> {
+    diesel::insert_into(audit_events_table::audit_events)
+        .values(NewAuditEvent {
+            actor_id: event.actor_id,
+            action: event.action.name(),
+            target_type: event.target.map(|(target, _)| target.name()),
+            target_id: event.target.map(|(_, id)| id),
+            workspace_id: event.workspace_id,
+            ip: event.ip.as_deref(),
+            data: Value::Object(event.data),
+        })
+        .execute(conn)?;
+
+    Ok(())
+}
+
+/// The request goes on when the event cannot be stored, the change it describes already
+/// happened or never will.
+pub fn record_or_log(conn: &mut PgConnection, event: AuditEvent) {
+    let action = event.action;
+    if let Err(e) = record(conn, event) {
+        log::error!("Error recording audit event {}: {:?}", action.name(), e);
+    }
+}
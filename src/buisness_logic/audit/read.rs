@@ -0,0 +1,89 @@
+use actix_web::web::Query;
+use actix_web::{get, HttpResponse};
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+use serde::Deserialize;
+
+use crate::api_error::ApiError;
+use crate::auth::StaffUser;
+use crate::models::AuditEvent;
+use crate::schema::audit_events as audit_events_data;
+use crate::schema::audit_events::dsl as audit_events_table;
+use crate::{est_conn, response::Response as Res, DPool};
+
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 200;
+
+#[derive(Deserialize)]
+pub struct AuditQuery {
+    actor_id: Option<i32>,
+    action: Option<String>,
+    target_type: Option<String>,
+    target_id: Option<i32>,
+    workspace_id: Option<i32>,
+    /// Events at or after this time.
+    since: Option<NaiveDateTime>,
+    /// Events before this time.
+    until: Option<NaiveDateTime>,
+    limit: Option<i64>,
+    /// Id of the last event of the previous page.
+    before_id: Option<i64>,
+}
+
+/// A page of events matching `query`, newest first. `workspace_id` overrides the one in the
+/// query for callers that may only read a single workspace.
+pub fn find_events(
+    conn: &mut PgConnection,
+    query: &AuditQuery,
+    workspace_id: Option<i32>,
+) -> Result<Vec<AuditEvent>, DieselError> {
+    let mut events = audit_events_table::audit_events
+        .select(AuditEvent::as_select())
+        .order(audit_events_data::id.desc())
+        .limit(
+            query
+                .limit
+                .unwrap_or(DEFAULT_PAGE_SIZE)
+                .clamp(1, MAX_PAGE_SIZE),
+        )
+        .into_boxed();
+
+    if let Some(actor_id) = query.actor_id {
+        events = events.filter(audit_events_data::actor_id.eq(actor_id));
+    }
+    if let Some(action) = &query.action {
+        events = events.filter(audit_events_data::action.eq(action.clone()));
+    }
+    if let Some(target_type) = &query.target_type {
+        events = events.filter(audit_events_data::target_type.eq(target_type.clone()));
+    }
+    if let Some(target_id) = query.target_id {
+        events = events.filter(audit_events_data::target_id.eq(target_id));
+    }
+    if let Some(workspace_id) = workspace_id.or(query.workspace_id) {
+        events = events.filter(audit_events_data::workspace_id.eq(workspace_id));
+    }
+    if let Some(since) = query.since {
+        events = events.filter(audit_events_data::created_at.ge(since));
+    }
+    if let Some(until) = query.until {
+        events = events.filter(audit_events_data::created_at.lt(until));
+    }
+    if let Some(before_id) = query.before_id {
+        events = events.filter(audit_events_data::id.lt(before_id));
+    }
+
+    events.load(conn)
+}
+
+/// Newest first, e.g. `/admin/audit?target_type=user&target_id=12&limit=20`.
+#[get("/admin/audit")]
+pub async fn list_audit_events(
+    _staff: StaffUser,
+    query: Query<AuditQuery>,
+    pool: DPool,
+) -> Result<HttpResponse, ApiError> {
+    let events = find_events(&mut est_conn(pool), &query, None)?;
+    Ok(HttpResponse::Ok().json(Res::new(events)))
+}
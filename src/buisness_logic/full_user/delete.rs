@@ -1 +1,253 @@
-// after 13 may
+use crate::api_error::ApiError;
+use crate::auth::confirmation_token::token::{Cft, ConfirmationToken, TokenEmailType, TokenType};
+use crate::auth::find_user::{Find, FindData};
+use crate::auth::password;
+use crate::auth::sessions::{self, ClientInfo};
+use crate::auth::AuthenticatedUser;
+use crate::buisness_logic::audit::{self, AuditAction, AuditEvent, AuditTarget};
+use crate::i18n::{self, keys, Locale};
+use crate::schema::account_deletion_requests as adr_data;
+use crate::schema::account_deletion_requests::dsl as adr_table;
+use crate::schema::attachments as attachments_data;
+use crate::schema::auth_users as auth_users_data;
+use crate::schema::auth_users::dsl as auth_users_table;
+use crate::schema::confirmation_tokens as ct_data;
+use crate::schema::confirmation_tokens::dsl as ct_table;
+use crate::schema::conversation_participants as conversation_participants_data;
+use crate::schema::conversation_participants::dsl as conversation_participants_table;
+use crate::schema::full_users as full_users_data;
+use crate::schema::full_users::dsl as full_users_table;
+use crate::schema::linked_identities as identities_data;
+use crate::schema::messages as messages_data;
+use crate::schema::messages::dsl as messages_table;
+use crate::schema::notification_preferences as preferences_data;
+use crate::schema::notifications as notifications_data;
+use crate::schema::password_reset_tokens as psr_data;
+use crate::schema::password_reset_tokens::dsl as psr_table;
+use crate::schema::user_roles as user_roles_data;
+use crate::schema::user_roles::dsl as user_roles_table;
+use crate::schema::users_citizenships as users_citizenships_data;
+use crate::schema::users_citizenships::dsl as users_citizenships_table;
+use crate::schema::workspace_invitations as workspace_invitations_data;
+use crate::schema::workspace_invitations::dsl as workspace_invitations_table;
+use crate::{models, DBPConn};
+
+use crate::{est_conn, response::Response as Res, DPool};
+use actix_web::web::Path;
+use actix_web::{post, put, HttpResponse};
+use chrono::Utc;
+use diesel::result::Error as DieselError;
+use diesel::{Connection, ExpressionMethods, QueryDsl, RunQueryDsl, SelectableHelper};
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct Token {
+    token: String,
+}
+
+#[post("/user/delete")]
+pub async fn request_account_deletion(
+    user: AuthenticatedUser,
+    client: ClientInfo,
+    pool: DPool,
+    locale: Locale,
+) -> Result<HttpResponse, ApiError> {
+    let auth_user = <FindData as Find>::find_auth_user_by_email(user.email.clone(), pool.clone())
+        .await
+        .map_err(ApiError::or_not_found(keys::USER_NOT_FOUND))?;
+
+    <Cft as ConfirmationToken>::send(
+        auth_user.username,
+        auth_user.email,
+        pool.clone(),
+        TokenEmailType::AccountDeletion,
+        None,
+        false,
+        TokenType::AccountDeletion,
+    )
+    .await?;
+
+    audit::record_or_log(
+        &mut est_conn(pool),
+        AuditEvent::new(AuditAction::AccountDeletionRequested)
+            .actor(auth_user.id)
+            .target(AuditTarget::User, auth_user.id)
+            .ip(client.ip),
+    );
+
+    Ok(HttpResponse::Ok().json(Res::new(i18n::t(locale, keys::ACCOUNT_DELETION_EMAIL_SENT))))
+}
+
+#[put("/user/delete/confirm/{token}")]
+pub async fn confirm_account_deletion(
+    token: Path<Token>,
+    pool: DPool,
+    locale: Locale,
+) -> Result<HttpResponse, ApiError> {
+    <Cft as ConfirmationToken>::confirm(token.token.clone(), TokenType::AccountDeletion, pool)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(Res::new(i18n::t(locale, keys::ACCOUNT_DELETION_SCHEDULED))))
+}
+
+#[post("/user/delete/cancel")]
+pub async fn cancel_account_deletion(
+    user: AuthenticatedUser,
+    client: ClientInfo,
+    pool: DPool,
+    locale: Locale,
+) -> Result<HttpResponse, ApiError> {
+    let conn = &mut est_conn(pool);
+
+    conn.transaction::<_, ApiError, _>(|conn| {
+        let cancelled = diesel::update(
+            adr_table::account_deletion_requests
+                .filter(adr_data::user_email.eq(&user.email))
+                .filter(adr_data::cancelled_at.is_null())
+                .filter(adr_data::completed_at.is_null()),
+        )
+        .set(adr_data::cancelled_at.eq(Utc::now().naive_utc()))
+        .execute(conn)?;
+
+        if cancelled == 0 {
+            return Err(ApiError::NotFound(keys::NO_PENDING_ACCOUNT_DELETION));
+        }
+
+        audit::record(
+            conn,
+            AuditEvent::new(AuditAction::AccountDeletionCancelled)
+                .actor(user.id)
+                .target(AuditTarget::User, user.id)
+                .ip(client.ip),
+        )?;
+        Ok(())
+    })?;
+
+    Ok(HttpResponse::Ok().json(Res::new(i18n::t(locale, keys::ACCOUNT_DELETION_CANCELLED))))
+}
+
+/// Anonymizes every account whose grace period has passed. Returns how many were processed and
+/// the attachments that went with them, whose stored files are left for the caller to delete.
+pub fn delete_due_accounts(
+    conn: &mut DBPConn,
+) -> Result<(usize, Vec<models::Attachment>), DieselError> {
+    let due_requests = adr_table::account_deletion_requests
+        .filter(adr_data::scheduled_for.le(Utc::now().naive_utc()))
+        .filter(adr_data::cancelled_at.is_null())
+        .filter(adr_data::completed_at.is_null())
+        .load::<models::AccountDeletionRequest>(conn)?;
+
+    let mut deleted = 0;
+    let mut attachments = Vec::new();
+    for request in due_requests {
+        let unusable_password = match password::unusable_hash() {
+            Ok(hash) => hash,
+            Err(e) => {
+                log::error!("Error deleting account for request {}: {}", request.id, e);
+                continue;
+            }
+        };
+        match conn
+            .transaction::<_, DieselError, _>(|c| anonymize_user(c, &request, &unusable_password))
+        {
+            Ok(user_attachments) => {
+                deleted += 1;
+                attachments.extend(user_attachments);
+            }
+            Err(err) => log::error!(
+                "Error deleting account for request {}: {:?}",
+                request.id,
+                err
+            ),
+        }
+    }
+
+    Ok((deleted, attachments))
+}
+
+fn anonymize_user(
+    conn: &mut DBPConn,
+    request: &models::AccountDeletionRequest,
+    unusable_password: &str,
+) -> Result<Vec<models::Attachment>, DieselError> {
+    let user = auth_users_table::auth_users
+        .filter(auth_users_data::email.eq(&request.user_email))
+        .first::<models::AuthUser>(conn)?;
+
+    diesel::delete(
+        users_citizenships_table::users_citizenships
+            .filter(users_citizenships_data::user_id.eq(user.id)),
+    )
+    .execute(conn)?;
+
+    diesel::delete(full_users_table::full_users.filter(full_users_data::user_id.eq(user.id)))
+        .execute(conn)?;
+
+    diesel::delete(messages_table::messages.filter(messages_data::sender_id.eq(user.id)))
+        .execute(conn)?;
+
+    diesel::delete(
+        conversation_participants_table::conversation_participants
+            .filter(conversation_participants_data::user_id.eq(user.id)),
+    )
+    .execute(conn)?;
+
+    // ON DELETE SET NULL clears the attendance photos and other records pointing at these, the
+    // caller deletes the stored files
+    let attachments =
+        diesel::delete(attachments_data::table.filter(attachments_data::owner_id.eq(user.id)))
+            .returning(models::Attachment::as_returning())
+            .get_results(conn)?;
+
+    // the provider accounts must not sign in to the anonymized row
+    diesel::delete(identities_data::table.filter(identities_data::user_id.eq(user.id)))
+        .execute(conn)?;
+
+    diesel::delete(notifications_data::table.filter(notifications_data::user_id.eq(user.id)))
+        .execute(conn)?;
+
+    diesel::delete(preferences_data::table.filter(preferences_data::user_id.eq(user.id)))
+        .execute(conn)?;
+
+    // tokens already issued stop working with their sessions
+    sessions::revoke_all(conn, user.id, None)?;
+
+    diesel::delete(user_roles_table::user_roles.filter(user_roles_data::user_id.eq(user.id)))
+        .execute(conn)?;
+
+    diesel::delete(ct_table::confirmation_tokens.filter(ct_data::user_email.eq(&user.email)))
+        .execute(conn)?;
+
+    diesel::delete(psr_table::password_reset_tokens.filter(psr_data::user_email.eq(&user.email)))
+        .execute(conn)?;
+
+    diesel::delete(
+        workspace_invitations_table::workspace_invitations
+            .filter(workspace_invitations_data::user_email.eq(&user.email)),
+    )
+    .execute(conn)?;
+
+    diesel::update(adr_table::account_deletion_requests.filter(adr_data::id.eq(request.id)))
+        .set(adr_data::completed_at.eq(Utc::now().naive_utc()))
+        .execute(conn)?;
+
+    // the event names the row only by id, the address is gone with the rest
+    audit::record(
+        conn,
+        AuditEvent::new(AuditAction::AccountDeleted)
+            .target(AuditTarget::User, user.id)
+            .param("request_id", request.id),
+    )?;
+
+    // tasks and workspaces keep pointing at the row, so it is anonymized instead of removed
+    diesel::update(auth_users_table::auth_users.filter(auth_users_data::id.eq(user.id)))
+        .set((
+            auth_users_data::username.eq(format!("deleted-user-{}", user.id)),
+            auth_users_data::email.eq(format!("deleted-user-{}@deleted.invalid", user.id)),
+            auth_users_data::password.eq(unusable_password),
+            auth_users_data::account_valid.eq(false),
+        ))
+        .execute(conn)?;
+
+    Ok(attachments)
+}
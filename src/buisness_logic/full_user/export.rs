@@ -0,0 +1,292 @@
+use crate::auth::find_user::{Find, FindData};
+use crate::auth::AuthenticatedUser;
+use crate::schema::account_deletion_requests as adr_data;
+use crate::schema::account_deletion_requests::dsl as adr_table;
+use crate::schema::attachments as attachments_data;
+use crate::schema::attendance as attendance_data;
+use crate::schema::attendance::dsl as attendance_table;
+use crate::schema::conversation_participants as conversation_participants_data;
+use crate::schema::conversation_participants::dsl as conversation_participants_table;
+use crate::schema::countries::dsl as countries_table;
+use crate::schema::full_users as full_users_data;
+use crate::schema::full_users::dsl as full_users_table;
+use crate::schema::messages as messages_data;
+use crate::schema::messages::dsl as messages_table;
+use crate::schema::notification_preferences as preferences_data;
+use crate::schema::notifications as notifications_data;
+use crate::schema::problems as problems_data;
+use crate::schema::problems::dsl as problems_table;
+use crate::schema::roles as roles_data;
+use crate::schema::roles::dsl as roles_table;
+use crate::schema::sessions as sessions_data;
+use crate::schema::tasks as tasks_data;
+use crate::schema::tasks::dsl as tasks_table;
+use crate::schema::user_roles as user_roles_data;
+use crate::schema::user_roles::dsl as user_roles_table;
+use crate::schema::users_citizenships as users_citizenships_data;
+use crate::schema::users_citizenships::dsl as users_citizenships_table;
+use crate::schema::worker_workspace_data as worker_workspace_data_data;
+use crate::schema::worker_workspace_data::dsl as worker_workspace_data_table;
+use crate::schema::workspace_invitations as workspace_invitations_data;
+use crate::schema::workspace_invitations::dsl as workspace_invitations_table;
+use crate::schema::workspace_users as workspace_users_data;
+use crate::schema::workspace_users::dsl as workspace_users_table;
+use crate::schema::workspaces as workspaces_data;
+use crate::schema::workspaces::dsl as workspaces_table;
+
+use crate::api_error::ApiError;
+use crate::i18n::keys;
+use crate::{est_conn, models, DBPConn, DPool};
+use actix_web::http::header::{ContentDisposition, DispositionParam, DispositionType};
+use actix_web::{get, HttpResponse};
+use chrono::{NaiveDateTime, Utc};
+use diesel::result::Error as DieselError;
+use diesel::{
+    BoolExpressionMethods, ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl,
+    SelectableHelper,
+};
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct AccountExport {
+    id: i32,
+    username: String,
+    email: String,
+    created_at: NaiveDateTime,
+    account_valid: bool,
+}
+
+#[derive(Serialize)]
+struct InvitationExport {
+    workspace_id: i32,
+    created_at: NaiveDateTime,
+    expires_at: NaiveDateTime,
+    confirmed_at: Option<NaiveDateTime>,
+}
+
+#[derive(Serialize)]
+struct NotificationExport {
+    event_type: String,
+    data: serde_json::Value,
+    read_at: Option<NaiveDateTime>,
+    created_at: NaiveDateTime,
+}
+
+#[derive(Serialize)]
+struct NotificationPreferenceExport {
+    event_type: String,
+    channel: String,
+}
+
+#[derive(Serialize)]
+struct DeletionRequestExport {
+    created_at: NaiveDateTime,
+    confirmed_at: Option<NaiveDateTime>,
+    scheduled_for: Option<NaiveDateTime>,
+    cancelled_at: Option<NaiveDateTime>,
+}
+
+#[derive(Serialize)]
+struct UserDataExport {
+    generated_at: NaiveDateTime,
+    account: AccountExport,
+    roles: Vec<String>,
+    profile: Option<models::FullUser>,
+    citizenships: Vec<models::Country>,
+    owned_workspaces: Vec<models::Workspace>,
+    workspace_memberships: Vec<models::WorkspaceUser>,
+    worker_workspace_data: Option<models::WorkerWorkspaceData>,
+    workspace_invitations: Vec<InvitationExport>,
+    assigned_tasks: Vec<models::Task>,
+    created_tasks: Vec<models::Task>,
+    problems: Vec<models::Problem>,
+    conversations: Vec<models::ConversationParticipant>,
+    messages: Vec<models::Message>,
+    attendance: Vec<models::Attendance>,
+    attachments: Vec<models::Attachment>,
+    sessions: Vec<models::Session>,
+    notifications: Vec<NotificationExport>,
+    notification_preferences: Vec<NotificationPreferenceExport>,
+    account_deletion_requests: Vec<DeletionRequestExport>,
+}
+
+#[get("/user/export")]
+pub async fn export_user_data(
+    user: AuthenticatedUser,
+    pool: DPool,
+) -> Result<HttpResponse, ApiError> {
+    let auth_user = <FindData as Find>::find_auth_user_by_email(user.email.clone(), pool.clone())
+        .await
+        .map_err(ApiError::or_not_found(keys::USER_NOT_FOUND))?;
+
+    let conn = &mut est_conn(pool);
+    let export = collect_user_data(conn, auth_user)?;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: vec![DispositionParam::Filename(format!(
+                "electro-vision-data-{}.json",
+                export.account.id
+            ))],
+        })
+        .json(export))
+}
+
+fn collect_user_data(
+    conn: &mut DBPConn,
+    user: models::AuthUser,
+) -> Result<UserDataExport, DieselError> {
+    let roles = user_roles_table::user_roles
+        .inner_join(roles_table::roles)
+        .filter(user_roles_data::user_id.eq(user.id))
+        .select(roles_data::name)
+        .load::<String>(conn)?;
+
+    let profile = full_users_table::full_users
+        .filter(full_users_data::user_id.eq(user.id))
+        .first::<models::FullUser>(conn)
+        .optional()?;
+
+    let citizenships = users_citizenships_table::users_citizenships
+        .filter(users_citizenships_data::user_id.eq(user.id))
+        .inner_join(countries_table::countries)
+        .select(models::Country::as_select())
+        .load::<models::Country>(conn)?;
+
+    let owned_workspaces = workspaces_table::workspaces
+        .filter(workspaces_data::owner_id.eq(user.id))
+        .select(models::Workspace::as_select())
+        .load::<models::Workspace>(conn)?;
+
+    let workspace_memberships = workspace_users_table::workspace_users
+        .filter(workspace_users_data::user_id.eq(user.id))
+        .select(models::WorkspaceUser::as_select())
+        .load::<models::WorkspaceUser>(conn)?;
+
+    let worker_workspace_data = worker_workspace_data_table::worker_workspace_data
+        .filter(worker_workspace_data_data::user_id.eq(user.id))
+        .first::<models::WorkerWorkspaceData>(conn)
+        .optional()?;
+
+    let workspace_invitations = workspace_invitations_table::workspace_invitations
+        .filter(workspace_invitations_data::user_email.eq(&user.email))
+        .load::<models::WorkspaceInvitation>(conn)?
+        .into_iter()
+        .map(|invitation| InvitationExport {
+            workspace_id: invitation.workspace_id,
+            created_at: invitation.created_at,
+            expires_at: invitation.expires_at,
+            confirmed_at: invitation.confirmed_at,
+        })
+        .collect();
+
+    let assigned_tasks = tasks_table::tasks
+        .filter(tasks_data::worker_id.eq(user.id))
+        .select(models::Task::as_select())
+        .load::<models::Task>(conn)?;
+
+    let created_tasks = tasks_table::tasks
+        .filter(tasks_data::assigner_id.eq(user.id))
+        .select(models::Task::as_select())
+        .load::<models::Task>(conn)?;
+
+    let problems = problems_table::problems
+        .filter(
+            problems_data::worker_id
+                .eq(user.id)
+                .or(problems_data::mentor_id.eq(user.id)),
+        )
+        .load::<models::Problem>(conn)?;
+
+    let conversations = conversation_participants_table::conversation_participants
+        .filter(conversation_participants_data::user_id.eq(user.id))
+        .select(models::ConversationParticipant::as_select())
+        .load::<models::ConversationParticipant>(conn)?;
+
+    let messages = messages_table::messages
+        .filter(messages_data::sender_id.eq(user.id))
+        .load::<models::Message>(conn)?;
+
+    let attendance = attendance_table::attendance
+        .filter(attendance_data::user_id.eq(user.id))
+        .load::<models::Attendance>(conn)?;
+
+    let attachments = attachments_data::table
+        .filter(attachments_data::owner_id.eq(user.id))
+        .order(attachments_data::created_at.asc())
+        .select(models::Attachment::as_select())
+        .load::<models::Attachment>(conn)?;
+
+    let sessions = sessions_data::table
+        .filter(sessions_data::user_id.eq(user.id))
+        .order(sessions_data::created_at.asc())
+        .select(models::Session::as_select())
+        .load::<models::Session>(conn)?;
+
+    let notifications = notifications_data::table
+        .filter(notifications_data::user_id.eq(user.id))
+        .order(notifications_data::id.asc())
+        .select(models::Notification::as_select())
+        .load::<models::Notification>(conn)?
+        .into_iter()
+        .map(|notification| NotificationExport {
+            event_type: notification.event_type,
+            data: notification.data,
+            read_at: notification.read_at,
+            created_at: notification.created_at,
+        })
+        .collect();
+
+    let notification_preferences = preferences_data::table
+        .filter(preferences_data::user_id.eq(user.id))
+        .select((preferences_data::event_type, preferences_data::channel))
+        .load::<(String, String)>(conn)?
+        .into_iter()
+        .map(|(event_type, channel)| NotificationPreferenceExport {
+            event_type,
+            channel,
+        })
+        .collect();
+
+    let account_deletion_requests = adr_table::account_deletion_requests
+        .filter(adr_data::user_email.eq(&user.email))
+        .load::<models::AccountDeletionRequest>(conn)?
+        .into_iter()
+        .map(|request| DeletionRequestExport {
+            created_at: request.created_at,
+            confirmed_at: request.confirmed_at,
+            scheduled_for: request.scheduled_for,
+            cancelled_at: request.cancelled_at,
+        })
+        .collect();
+
+    Ok(UserDataExport {
+        generated_at: Utc::now().naive_utc(),
+        account: AccountExport {
+            id: user.id,
+            username: user.username,
+            email: user.email,
+            created_at: user.created_at,
+            account_valid: user.account_valid,
+        },
+        roles,
+        profile,
+        citizenships,
+        owned_workspaces,
+        workspace_memberships,
+        worker_workspace_data,
+        workspace_invitations,
+        assigned_tasks,
+        created_tasks,
+        problems,
+        conversations,
+        messages,
+        attendance,
+        attachments,
+        sessions,
+        notifications,
+        notification_preferences,
+        account_deletion_requests,
+    })
+}
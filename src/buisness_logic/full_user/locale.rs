@@ -0,0 +1,40 @@
+use crate::schema::auth_users as auth_users_data;
+use crate::schema::auth_users::dsl as auth_users_table;
+
+use crate::api_error::ApiError;
+use crate::auth::AuthenticatedUser;
+use crate::i18n::{self, keys, Locale};
+use crate::validation::{validate_locale, ValidatedJson};
+use crate::{est_conn, response::Response as Res, DPool};
+use actix_web::{put, HttpResponse};
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
+use serde::Deserialize;
+use validator::Validate;
+
+#[derive(Deserialize, Validate)]
+struct UpdateLocaleRequest {
+    #[validate(custom(function = "validate_locale"))]
+    locale: String,
+}
+
+/// Sets the language of the emails sent to the user.
+#[put("/user/locale")]
+pub async fn update_locale(
+    user: AuthenticatedUser,
+    req: ValidatedJson<UpdateLocaleRequest>,
+    pool: DPool,
+) -> Result<HttpResponse, ApiError> {
+    let locale = Locale::from_tag(&req.locale).unwrap_or_default();
+    let conn = &mut est_conn(pool);
+
+    let updated =
+        diesel::update(auth_users_table::auth_users.filter(auth_users_data::email.eq(&user.email)))
+            .set(auth_users_data::locale.eq(locale.code()))
+            .execute(conn)?;
+
+    if updated == 0 {
+        return Err(ApiError::NotFound(keys::USER_NOT_FOUND));
+    }
+
+    Ok(HttpResponse::Ok().json(Res::new(i18n::t(locale, keys::LOCALE_UPDATED))))
+}
@@ -1,2 +1,7 @@
+// after 13 may
+pub mod delete;
+pub mod export;
+pub mod locale;
 pub mod read;
 pub mod register;
+pub mod update;
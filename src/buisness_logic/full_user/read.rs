@@ -10,126 +10,98 @@ use crate::schema::full_users as full_users_data;
 use crate::schema::phone_dial_codes as phone_dial_codes_data;
 use crate::schema::users_citizenships as users_citizenships_data;
 
+use crate::api_error::ApiError;
+use crate::auth::{AuthenticatedUser, PublicUser, Viewer};
+use crate::i18n::keys;
 use crate::{est_conn, response::Response as Res};
-use crate::{models, DPool};
-use actix_web::post;
-use actix_web::{get, web::Json, HttpResponse};
-use diesel::{ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl, SelectableHelper};
+use crate::{models, validation::ValidatedJson, DPool};
+use actix_web::{post, HttpResponse};
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl, SelectableHelper};
 use serde::{Deserialize, Serialize};
+use validator::Validate;
 
+/// Workspace peers see the contact details, only the user and staff see the private ones.
 #[derive(Serialize)]
 struct FullUserResponse {
-    id: i32,
-    username: String,
-    created_at: chrono::NaiveDateTime,
-    account_valid: bool,
+    #[serde(flatten)]
+    user: PublicUser,
     phone: String,
     phone_dial_code: String,
-    country_of_origin: String,
     title: Option<String>,
     education: Option<String>,
-    birth_date: chrono::NaiveDate,
-    account_bank_number: Option<String>,
-    photo: Option<Vec<u8>>,
-    citizenships: Vec<String>,
+    photo_attachment_id: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    country_of_origin: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    birth_date: Option<chrono::NaiveDate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    account_bank_number: Option<Option<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    citizenships: Option<Vec<String>>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Validate)]
 struct GetFullUserRequest {
+    #[validate(email)]
     email: String,
 }
 
 #[post("/user/list")]
-pub async fn get_full_user(req: Json<GetFullUserRequest>, pool: DPool) -> HttpResponse {
+pub async fn get_full_user(
+    viewer: AuthenticatedUser,
+    req: ValidatedJson<GetFullUserRequest>,
+    pool: DPool,
+) -> Result<HttpResponse, ApiError> {
     let conn = &mut est_conn(pool);
 
-    let auth_user = match auth_users_table::auth_users
+    let auth_user = auth_users_table::auth_users
         .filter(auth_users_data::email.eq(&req.email))
         .first::<models::AuthUser>(conn)
-        .optional()
-    {
-        Ok(Some(user)) => user,
-        Ok(None) => {
-            return HttpResponse::NotFound().json(Res::new("User not found"));
-        }
-        Err(err) => {
-            eprintln!("Error fetching auth user: {}", err);
-            return HttpResponse::InternalServerError().json(Res::new("Error fetching user"));
-        }
-    };
+        .map_err(ApiError::or_not_found(keys::USER_NOT_FOUND))?;
+
+    // strangers learn nothing, not even that the address is registered
+    let viewer = Viewer::of(conn, &viewer, auth_user.id)?;
+    if viewer == Viewer::Other {
+        return Err(ApiError::NotFound(keys::USER_NOT_FOUND));
+    }
+    let private = viewer.sees_account();
 
-    let full_user = match full_users_table::full_users
+    let full_user = full_users_table::full_users
         .filter(full_users_data::user_id.eq(auth_user.id))
         .first::<models::FullUser>(conn)
-        .optional()
-    {
-        Ok(Some(user)) => user,
-        Ok(None) => {
-            return HttpResponse::NotFound().json(Res::new("Full user details not found"));
-        }
-        Err(err) => {
-            eprintln!("Error fetching full user: {}", err);
-            return HttpResponse::InternalServerError()
-                .json(Res::new("Error fetching user details"));
-        }
-    };
+        .map_err(ApiError::or_not_found(keys::FULL_USER_NOT_FOUND))?;
 
-    let phone_dial_code = match phone_dial_codes_table::phone_dial_codes
+    let phone_dial_code = phone_dial_codes_table::phone_dial_codes
         .filter(phone_dial_codes_data::id.eq(full_user.phonde_dial_code_id))
-        .first::<models::PhoneDialCode>(conn)
-    {
-        Ok(code) => code,
-        Err(err) => {
-            eprintln!("Error fetching phone dial code: {}", err);
-            return HttpResponse::InternalServerError().json(Res::new("Error fetching phone code"));
-        }
-    };
+        .first::<models::PhoneDialCode>(conn)?;
 
-    let country_of_origin = match countries_table::countries
+    let country_of_origin = countries_table::countries
         .filter(countries_data::id.eq(full_user.country_of_origin_id))
-        .first::<models::Country>(conn)
-    {
-        Ok(country) => country,
-        Err(err) => {
-            eprintln!("Error fetching country: {}", err);
-            return HttpResponse::InternalServerError().json(Res::new("Error fetching country"));
-        }
-    };
+        .first::<models::Country>(conn)?;
 
-    let citizenships = match users_citizenships_table::users_citizenships
-        .filter(users_citizenships_data::user_id.eq(auth_user.id))
-        .inner_join(countries_table::countries)
-        .select(models::Country::as_select())
-        .load::<models::Country>(conn)
-    {
-        Ok(countries) => countries,
-        Err(err) => {
-            eprintln!("Error fetching citizenships: {}", err);
-            return HttpResponse::InternalServerError()
-                .json(Res::new("Error fetching citizenships"));
-        }
+    let citizenships = if private {
+        let countries = users_citizenships_table::users_citizenships
+            .filter(users_citizenships_data::user_id.eq(auth_user.id))
+            .inner_join(countries_table::countries)
+            .select(models::Country::as_select())
+            .load::<models::Country>(conn)?;
+        Some(countries.into_iter().map(|country| country.name).collect())
+    } else {
+        None
     };
 
-    let citizenships_countries = citizenships
-        .iter()
-        .map(|citizenship| citizenship.name.clone())
-        .collect::<Vec<String>>();
-
     let response = FullUserResponse {
-        id: auth_user.id,
-        username: auth_user.username,
-        created_at: auth_user.created_at,
-        account_valid: auth_user.account_valid,
+        user: PublicUser::new(auth_user, viewer),
         phone: full_user.phone,
         phone_dial_code: phone_dial_code.code,
-        country_of_origin: country_of_origin.name,
         title: full_user.title,
         education: full_user.education,
-        birth_date: full_user.birth_date,
-        account_bank_number: full_user.account_bank_number,
-        photo: full_user.photo,
-        citizenships: citizenships_countries,
+        photo_attachment_id: full_user.photo_attachment_id,
+        country_of_origin: private.then_some(country_of_origin.name),
+        birth_date: private.then_some(full_user.birth_date),
+        account_bank_number: private.then_some(full_user.account_bank_number),
+        citizenships,
     };
 
-    HttpResponse::Ok().json(Res::new(response))
+    Ok(HttpResponse::Ok().json(Res::new(response)))
 }
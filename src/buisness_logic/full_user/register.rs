@@ -7,116 +7,90 @@ use crate::schema::phone_dial_codes::dsl as phone_dial_codes_table;
 use crate::schema::users_citizenships as users_citizenships_data;
 use crate::schema::users_citizenships::dsl as users_citizenships_table;
 
-use crate::{est_conn, response::Response as Res};
-use actix_web::{post, web::Json, HttpResponse};
+use crate::api_error::ApiError;
+use crate::buisness_logic::attachments;
+use crate::i18n::{self, keys, Locale};
+use crate::{est_conn, response::Response as Res, DBPConn};
+use actix_web::{post, HttpResponse};
 use chrono::NaiveDate;
 use diesel::result::Error as DieselError;
-use diesel::{Connection, ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl};
+use diesel::{Connection, ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl};
 use serde::Deserialize;
+use validator::{Validate, ValidationError};
 
 use crate::{
     auth::find_user::{Find, FindData},
-    models, DPool,
+    models,
+    validation::{
+        on_field, validate_birth_date, validate_iban, validate_phone_number_for_dial_code,
+        ValidatedJson,
+    },
+    DPool,
 };
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Validate)]
+#[validate(schema(
+    function = "validate_register_phone_number",
+    skip_on_field_errors = false
+))]
 struct RegisterFullUserRequest {
     phone_number: String,
+    #[validate(length(min = 1, max = 6))]
     phone_dial_code: Option<String>,
+    #[validate(length(min = 1, max = 50))]
     country_of_origin: Option<String>,
     // forgor what it is, lets say mrs, ms yap yap,
+    #[validate(length(max = 50))]
     title: Option<String>,
+    #[validate(length(max = 100))]
     education: Option<String>,
+    #[validate(custom(function = "validate_birth_date"))]
     birth_date: NaiveDate,
+    #[validate(custom(function = "validate_iban"))]
     account_bank_number: Option<String>,
     // idk types of files
+    #[validate(email)]
     email: String,
-    photo: Option<Vec<u8>>,
+    photo_attachment_id: Option<i32>,
     citizenships_countries_iso3: Option<Vec<String>>,
 }
 
+fn validate_register_phone_number(req: &RegisterFullUserRequest) -> Result<(), ValidationError> {
+    validate_phone_number_for_dial_code(&req.phone_number, req.phone_dial_code.as_deref())
+        .map_err(|err| on_field(err, "phone_number"))
+}
+
 #[post("/user/register")]
-pub async fn register_full_user(req: Json<RegisterFullUserRequest>, pool: DPool) -> HttpResponse {
+pub async fn register_full_user(
+    req: ValidatedJson<RegisterFullUserRequest>,
+    pool: DPool,
+    locale: Locale,
+) -> Result<HttpResponse, ApiError> {
     let conn = &mut est_conn(pool.clone());
 
-    let auth_user =
-        match <FindData as Find>::find_auth_user_by_email(req.email.clone(), pool.clone()).await {
-            Ok(user) => user,
-            Err(err) => {
-                eprintln!("Err seraching for usr {:?}", err);
-                return HttpResponse::InternalServerError()
-                    .json(Res::new("No user found for that email"));
-            }
-        };
-
-    let mut citizenship_country_ids = vec![];
+    let auth_user = <FindData as Find>::find_auth_user_by_email(req.email.clone(), pool.clone())
+        .await
+        .map_err(ApiError::or_not_found(keys::USER_NOT_FOUND))?;
 
-    if let Some(countries) = &req.citizenships_countries_iso3 {
-        for iso3_code in countries {
-            match countries_table::countries
-                .filter(countries_data::iso3.eq(iso3_code))
-                .select(countries_data::id)
-                .first::<i32>(conn)
-                .optional()
-            {
-                Ok(Some(id)) => citizenship_country_ids.push(id),
-                Ok(None) => {
-                    return HttpResponse::BadRequest().json(Res::new(&format!(
-                        "Invalid citizenship country: {}",
-                        iso3_code
-                    )));
-                }
-                Err(err) => {
-                    eprintln!("DB error fetching citizenship country: {:?}", err);
-                    return HttpResponse::InternalServerError()
-                        .json(Res::new("Failed to fetch citizenship countries"));
-                }
-            }
-        }
-    }
+    let citizenship_country_ids = match &req.citizenships_countries_iso3 {
+        Some(countries) => find_citizenship_country_ids(conn, countries)?,
+        None => vec![],
+    };
 
-    let country_id = if let Some(country) = &req.country_of_origin {
-        match countries_table::countries
-            .filter(countries_data::name.eq(country))
-            .select(countries_data::id)
-            .first::<i32>(conn)
-            .optional()
-        {
-            Ok(Some(id)) => id,
-            Ok(None) => {
-                return HttpResponse::BadRequest().json(Res::new("Invalid country of origin"))
-            }
-            Err(err) => {
-                eprintln!("DB error fetching country: {:?}", err);
-                return HttpResponse::InternalServerError()
-                    .json(Res::new("Failed to fetch country"));
-            }
-        }
-    } else {
-        return HttpResponse::BadRequest().json(Res::new("Missing country of origin"));
+    let country_id = match &req.country_of_origin {
+        Some(country) => find_country_id(conn, country)?,
+        None => return Err(ApiError::BadRequest(keys::MISSING_COUNTRY_OF_ORIGIN)),
     };
 
-    let dial_code_id = if let Some(code) = &req.phone_dial_code {
-        match phone_dial_codes_table::phone_dial_codes
-            .filter(phone_dial_codes_data::code.eq(code))
-            .select(phone_dial_codes_data::id)
-            .first::<i32>(conn)
-            .optional()
-        {
-            Ok(Some(id)) => id,
-            Ok(None) => {
-                return HttpResponse::BadRequest().json(Res::new("Invalid phone dial code"))
-            }
-            Err(err) => {
-                eprintln!("DB error fetching dial code: {:?}", err);
-                return HttpResponse::InternalServerError()
-                    .json(Res::new("Failed to fetch phone dial code"));
-            }
-        }
-    } else {
-        return HttpResponse::BadRequest().json(Res::new("Missing phone dial code"));
+    let dial_code_id = match &req.phone_dial_code {
+        Some(code) => find_dial_code_id(conn, code)?,
+        None => return Err(ApiError::BadRequest(keys::MISSING_DIAL_CODE)),
     };
 
+    if let Some(attachment_id) = req.photo_attachment_id {
+        attachments::attach_photo(conn, attachment_id, auth_user.id)?;
+    }
+
     let user = models::FullUser {
         user_id: auth_user.id,
         phone: req.phone_number.clone(),
@@ -126,31 +100,74 @@ pub async fn register_full_user(req: Json<RegisterFullUserRequest>, pool: DPool)
         education: req.education.clone(),
         birth_date: req.birth_date.clone(),
         account_bank_number: req.account_bank_number.clone(),
-        photo: req.photo.clone(),
+        photo_attachment_id: req.photo_attachment_id,
     };
 
-    let result = conn.transaction::<_, DieselError, _>(|c| {
+    conn.transaction::<_, DieselError, _>(|c| {
         diesel::insert_into(full_users_table::full_users)
             .values(&user)
             .execute(c)?;
 
-        for cid in citizenship_country_ids {
-            let citizenship = models::UserCitizenship {
-                user_id: auth_user.id,
-                country_id: cid,
-            };
-            diesel::insert_into(users_citizenships_table::users_citizenships)
-                .values(&citizenship)
-                .execute(c)?;
-        }
-        Ok(())
-    });
-
-    match result {
-        Ok(_) => HttpResponse::Ok().json(Res::new("User registered successfully")),
-        Err(err) => {
-            eprintln!("DB error registering user: {:?}", err);
-            HttpResponse::InternalServerError().json(Res::new("Failed to register user"))
-        }
+        insert_citizenships(c, auth_user.id, &citizenship_country_ids)
+    })
+    .map_err(ApiError::or_conflict(keys::FULL_USER_ALREADY_EXISTS))?;
+
+    Ok(HttpResponse::Ok().json(Res::new(i18n::t(locale, keys::FULL_USER_REGISTERED))))
+}
+
+pub(super) fn find_citizenship_country_ids(
+    conn: &mut DBPConn,
+    iso3_codes: &[String],
+) -> Result<Vec<i32>, ApiError> {
+    iso3_codes
+        .iter()
+        .map(|iso3_code| {
+            countries_table::countries
+                .filter(countries_data::iso3.eq(iso3_code))
+                .select(countries_data::id)
+                .first::<i32>(conn)
+                .map_err(invalid(keys::INVALID_CITIZENSHIP))
+        })
+        .collect()
+}
+
+pub(super) fn find_country_id(conn: &mut DBPConn, country: &str) -> Result<i32, ApiError> {
+    countries_table::countries
+        .filter(countries_data::name.eq(country))
+        .select(countries_data::id)
+        .first::<i32>(conn)
+        .map_err(invalid(keys::INVALID_COUNTRY_OF_ORIGIN))
+}
+
+pub(super) fn find_dial_code_id(conn: &mut DBPConn, code: &str) -> Result<i32, ApiError> {
+    phone_dial_codes_table::phone_dial_codes
+        .filter(phone_dial_codes_data::code.eq(code))
+        .select(phone_dial_codes_data::id)
+        .first::<i32>(conn)
+        .map_err(invalid(keys::INVALID_DIAL_CODE))
+}
+
+/// Unknown lookup values are a client mistake, not a missing resource.
+fn invalid(code: &'static str) -> impl FnOnce(DieselError) -> ApiError {
+    move |err| match err {
+        DieselError::NotFound => ApiError::BadRequest(code),
+        err => ApiError::Database(err),
+    }
+}
+
+pub(super) fn insert_citizenships(
+    conn: &mut PgConnection,
+    user_id: i32,
+    country_ids: &[i32],
+) -> Result<(), DieselError> {
+    for cid in country_ids {
+        let citizenship = models::UserCitizenship {
+            user_id,
+            country_id: *cid,
+        };
+        diesel::insert_into(users_citizenships_table::users_citizenships)
+            .values(&citizenship)
+            .execute(conn)?;
     }
+    Ok(())
 }
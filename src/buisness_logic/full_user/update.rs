@@ -1 +1,163 @@
-// after 13 may
+use crate::schema::full_users as full_users_data;
+use crate::schema::full_users::dsl as full_users_table;
+use crate::schema::phone_dial_codes as phone_dial_codes_data;
+use crate::schema::users_citizenships as users_citizenships_data;
+use crate::schema::users_citizenships::dsl as users_citizenships_table;
+
+use crate::api_error::ApiError;
+use crate::auth::find_user::{Find, FindData};
+use crate::auth::AuthenticatedUser;
+use crate::buisness_logic::attachments;
+use crate::i18n::{self, keys, Locale};
+use crate::models_insertable::FullUserChangeset;
+use crate::validation::{validate_iban, validate_phone_number_for_dial_code, ValidatedJson};
+use crate::{est_conn, response::Response as Res, DPool};
+use actix_web::{put, HttpResponse};
+use diesel::result::Error as DieselError;
+use diesel::{Connection, ExpressionMethods, PgConnection, QueryDsl, RunQueryDsl};
+use serde::Deserialize;
+use validator::{Validate, ValidationErrors};
+
+use super::register::{
+    find_citizenship_country_ids, find_country_id, find_dial_code_id, insert_citizenships,
+};
+
+/// Fields left out stay as they are, `null` clears the optional ones.
+#[derive(Deserialize, Validate)]
+struct UpdateFullUserRequest {
+    phone_number: Option<String>,
+    #[validate(length(min = 1, max = 6))]
+    phone_dial_code: Option<String>,
+    #[validate(length(min = 1, max = 50))]
+    country_of_origin: Option<String>,
+    #[validate(length(max = 50))]
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    title: Option<Option<String>>,
+    #[validate(length(max = 100))]
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    education: Option<Option<String>>,
+    #[validate(custom(function = "validate_iban"))]
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    account_bank_number: Option<Option<String>>,
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    photo_attachment_id: Option<Option<i32>>,
+    citizenships_countries_iso3: Option<Vec<String>>,
+}
+
+/// The phone number has to fit its dial code, so whichever half the request leaves out is
+/// taken from the stored profile and the pair is checked together.
+fn validate_phone_number(
+    conn: &mut PgConnection,
+    user_id: i32,
+    req: &UpdateFullUserRequest,
+) -> Result<(), ApiError> {
+    if req.phone_number.is_none() && req.phone_dial_code.is_none() {
+        return Ok(());
+    }
+
+    let (stored_phone, stored_dial_code) = full_users_table::full_users
+        .inner_join(phone_dial_codes_data::table)
+        .filter(full_users_data::user_id.eq(user_id))
+        .select((full_users_data::phone, phone_dial_codes_data::code))
+        .first::<(String, String)>(conn)
+        .map_err(ApiError::or_not_found(keys::FULL_USER_NOT_FOUND))?;
+
+    let phone = req.phone_number.as_deref().unwrap_or(&stored_phone);
+    let dial_code = req.phone_dial_code.as_deref().unwrap_or(&stored_dial_code);
+    validate_phone_number_for_dial_code(phone, Some(dial_code)).map_err(|err| {
+        let mut errors = ValidationErrors::new();
+        errors.add("phone_number", err);
+        ApiError::Validation(errors)
+    })
+}
+
+#[put("/user/update")]
+pub async fn update_full_user(
+    user: AuthenticatedUser,
+    req: ValidatedJson<UpdateFullUserRequest>,
+    pool: DPool,
+    locale: Locale,
+) -> Result<HttpResponse, ApiError> {
+    let conn = &mut est_conn(pool.clone());
+
+    let auth_user = <FindData as Find>::find_auth_user_by_email(user.email.clone(), pool.clone())
+        .await
+        .map_err(ApiError::or_not_found(keys::USER_NOT_FOUND))?;
+
+    let citizenship_country_ids = match &req.citizenships_countries_iso3 {
+        Some(countries) => Some(find_citizenship_country_ids(conn, countries)?),
+        None => None,
+    };
+
+    let country_of_origin_id = match &req.country_of_origin {
+        Some(country) => Some(find_country_id(conn, country)?),
+        None => None,
+    };
+
+    validate_phone_number(conn, auth_user.id, &req)?;
+
+    let phonde_dial_code_id = match &req.phone_dial_code {
+        Some(code) => Some(find_dial_code_id(conn, code)?),
+        None => None,
+    };
+
+    if let Some(Some(attachment_id)) = req.photo_attachment_id {
+        attachments::attach_photo(conn, attachment_id, auth_user.id)?;
+    }
+
+    let changes = FullUserChangeset {
+        phone: req.phone_number.clone(),
+        phonde_dial_code_id,
+        country_of_origin_id,
+        title: req.title.clone(),
+        education: req.education.clone(),
+        account_bank_number: req.account_bank_number.clone(),
+        photo_attachment_id: req.photo_attachment_id,
+    };
+
+    let has_profile_changes = changes.phone.is_some()
+        || changes.phonde_dial_code_id.is_some()
+        || changes.country_of_origin_id.is_some()
+        || changes.title.is_some()
+        || changes.education.is_some()
+        || changes.account_bank_number.is_some()
+        || changes.photo_attachment_id.is_some();
+
+    if !has_profile_changes && citizenship_country_ids.is_none() {
+        return Err(ApiError::BadRequest(keys::NOTHING_TO_UPDATE));
+    }
+
+    conn.transaction::<_, DieselError, _>(|c| {
+        let updated = if has_profile_changes {
+            diesel::update(
+                full_users_table::full_users.filter(full_users_data::user_id.eq(auth_user.id)),
+            )
+            .set(&changes)
+            .execute(c)?
+        } else {
+            full_users_table::full_users
+                .filter(full_users_data::user_id.eq(auth_user.id))
+                .count()
+                .get_result::<i64>(c)? as usize
+        };
+
+        if updated == 0 {
+            return Err(DieselError::NotFound);
+        }
+
+        if let Some(country_ids) = &citizenship_country_ids {
+            diesel::delete(
+                users_citizenships_table::users_citizenships
+                    .filter(users_citizenships_data::user_id.eq(auth_user.id)),
+            )
+            .execute(c)?;
+
+            insert_citizenships(c, auth_user.id, country_ids)?;
+        }
+
+        Ok(())
+    })
+    .map_err(ApiError::or_not_found(keys::FULL_USER_NOT_FOUND))?;
+
+    Ok(HttpResponse::Ok().json(Res::new(i18n::t(locale, keys::FULL_USER_UPDATED))))
+}
@@ -1,2 +1,6 @@
+pub mod admin;
+pub mod attachments;
+pub mod audit;
 pub mod full_user;
+pub mod notifications;
 pub mod workspace;
@@ -0,0 +1,257 @@
+pub mod preferences;
+pub mod read;
+
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+use diesel::PgConnection;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::emails::mailer::OutgoingEmail;
+use crate::emails::{self, outbox, EmailType, NotificationEmailContext};
+use crate::i18n::{self, keys, Locale};
+use crate::models::AuthUser;
+use crate::models_insertable::NewNotification;
+use crate::schema::auth_users::dsl as auth_users_table;
+use crate::schema::notification_preferences as preferences_data;
+use crate::schema::notification_preferences::dsl as preferences_table;
+use crate::schema::notifications::dsl as notifications_table;
+use crate::settings;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationEvent {
+    TaskAssigned,
+    TaskStatusChanged,
+    TaskDueSoon,
+    TaskOverdue,
+    InvitationAccepted,
+    ProblemReported,
+}
+
+impl NotificationEvent {
+    pub const ALL: [NotificationEvent; 6] = [
+        NotificationEvent::TaskAssigned,
+        NotificationEvent::TaskStatusChanged,
+        NotificationEvent::TaskDueSoon,
+        NotificationEvent::TaskOverdue,
+        NotificationEvent::InvitationAccepted,
+        NotificationEvent::ProblemReported,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            NotificationEvent::TaskAssigned => "task_assigned",
+            NotificationEvent::TaskStatusChanged => "task_status_changed",
+            NotificationEvent::TaskDueSoon => "task_due_soon",
+            NotificationEvent::TaskOverdue => "task_overdue",
+            NotificationEvent::InvitationAccepted => "invitation_accepted",
+            NotificationEvent::ProblemReported => "problem_reported",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<NotificationEvent> {
+        NotificationEvent::ALL
+            .into_iter()
+            .find(|event| event.name() == name)
+    }
+
+    fn title_key(self) -> &'static str {
+        match self {
+            NotificationEvent::TaskAssigned => keys::NOTIFICATION_TASK_ASSIGNED_TITLE,
+            NotificationEvent::TaskStatusChanged => keys::NOTIFICATION_TASK_STATUS_CHANGED_TITLE,
+            NotificationEvent::TaskDueSoon => keys::NOTIFICATION_TASK_DUE_SOON_TITLE,
+            NotificationEvent::TaskOverdue => keys::NOTIFICATION_TASK_OVERDUE_TITLE,
+            NotificationEvent::InvitationAccepted => keys::NOTIFICATION_INVITATION_ACCEPTED_TITLE,
+            NotificationEvent::ProblemReported => keys::NOTIFICATION_PROBLEM_REPORTED_TITLE,
+        }
+    }
+
+    fn message_key(self) -> &'static str {
+        match self {
+            NotificationEvent::TaskAssigned => keys::NOTIFICATION_TASK_ASSIGNED_MESSAGE,
+            NotificationEvent::TaskStatusChanged => keys::NOTIFICATION_TASK_STATUS_CHANGED_MESSAGE,
+            NotificationEvent::TaskDueSoon => keys::NOTIFICATION_TASK_DUE_SOON_MESSAGE,
+            NotificationEvent::TaskOverdue => keys::NOTIFICATION_TASK_OVERDUE_MESSAGE,
+            NotificationEvent::InvitationAccepted => keys::NOTIFICATION_INVITATION_ACCEPTED_MESSAGE,
+            NotificationEvent::ProblemReported => keys::NOTIFICATION_PROBLEM_REPORTED_MESSAGE,
+        }
+    }
+
+    /// Used until the user picks a channel for the event.
+    pub fn default_channel(self) -> Channel {
+        match self {
+            NotificationEvent::TaskAssigned
+            | NotificationEvent::TaskDueSoon
+            | NotificationEvent::TaskOverdue => Channel::Both,
+            _ => Channel::InApp,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Channel {
+    InApp,
+    Email,
+    Both,
+}
+
+impl Channel {
+    pub fn name(self) -> &'static str {
+        match self {
+            Channel::InApp => "in_app",
+            Channel::Email => "email",
+            Channel::Both => "both",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Channel> {
+        [Channel::InApp, Channel::Email, Channel::Both]
+            .into_iter()
+            .find(|channel| channel.name() == name)
+    }
+
+    fn in_app(self) -> bool {
+        matches!(self, Channel::InApp | Channel::Both)
+    }
+
+    fn email(self) -> bool {
+        matches!(self, Channel::Email | Channel::Both)
+    }
+}
+
+/// A domain event for one recipient, `params` fill the placeholders of the event messages.
+pub struct Notification {
+    pub event: NotificationEvent,
+    /// Frontend path, e.g. `/workspace/1/tasks/2`.
+    pub link: String,
+    pub params: Vec<(&'static str, String)>,
+}
+
+impl Notification {
+    pub fn new(event: NotificationEvent, link: String) -> Self {
+        Self {
+            event,
+            link,
+            params: Vec::new(),
+        }
+    }
+
+    pub fn param(mut self, name: &'static str, value: impl Into<String>) -> Self {
+        self.params.push((name, value.into()));
+        self
+    }
+
+    fn data(&self) -> Value {
+        let mut data = self
+            .params
+            .iter()
+            .map(|(name, value)| (name.to_string(), Value::String(value.clone())))
+            .collect::<Map<_, _>>();
+        data.insert("link".to_string(), Value::String(self.link.clone()));
+        Value::Object(data)
+    }
+}
+
+/// Title and message of a stored notification in `locale`.
+pub fn render(event_type: &str, data: &Value, locale: Locale) -> (String, String) {
+    let Some(event) = NotificationEvent::from_name(event_type) else {
+        return (event_type.to_string(), String::new());
+    };
+
+    let params = data
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter_map(|(name, value)| Some((name.as_str(), value.as_str()?.to_string())))
+        .collect::<Vec<_>>();
+
+    (
+        i18n::interpolate(i18n::t(locale, event.title_key()), &params),
+        i18n::interpolate(i18n::t(locale, event.message_key()), &params),
+    )
+}
+
+/// The channel `user_id` picked for `event`, or its default.
+pub fn channel(
+    conn: &mut PgConnection,
+    user_id: i32,
+    event: NotificationEvent,
+) -> Result<Channel, DieselError> {
+    let channel = preferences_table::notification_preferences
+        .filter(preferences_data::user_id.eq(user_id))
+        .filter(preferences_data::event_type.eq(event.name()))
+        .select(preferences_data::channel)
+        .first::<String>(conn)
+        .optional()?;
+
+    Ok(channel
+        .as_deref()
+        .and_then(Channel::from_name)
+        .unwrap_or(event.default_channel()))
+}
+
+/// Delivers `notification` to `user_id` through the channels they picked,
+/// call it inside the transaction of the change it reports.
+pub fn notify(
+    conn: &mut PgConnection,
+    user_id: i32,
+    notification: &Notification,
+) -> Result<(), DieselError> {
+    let channel = channel(conn, user_id, notification.event)?;
+    let data = notification.data();
+
+    if channel.in_app() {
+        diesel::insert_into(notifications_table::notifications)
+            .values(NewNotification {
+                user_id,
+                event_type: notification.event.name(),
+                data: data.clone(),
+            })
+            .execute(conn)?;
+    }
+
+    if channel.email() {
+        let user = auth_users_table::auth_users
+            .find(user_id)
+            .select(AuthUser::as_select())
+            .first(conn)?;
+        let locale = Locale::from_tag(&user.locale).unwrap_or_default();
+        let (title, message) = render(notification.event.name(), &data, locale);
+
+        let rendered = emails::render(
+            EmailType::Notification,
+            locale,
+            &NotificationEmailContext {
+                username: user.username.clone(),
+                link: format!("{}{}", settings::get().frontend_url, notification.link),
+                title: title.clone(),
+                message,
+            },
+        );
+
+        match rendered {
+            Ok(mut content) => {
+                content.subject = i18n::interpolate(&content.subject, &[("title", title)]);
+                outbox::enqueue(
+                    conn,
+                    EmailType::Notification,
+                    &OutgoingEmail {
+                        to_name: user.username,
+                        to_email: user.email,
+                        content,
+                    },
+                )?;
+            }
+            // the change itself must not fail because of a broken template
+            Err(e) => log::error!(
+                "rendering {} notification email failed: {:?}",
+                notification.event.name(),
+                e
+            ),
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,76 @@
+use actix_web::{get, put, HttpResponse};
+use diesel::upsert::excluded;
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use super::{Channel, NotificationEvent};
+use crate::api_error::ApiError;
+use crate::auth::AuthenticatedUser;
+use crate::i18n::{self, keys, Locale};
+use crate::schema::notification_preferences as preferences_data;
+use crate::schema::notification_preferences::dsl as preferences_table;
+use crate::validation::ValidatedJson;
+use crate::{est_conn, response::Response as Res, DPool};
+
+#[derive(Serialize, Deserialize, Validate)]
+struct Preference {
+    event_type: NotificationEvent,
+    channel: Channel,
+}
+
+/// The channel of every event, defaults included.
+#[get("/notifications/preferences")]
+pub async fn get_preferences(
+    user: AuthenticatedUser,
+    pool: DPool,
+) -> Result<HttpResponse, ApiError> {
+    let conn = &mut est_conn(pool);
+    let user_id = user.id;
+
+    let stored = preferences_table::notification_preferences
+        .filter(preferences_data::user_id.eq(user_id))
+        .select((preferences_data::event_type, preferences_data::channel))
+        .load::<(String, String)>(conn)?;
+
+    let preferences = NotificationEvent::ALL
+        .into_iter()
+        .map(|event| Preference {
+            event_type: event,
+            channel: stored
+                .iter()
+                .find(|(event_type, _)| event_type == event.name())
+                .and_then(|(_, channel)| Channel::from_name(channel))
+                .unwrap_or(event.default_channel()),
+        })
+        .collect::<Vec<_>>();
+
+    Ok(HttpResponse::Ok().json(Res::new(preferences)))
+}
+
+#[put("/notifications/preferences")]
+pub async fn update_preference(
+    user: AuthenticatedUser,
+    req: ValidatedJson<Preference>,
+    pool: DPool,
+    locale: Locale,
+) -> Result<HttpResponse, ApiError> {
+    let conn = &mut est_conn(pool);
+    let user_id = user.id;
+
+    diesel::insert_into(preferences_table::notification_preferences)
+        .values((
+            preferences_data::user_id.eq(user_id),
+            preferences_data::event_type.eq(req.event_type.name()),
+            preferences_data::channel.eq(req.channel.name()),
+        ))
+        .on_conflict((preferences_data::user_id, preferences_data::event_type))
+        .do_update()
+        .set(preferences_data::channel.eq(excluded(preferences_data::channel)))
+        .execute(conn)?;
+
+    Ok(HttpResponse::Ok().json(Res::new(i18n::t(
+        locale,
+        keys::NOTIFICATION_PREFERENCE_UPDATED,
+    ))))
+}
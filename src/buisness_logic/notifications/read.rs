@@ -0,0 +1,152 @@
+use actix_web::web::{Path, Query};
+use actix_web::{get, put, HttpResponse};
+use chrono::{NaiveDateTime, Utc};
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl, SelectableHelper};
+use serde::{Deserialize, Serialize};
+
+use super::render;
+use crate::api_error::ApiError;
+use crate::auth::AuthenticatedUser;
+use crate::i18n::{self, keys, Locale};
+use crate::models::Notification;
+use crate::schema::notifications as notifications_data;
+use crate::schema::notifications::dsl as notifications_table;
+use crate::{est_conn, response::Response as Res, DPool};
+
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 100;
+
+#[derive(Deserialize)]
+struct ListQuery {
+    #[serde(default)]
+    unread_only: bool,
+    limit: Option<i64>,
+    /// Id of the last notification of the previous page.
+    before_id: Option<i32>,
+}
+
+#[derive(Serialize)]
+struct NotificationView {
+    id: i32,
+    event_type: String,
+    title: String,
+    message: String,
+    link: Option<String>,
+    read: bool,
+    created_at: NaiveDateTime,
+}
+
+#[derive(Serialize)]
+struct UnreadCount {
+    count: i64,
+}
+
+/// Newest first, e.g. `/notifications?unread_only=true&limit=20&before_id=120`.
+#[get("/notifications")]
+pub async fn list_notifications(
+    user: AuthenticatedUser,
+    query: Query<ListQuery>,
+    pool: DPool,
+    locale: Locale,
+) -> Result<HttpResponse, ApiError> {
+    let conn = &mut est_conn(pool);
+    let user_id = user.id;
+
+    let mut notifications = notifications_table::notifications
+        .filter(notifications_data::user_id.eq(user_id))
+        .select(Notification::as_select())
+        .order(notifications_data::id.desc())
+        .limit(
+            query
+                .limit
+                .unwrap_or(DEFAULT_PAGE_SIZE)
+                .clamp(1, MAX_PAGE_SIZE),
+        )
+        .into_boxed();
+
+    if query.unread_only {
+        notifications = notifications.filter(notifications_data::read_at.is_null());
+    }
+    if let Some(before_id) = query.before_id {
+        notifications = notifications.filter(notifications_data::id.lt(before_id));
+    }
+
+    let notifications = notifications
+        .load(conn)?
+        .into_iter()
+        .map(|notification| {
+            let (title, message) = render(&notification.event_type, &notification.data, locale);
+            NotificationView {
+                id: notification.id,
+                link: notification.data["link"].as_str().map(str::to_string),
+                event_type: notification.event_type,
+                title,
+                message,
+                read: notification.read_at.is_some(),
+                created_at: notification.created_at,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    Ok(HttpResponse::Ok().json(Res::new(notifications)))
+}
+
+#[get("/notifications/unread/count")]
+pub async fn unread_count(user: AuthenticatedUser, pool: DPool) -> Result<HttpResponse, ApiError> {
+    let conn = &mut est_conn(pool);
+    let user_id = user.id;
+
+    let count = notifications_table::notifications
+        .filter(notifications_data::user_id.eq(user_id))
+        .filter(notifications_data::read_at.is_null())
+        .count()
+        .get_result(conn)?;
+
+    Ok(HttpResponse::Ok().json(Res::new(UnreadCount { count })))
+}
+
+#[put("/notifications/{id}/read")]
+pub async fn mark_read(
+    user: AuthenticatedUser,
+    id: Path<i32>,
+    pool: DPool,
+    locale: Locale,
+) -> Result<HttpResponse, ApiError> {
+    let conn = &mut est_conn(pool);
+    let user_id = user.id;
+
+    let notification = notifications_table::notifications
+        .filter(notifications_data::id.eq(id.into_inner()))
+        .filter(notifications_data::user_id.eq(user_id));
+
+    let found = diesel::select(diesel::dsl::exists(notification)).get_result::<bool>(conn)?;
+    if !found {
+        return Err(ApiError::NotFound(keys::NOTIFICATION_NOT_FOUND));
+    }
+
+    diesel::update(notification.filter(notifications_data::read_at.is_null()))
+        .set(notifications_data::read_at.eq(Utc::now().naive_utc()))
+        .execute(conn)?;
+
+    Ok(HttpResponse::Ok().json(Res::new(i18n::t(locale, keys::NOTIFICATIONS_MARKED_READ))))
+}
+
+#[put("/notifications/read")]
+pub async fn mark_all_read(
+    user: AuthenticatedUser,
+    pool: DPool,
+    locale: Locale,
+) -> Result<HttpResponse, ApiError> {
+    let conn = &mut est_conn(pool);
+    let user_id = user.id;
+
+    diesel::update(
+        notifications_table::notifications
+            .filter(notifications_data::user_id.eq(user_id))
+            .filter(notifications_data::read_at.is_null()),
+    )
+    .set(notifications_data::read_at.eq(Utc::now().naive_utc()))
+    .execute(conn)?;
+
+    Ok(HttpResponse::Ok().json(Res::new(i18n::t(locale, keys::NOTIFICATIONS_MARKED_READ))))
+}
@@ -1,10 +1,16 @@
-use crate::auth::auth_error::{InvitationError, VerificationTokenError};
+use crate::api_error::ApiError;
+use crate::auth::auth_error::VerificationTokenError;
+use crate::auth::confirmation_token::hash::{hash_token, token_matches};
 use crate::auth::confirmation_token::token::Cft;
 use crate::auth::confirmation_token::token::ConfirmationToken;
 use crate::auth::confirmation_token::token::TokenType;
 use crate::auth::find_user::Find;
 use crate::auth::find_user::FindData;
+use crate::auth::sessions::ClientInfo;
+use crate::buisness_logic::audit::{self, AuditAction, AuditEvent, AuditTarget};
+use crate::buisness_logic::notifications::{notify, Notification, NotificationEvent};
 use crate::constants::WORKSPACE_ROLES;
+use crate::i18n::{self, keys, Locale};
 use crate::models;
 use crate::models::WorkspaceInvitation;
 use crate::models_insertable;
@@ -13,7 +19,10 @@ use crate::schema::workspace_invitations as workspace_invitations_data;
 use crate::schema::workspace_invitations::dsl as workspace_invitations_table;
 use crate::schema::workspace_roles::dsl as workspace_roles_table;
 use crate::schema::workspace_users::dsl as workspace_users_table;
+use crate::schema::workspaces as workspaces_data;
+use crate::schema::workspaces::dsl as workspaces_table;
 use actix_web::{put, HttpResponse};
+use chrono::Utc;
 use diesel::ExpressionMethods;
 use diesel::OptionalExtension;
 use diesel::QueryDsl;
@@ -28,102 +37,93 @@ struct Token {
 }
 
 #[put("/workspace/invitation/accept/{token}")]
-pub async fn add_user_to_workspace(pool: DPool, req: actix_web::web::Path<Token>) -> HttpResponse {
-    let workspace_invitation = match workspace_invitations_table::workspace_invitations
-        .filter(workspace_invitations_data::token.eq(req.token.clone()))
+pub async fn add_user_to_workspace(
+    pool: DPool,
+    req: actix_web::web::Path<Token>,
+    client: ClientInfo,
+    locale: Locale,
+) -> Result<HttpResponse, ApiError> {
+    let workspace_invitation = workspace_invitations_table::workspace_invitations
+        .filter(workspace_invitations_data::token.eq(hash_token(&req.token)))
         .first::<WorkspaceInvitation>(&mut est_conn(pool.clone()))
-        .optional()
-    {
-        Ok(Some(invitation)) => invitation,
-        Ok(None) => return HttpResponse::BadRequest().json(Res::new("Token invalid")),
-        Err(e) => {
-            eprintln!("error while accepting invitation: {:?}", e);
-            return HttpResponse::InternalServerError().json(Res::new("Something went wrong"));
-        }
-    };
-
-    match <Cft as ConfirmationToken>::confirm(
+        .optional()?
+        .filter(|invitation| token_matches(&req.token, &invitation.token))
+        .ok_or(ApiError::Token(VerificationTokenError::NotFound))?;
+
+    <Cft as ConfirmationToken>::confirm(
         req.token.clone(),
         TokenType::WorkspaceInvitation(workspace_invitation.workspace_id),
         pool.clone(),
     )
-    .await
-    {
-        Ok(_) => {
-            let conn = &mut est_conn(pool.clone());
-
-            let user = match <FindData as Find>::find_auth_user_by_email(
-                workspace_invitation.user_email,
-                pool,
+    .await?;
+
+    let conn = &mut est_conn(pool.clone());
+
+    let user = <FindData as Find>::find_auth_user_by_email(workspace_invitation.user_email, pool)
+        .await
+        .map_err(ApiError::or_not_found(keys::USER_NOT_FOUND))?;
+
+    // dodac inserta na workspaceroles z defaultowym worker, tam bezdie mozna dodawac jesze wiecej rol
+    // cos jak na discord
+    conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        let workspace_role = models_insertable::WorkspaceRole {
+            user_id: user.id,
+            name: WORKSPACE_ROLES[3].to_string(),
+        };
+
+        let inserted_role = diesel::insert_into(workspace_roles_table::workspace_roles)
+            .values(&workspace_role)
+            .get_result::<models::WorkspaceRole>(conn)?;
+
+        let invitation = models::WorkspaceUser {
+            user_id: user.id,
+            workspace_id: workspace_invitation.workspace_id,
+            workspace_role_id: inserted_role.id,
+            plane_file_cut_name: None,
+            position_id: None,
+            checkin_time: None,
+            checkout_time: None,
+        };
+
+        diesel::insert_into(workspace_users_table::workspace_users)
+            .values(&invitation)
+            .execute(conn)?;
+
+        diesel::update(
+            workspace_invitations_table::workspace_invitations.find(workspace_invitation.id),
+        )
+        .set(workspace_invitations_data::confirmed_at.eq(Utc::now().naive_utc()))
+        .execute(conn)?;
+
+        let (owner_id, workspace_name) = workspaces_table::workspaces
+            .find(workspace_invitation.workspace_id)
+            .select((workspaces_data::owner_id, workspaces_data::name))
+            .first::<(i32, String)>(conn)?;
+
+        notify(
+            conn,
+            owner_id,
+            &Notification::new(
+                NotificationEvent::InvitationAccepted,
+                format!("/workspace/{}", workspace_invitation.workspace_id),
             )
-            .await
-            {
-                Ok(user) => user,
-                Err(err) => {
-                    eprintln!("Error finding user: {}", err);
-                    return HttpResponse::InternalServerError()
-                        .json(Res::new("Server error while finding user"));
-                }
-            };
-            // dodac inserta na workspaceroles z defaultowym worker, tam bezdie mozna dodawac jesze wiecej rol
-            // cos jak na discord
-            let result = conn.transaction::<_, diesel::result::Error, _>(|conn| {
-                let workspace_role = models_insertable::WorkspaceRole {
-                    user_id: user.id,
-                    name: WORKSPACE_ROLES[3].to_string(),
-                };
-
-                let inserted_role = diesel::insert_into(workspace_roles_table::workspace_roles)
-                    .values(&workspace_role)
-                    .get_result::<models::WorkspaceRole>(conn)?;
-
-                let invitation = models::WorkspaceUser {
-                    user_id: user.id,
-                    workspace_id: workspace_invitation.workspace_id,
-                    workspace_role_id: inserted_role.id,
-                    plane_file_cut_name: None,
-                    position_id: None,
-                    checkin_time: None,
-                    checkout_time: None,
-                };
-
-                diesel::insert_into(workspace_users_table::workspace_users)
-                    .values(&invitation)
-                    .execute(conn)?;
-
-                Ok(())
-            });
-
-            match result {
-                Ok(_) => HttpResponse::Ok().json(Res::new("invitation accepted")),
-                Err(err) => {
-                    eprintln!("Error accepting invitation: {}", err);
-                    HttpResponse::InternalServerError()
-                        .json(Res::new("Server error while accepting invitation"))
-                }
-            }
-        }
-        Err(e) => match e {
-            VerificationTokenError::Invitation(InvitationError::UserAlreadyInWorkspace) => {
-                eprintln!(
-                    "error while accepting invitation, User already in workspace: {:?}",
-                    e
-                );
-                HttpResponse::Unauthorized().json(Res::new("User already in workspace"))
-            }
-            VerificationTokenError::NotFound => {
-                eprintln!("error while accepting invitation: {:?}", e);
-                HttpResponse::Unauthorized().json(Res::new("Expired token"))
-            }
-            VerificationTokenError::Expired => {
-                eprintln!("error while accepting invitation, token expired: {:?}", e);
-                HttpResponse::Unauthorized().json(Res::new("Expired token"))
-            }
-            _ => {
-                eprintln!("error while accepting invitation: {:?}", e);
-                HttpResponse::InternalServerError()
-                    .json(Res::new("Something went wrong, prolly wrong id"))
-            }
-        },
-    }
+            .param("user", user.username.clone())
+            .param("workspace", workspace_name),
+        )?;
+
+        audit::record(
+            conn,
+            AuditEvent::new(AuditAction::InvitationAccepted)
+                .actor(user.id)
+                .target(AuditTarget::User, user.id)
+                .workspace(workspace_invitation.workspace_id)
+                .ip(client.ip.clone())
+                .param("role", WORKSPACE_ROLES[3]),
+        )?;
+
+        Ok(())
+    })
+    .map_err(ApiError::or_conflict(keys::USER_ALREADY_IN_WORKSPACE))?;
+
+    Ok(HttpResponse::Ok().json(Res::new(i18n::t(locale, keys::INVITATION_ACCEPTED))))
 }
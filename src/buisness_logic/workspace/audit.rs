@@ -0,0 +1,26 @@
+use actix_web::web::{Path, Query};
+use actix_web::{get, HttpResponse};
+
+use crate::api_error::ApiError;
+use crate::auth::AuthenticatedUser;
+use crate::buisness_logic::audit::read::{find_events, AuditQuery};
+use crate::buisness_logic::workspace::require_workspace_manager;
+use crate::{est_conn, response::Response as Res, DPool};
+
+/// The workspace's events, filtered and paged like `/admin/audit`, e.g.
+/// `/workspace/3/audit?action=task.created&limit=20`.
+#[get("/workspace/{id}/audit")]
+pub async fn list_workspace_audit_events(
+    user: AuthenticatedUser,
+    path: Path<i32>,
+    query: Query<AuditQuery>,
+    pool: DPool,
+) -> Result<HttpResponse, ApiError> {
+    let workspace_id = path.into_inner();
+    let conn = &mut est_conn(pool);
+
+    require_workspace_manager(conn, user.id, workspace_id)?;
+
+    let events = find_events(conn, &query, Some(workspace_id))?;
+    Ok(HttpResponse::Ok().json(Res::new(events)))
+}
@@ -1,25 +1,38 @@
+use crate::api_error::ApiError;
 use crate::auth::find_user::Find;
 use crate::auth::find_user::FindData;
+use crate::auth::sessions::ClientInfo;
+use crate::auth::AuthenticatedUser;
+use crate::buisness_logic::attachments::is_workspace_member;
+use crate::buisness_logic::audit::{self, AuditAction, AuditEvent, AuditTarget};
+use crate::buisness_logic::notifications::{notify, Notification, NotificationEvent};
+use crate::buisness_logic::workspace::require_workspace_manager;
+use crate::buisness_logic::workspace::task_attachments::{
+    attach_to_task, insert_task_attachments, TaskAttachmentRequest,
+};
+use crate::i18n::{self, keys, Locale};
 use crate::models_insertable;
 use crate::response::Response as Res;
+use crate::schema::tasks as tasks_data;
 use crate::schema::tasks::dsl as tasks_table;
 use crate::schema::tasks_category as tasks_category_data;
 use crate::schema::tasks_category::dsl as tasks_category_table;
+use crate::validation::ValidatedJson;
 
-use actix_web::{post, web::Json, HttpResponse};
+use actix_web::{post, HttpResponse};
 use chrono::NaiveDateTime;
 use chrono::Utc;
-use diesel::result::DatabaseErrorKind;
 use diesel::result::Error as DieselError;
 use diesel::QueryDsl;
 use diesel::{Connection, ExpressionMethods, RunQueryDsl};
 use serde::Deserialize;
+use validator::Validate;
 
 use crate::{est_conn, DPool};
 
 #[derive(Deserialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
-enum Status {
+pub enum Status {
     HelpNeeded,
     Todo,
     InProgress,
@@ -27,6 +40,29 @@ enum Status {
     Canceled,
 }
 
+impl Status {
+    /// Row id in the `status` table.
+    pub fn id(&self) -> i32 {
+        match self {
+            Status::HelpNeeded => 1,
+            Status::Todo => 2,
+            Status::InProgress => 3,
+            Status::Completed => 4,
+            Status::Canceled => 5,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Status::HelpNeeded => "HELP_NEEDED",
+            Status::Todo => "TODO",
+            Status::InProgress => "IN_PROGRESS",
+            Status::Completed => "COMPLETED",
+            Status::Canceled => "CANCELED",
+        }
+    }
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 enum Importance {
@@ -40,64 +76,54 @@ struct WorkspaceId {
     id: i32,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Validate)]
 struct CreateTaskRequest {
-    assigner_email: String,
+    #[validate(email)]
     assignee_email: String,
     description: Option<String>,
-    description_multimedia: Option<Vec<u8>>,
+    /// Uploaded by the assigner, the caller, see `task_attachments`.
+    #[serde(default)]
+    #[validate(nested)]
+    attachments: Vec<TaskAttachmentRequest>,
     due_date: Option<NaiveDateTime>,
     status: Option<Status>,
+    #[validate(length(min = 1, max = 50))]
     title: String,
     importance: Option<Importance>,
+    #[validate(length(min = 1, max = 50))]
     category: Option<String>,
 }
 
+/// The caller assigns the task, so they have to manage the workspace.
 #[post("/workspace/{id}/tasks/create")]
 pub async fn create_task(
+    user: AuthenticatedUser,
     pool: DPool,
-    req: Json<CreateTaskRequest>,
+    req: ValidatedJson<CreateTaskRequest>,
     id: actix_web::web::Path<WorkspaceId>,
-) -> HttpResponse {
+    client: ClientInfo,
+    locale: Locale,
+) -> Result<HttpResponse, ApiError> {
     let conn = &mut est_conn(pool.clone());
     let workspace_id = id.id;
 
-    let assigner =
-        match <FindData as Find>::find_auth_user_by_email(req.assigner_email.clone(), pool.clone())
-            .await
-        {
-            Ok(user) => user,
-            Err(_) => return HttpResponse::BadRequest().json(Res::new("Assigner not found")),
-        };
+    require_workspace_manager(conn, user.id, workspace_id)?;
+
+    let assigner = <FindData as Find>::find_auth_user_by_email(user.email.clone(), pool.clone())
+        .await
+        .map_err(ApiError::or_not_found(keys::ASSIGNER_NOT_FOUND))?;
 
     let assignee =
-        match <FindData as Find>::find_auth_user_by_email(req.assignee_email.clone(), pool.clone())
+        <FindData as Find>::find_auth_user_by_email(req.assignee_email.clone(), pool.clone())
             .await
-        {
-            Ok(user) => user,
-            Err(_) => return HttpResponse::BadRequest().json(Res::new("Assignee not found")),
-        };
+            .map_err(ApiError::or_not_found(keys::ASSIGNEE_NOT_FOUND))?;
 
-    let workspaces = match <FindData as Find>::find_workspace_by_owner_email(
-        req.assigner_email.clone(),
-        pool.clone(),
-    )
-    .await
-    {
-        Ok(w) => w,
-        Err(_) => {
-            return HttpResponse::BadRequest()
-                .json(Res::new("Workspace not found for assigner's email"));
-        }
-    };
+    // the assignment notifies them, which must not reach people outside the workspace
+    if !is_workspace_member(conn, assignee.id, workspace_id)? {
+        return Err(ApiError::BadRequest(keys::ASSIGNEE_NOT_WORKSPACE_MEMBER));
+    }
 
-    match workspaces.into_iter().find(|w| w.id == workspace_id) {
-        Some(_) => {}
-        None => {
-            return HttpResponse::BadRequest()
-                .json(Res::new("Workspace not found for assigner's email"));
-        }
-    };
+    attach_to_task(conn, assigner.id, workspace_id, &req.attachments)?;
 
     let result = conn.transaction::<_, DieselError, _>(|conn| {
         let category_id = match req.category.as_deref() {
@@ -137,13 +163,7 @@ pub async fn create_task(
             }
         };
 
-        let status_id = req.status.as_ref().map_or(2, |s| match s {
-            Status::HelpNeeded => 1,
-            Status::Todo => 2,
-            Status::InProgress => 3,
-            Status::Completed => 4,
-            Status::Canceled => 5,
-        });
+        let status_id = req.status.as_ref().map_or(Status::Todo.id(), Status::id);
 
         let importance_id = req.importance.as_ref().map_or(2, |i| match i {
             Importance::Low => 1,
@@ -156,7 +176,6 @@ pub async fn create_task(
             assigner_id: assigner.id,
             worker_id: assignee.id,
             description: req.description.clone(),
-            description_multimedia: req.description_multimedia.clone(),
             assignment_date: Utc::now().naive_utc(),
             due_date: req.due_date,
             status_id,
@@ -165,29 +184,43 @@ pub async fn create_task(
             category_id,
         };
 
-        diesel::insert_into(tasks_table::tasks)
+        let task_id = diesel::insert_into(tasks_table::tasks)
             .values(&new_task)
-            .execute(conn)?;
+            .returning(tasks_data::id)
+            .get_result::<i32>(conn)?;
+        insert_task_attachments(conn, task_id, &req.attachments)?;
+
+        audit::record(
+            conn,
+            AuditEvent::new(AuditAction::TaskCreated)
+                .actor(assigner.id)
+                .target(AuditTarget::Task, task_id)
+                .workspace(workspace_id)
+                .ip(client.ip.clone())
+                .param("title", req.title.clone())
+                .param("assignee_id", assignee.id),
+        )?;
+
+        if assignee.id != assigner.id {
+            notify(
+                conn,
+                assignee.id,
+                &Notification::new(
+                    NotificationEvent::TaskAssigned,
+                    format!("/workspace/{}/tasks/{}", workspace_id, task_id),
+                )
+                .param("assigner", assigner.username.clone())
+                .param("task", req.title.clone()),
+            )?;
+        }
 
         Ok(())
     });
 
-    match result {
-        Ok(_) => HttpResponse::Ok().json(Res::new("Task created successfully")),
-        Err(DieselError::NotFound) => {
-            HttpResponse::BadRequest().json(Res::new("Workspace not found"))
-        }
-        Err(DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, _)) => {
-            HttpResponse::Conflict().json(Res::new("Task with this title already exists"))
-        }
-        Err(DieselError::DatabaseError(DatabaseErrorKind::ForeignKeyViolation, e)) => {
-            eprintln!("FK violation: {:?}", e);
-            HttpResponse::BadRequest()
-                .json(Res::new("Invalid reference (workspace, user, or category)"))
-        }
-        Err(err) => {
-            eprintln!("Error creating task: {}", err);
-            HttpResponse::InternalServerError().json(Res::new("Server error while creating task"))
-        }
-    }
+    result.map_err(|err| match err {
+        DieselError::NotFound => ApiError::NotFound(keys::WORKSPACE_NOT_FOUND),
+        err => ApiError::or_conflict(keys::TASK_TITLE_TAKEN)(err),
+    })?;
+
+    Ok(HttpResponse::Ok().json(Res::new(i18n::t(locale, keys::TASK_CREATED))))
 }
@@ -1,6 +1,10 @@
+use crate::api_error::ApiError;
 use crate::auth::find_user::Find;
 use crate::auth::find_user::FindData;
+use crate::auth::sessions::ClientInfo;
+use crate::buisness_logic::audit::{self, AuditAction, AuditEvent, AuditTarget};
 use crate::constants::WORKSPACE_ROLES;
+use crate::i18n::keys;
 use crate::models;
 use crate::models_insertable;
 use crate::models_insertable::NewWorkspace;
@@ -8,36 +12,40 @@ use crate::response::Response as Res;
 use crate::schema::workspace_roles::dsl as workspace_roles_table;
 use crate::schema::workspace_users::dsl as workspace_users_table;
 use crate::schema::workspaces::dsl as workspaces_table;
-use actix_web::{post, web::Json, HttpResponse};
+use crate::validation::ValidatedJson;
+use actix_web::{post, HttpResponse};
 use chrono::NaiveDateTime;
 use chrono::Utc;
 use diesel::result::DatabaseErrorKind;
 use diesel::result::Error as DieselError;
 use diesel::{Connection, RunQueryDsl};
 use serde::Deserialize;
+use validator::Validate;
 
 use crate::{est_conn, DPool};
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Validate)]
 struct CreateWorkspaceRequest {
+    #[validate(email)]
     owner_email: String,
+    #[validate(length(min = 1, max = 60))]
     name: String,
     finish_date: Option<NaiveDateTime>,
+    #[validate(length(min = 1, max = 150))]
     plan_file_name: Option<String>,
+    #[validate(length(max = 40))]
     geolocation: Option<String>,
 }
 
 #[post("/workspace/create")]
-pub async fn create_workspace(pool: DPool, req: Json<CreateWorkspaceRequest>) -> HttpResponse {
-    let user =
-        match <FindData as Find>::find_auth_user_by_email(req.owner_email.clone(), pool.clone())
-            .await
-        {
-            Ok(user) => user,
-            Err(_) => {
-                return HttpResponse::BadRequest().json(Res::new("User not found"));
-            }
-        };
+pub async fn create_workspace(
+    pool: DPool,
+    req: ValidatedJson<CreateWorkspaceRequest>,
+    client: ClientInfo,
+) -> Result<HttpResponse, ApiError> {
+    let user = <FindData as Find>::find_auth_user_by_email(req.owner_email.clone(), pool.clone())
+        .await
+        .map_err(ApiError::or_not_found(keys::USER_NOT_FOUND))?;
 
     let conn = &mut est_conn(pool);
 
@@ -57,6 +65,16 @@ pub async fn create_workspace(pool: DPool, req: Json<CreateWorkspaceRequest>) ->
             .values(&new_workspace)
             .get_result::<models::Workspace>(conn)?;
 
+        audit::record(
+            conn,
+            AuditEvent::new(AuditAction::WorkspaceCreated)
+                .actor(user.id)
+                .target(AuditTarget::Workspace, workspace.id)
+                .workspace(workspace.id)
+                .ip(client.ip.clone())
+                .param("name", workspace.name.clone()),
+        )?;
+
         // Add workspace role
         let workspace_role = models_insertable::WorkspaceRole {
             user_id: user.id,
@@ -94,26 +112,16 @@ pub async fn create_workspace(pool: DPool, req: Json<CreateWorkspaceRequest>) ->
     });
 
     match result {
-        Ok(w) => HttpResponse::Ok().json(Res::new(w)),
+        Ok(w) => Ok(HttpResponse::Ok().json(Res::new(w))),
         Err(DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, e)) => {
-            eprintln!("{:?}", e);
             if e.message().contains("owner_id_plan_file_name") {
-                HttpResponse::Conflict()
-                    .json(Res::new("Plan file name already exists for this owner"))
+                Err(ApiError::Conflict(keys::PLAN_FILE_NAME_TAKEN))
             } else if e.message().contains("owner_id_name") {
-                HttpResponse::Conflict()
-                    .json(Res::new("Workspace name already exists for this owner"))
+                Err(ApiError::Conflict(keys::WORKSPACE_NAME_TAKEN))
             } else {
-                HttpResponse::Conflict().json(Res::new("Unique constraint violation"))
+                Err(ApiError::Conflict(keys::ALREADY_EXISTS))
             }
         }
-        Err(DieselError::DatabaseError(DatabaseErrorKind::ForeignKeyViolation, _)) => {
-            HttpResponse::BadRequest().json(Res::new("Invalid user or subscription reference"))
-        }
-        Err(err) => {
-            eprintln!("Error creating workspace: {}", err);
-            HttpResponse::InternalServerError()
-                .json(Res::new("Server error while creating workspace"))
-        }
+        Err(err) => Err(err.into()),
     }
 }
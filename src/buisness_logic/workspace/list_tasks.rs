@@ -1,17 +1,16 @@
+use crate::api_error::ApiError;
 use crate::auth::find_user::FindData;
-use crate::response::Response as Res;
+use crate::i18n::keys;
+use crate::validation::ValidatedJson;
 use crate::DPool;
 use crate::{auth::find_user::Find, est_conn};
 use actix_web::post;
-use actix_web::{
-    web::{Json, Path},
-    HttpResponse,
-};
+use actix_web::{web::Path, HttpResponse};
 use chrono::NaiveDateTime;
 use diesel::prelude::*;
 use diesel::result::Error;
-use diesel::result::Error as DieselError;
 use serde::{Deserialize, Serialize};
+use validator::Validate;
 
 #[derive(QueryableByName, Serialize)]
 struct TaskResponse {
@@ -42,8 +41,9 @@ pub struct WorkspaceId {
     id: i32,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Validate)]
 pub struct ListTasksRequest {
+    #[validate(email)]
     owner_email: String,
 }
 
@@ -51,30 +51,18 @@ pub struct ListTasksRequest {
 pub async fn list_tasks(
     pool: DPool,
     path: Path<WorkspaceId>,
-    req: Json<ListTasksRequest>,
-) -> HttpResponse {
+    req: ValidatedJson<ListTasksRequest>,
+) -> Result<HttpResponse, ApiError> {
     let workspace_id = path.id;
 
-    let workspaces = match <FindData as Find>::find_workspace_by_owner_email(
-        req.owner_email.clone(),
-        pool.clone(),
-    )
-    .await
-    {
-        Ok(w) => w,
-        Err(_) => {
-            return HttpResponse::BadRequest()
-                .json(Res::new("Workspace not found for owner's email or id"));
-        }
-    };
+    let workspaces =
+        <FindData as Find>::find_workspace_by_owner_email(req.owner_email.clone(), pool.clone())
+            .await
+            .map_err(ApiError::or_not_found(keys::WORKSPACE_NOT_FOUND))?;
 
-    match workspaces.into_iter().find(|w| w.id == workspace_id) {
-        Some(_) => {}
-        None => {
-            return HttpResponse::BadRequest()
-                .json(Res::new("Workspace not found for owner's email or id"));
-        }
-    };
+    if !workspaces.iter().any(|w| w.id == workspace_id) {
+        return Err(ApiError::NotFound(keys::WORKSPACE_NOT_FOUND));
+    }
 
     let conn = &mut est_conn(pool);
 
@@ -106,12 +94,5 @@ pub async fn list_tasks(
             .load::<TaskResponse>(conn)
     });
 
-    match result {
-        Ok(tasks) => HttpResponse::Ok().json(tasks),
-        Err(DieselError::NotFound) => HttpResponse::NotFound().json(Res::new("No tasks found")),
-        Err(err) => {
-            eprintln!("Error listing tasks: {}", err);
-            HttpResponse::InternalServerError().json(Res::new("Server error while listing tasks"))
-        }
-    }
+    Ok(HttpResponse::Ok().json(result?))
 }
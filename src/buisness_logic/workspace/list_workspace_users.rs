@@ -1,4 +1,6 @@
+use crate::api_error::ApiError;
 use crate::auth::find_user::{Find, FindData};
+use crate::i18n::keys;
 use crate::response::Response as Res;
 use crate::schema::auth_users::dsl as auth_users_table;
 use crate::schema::positions as positions_data;
@@ -7,21 +9,20 @@ use crate::schema::workspace_roles as workspace_roles_data;
 use crate::schema::workspace_roles::dsl as workspace_roles_table;
 use crate::schema::workspace_users as workspace_users_data;
 use crate::schema::workspace_users::dsl as workspace_users_table;
+use crate::validation::ValidatedJson;
 use crate::{schema::auth_users as auth_users_data, DBPConn};
 
-use actix_web::{
-    post,
-    web::{Json, Path},
-    HttpResponse,
-};
+use actix_web::{post, web::Path, HttpResponse};
 use diesel::{result::Error as DieselError, ExpressionMethods};
 use diesel::{JoinOnDsl, NullableExpressionMethods, QueryDsl, RunQueryDsl};
 use serde::{Deserialize, Serialize};
+use validator::Validate;
 
 use crate::{est_conn, DPool};
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Validate)]
 struct ListWorkspaceUsersRequest {
+    #[validate(email)]
     email: String,
 }
 
@@ -31,7 +32,7 @@ struct ListWorkspaceUsersPath {
 }
 
 #[derive(Serialize)]
-struct WorkspaceUserResponse {
+pub struct WorkspaceUserResponse {
     id: i32,
     username: String,
     email: String,
@@ -42,46 +43,27 @@ struct WorkspaceUserResponse {
 #[post("/workspace/{id}/users/list")]
 pub async fn list_workspace_users(
     pool: DPool,
-    req: Json<ListWorkspaceUsersRequest>,
+    req: ValidatedJson<ListWorkspaceUsersRequest>,
     path: Path<ListWorkspaceUsersPath>,
-) -> HttpResponse {
+) -> Result<HttpResponse, ApiError> {
     let workspace_id = path.id;
-    let _email = req.email.clone();
 
     let conn = &mut est_conn(pool.clone());
 
     let workspaces =
-        match <FindData as Find>::find_workspace_by_owner_email(req.email.clone(), pool.clone())
+        <FindData as Find>::find_workspace_by_owner_email(req.email.clone(), pool.clone())
             .await
-        {
-            Ok(w) => w,
-            Err(_) => {
-                return HttpResponse::BadRequest()
-                    .json(Res::new("Workspace not found for owner's email or id"));
-            }
-        };
-
-    match workspaces.into_iter().find(|w| w.id == workspace_id) {
-        Some(_) => {}
-        None => {
-            return HttpResponse::BadRequest()
-                .json(Res::new("Workspace not found for owner's email or id"));
-        }
-    };
+            .map_err(ApiError::or_not_found(keys::WORKSPACE_NOT_FOUND))?;
 
-    match get_workspace_users(conn, workspace_id).await {
-        Ok(users) => HttpResponse::Ok().json(Res::new(users)),
-        Err(DieselError::NotFound) => {
-            HttpResponse::NotFound().json(Res::new("Workspace not found for that id"))
-        }
-        Err(err) => {
-            eprintln!("Error listing workspace users: {}", err);
-            HttpResponse::InternalServerError().json(Res::new("Server error while listing users"))
-        }
+    if !workspaces.iter().any(|w| w.id == workspace_id) {
+        return Err(ApiError::NotFound(keys::WORKSPACE_NOT_FOUND));
     }
+
+    let users = get_workspace_users(conn, workspace_id).await?;
+    Ok(HttpResponse::Ok().json(Res::new(users)))
 }
 
-async fn get_workspace_users(
+pub async fn get_workspace_users(
     conn: &mut DBPConn,
     workspace_id: i32,
 ) -> Result<Vec<WorkspaceUserResponse>, DieselError> {
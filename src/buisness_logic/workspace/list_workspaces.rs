@@ -1,19 +1,23 @@
+use crate::api_error::ApiError;
 use crate::response::Response as Res;
 use crate::schema::auth_users::dsl as auth_users_table;
 use crate::schema::ev_subscriptions::dsl as ev_subscriptions_table;
 use crate::schema::workspaces::dsl as workspaces_table;
+use crate::validation::ValidatedJson;
 use crate::{schema::auth_users as auth_users_data, DBPConn};
 
-use actix_web::{post, web::Json, HttpResponse};
+use actix_web::{post, HttpResponse};
 use chrono::NaiveDateTime;
 use diesel::{result::Error as DieselError, ExpressionMethods};
 use diesel::{JoinOnDsl, QueryDsl, RunQueryDsl};
 use serde::{Deserialize, Serialize};
+use validator::Validate;
 
 use crate::{est_conn, DPool};
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Validate)]
 struct ListWorkspacesRequest {
+    #[validate(email)]
     email: String,
 }
 
@@ -29,18 +33,15 @@ struct WorkspaceResponse {
 }
 
 #[post("/workspace/list")]
-pub async fn list_workspaces(pool: DPool, req: Json<ListWorkspacesRequest>) -> HttpResponse {
+pub async fn list_workspaces(
+    pool: DPool,
+    req: ValidatedJson<ListWorkspacesRequest>,
+) -> Result<HttpResponse, ApiError> {
     let email = req.email.clone();
     let conn = &mut est_conn(pool);
 
-    match get_workspaces(conn, email).await {
-        Ok(workspaces) => HttpResponse::Ok().json(Res::new(workspaces)),
-        Err(err) => {
-            eprintln!("Error listing workspaces: {}", err);
-            HttpResponse::InternalServerError()
-                .json(Res::new("Server error while listing workspaces"))
-        }
-    }
+    let workspaces = get_workspaces(conn, email).await?;
+    Ok(HttpResponse::Ok().json(Res::new(workspaces)))
 }
 
 async fn get_workspaces(
@@ -1,7 +1,68 @@
 pub mod add_user_to_workspace;
+pub mod audit;
 pub mod create_task;
 pub mod create_workspace;
 pub mod list_tasks;
 pub mod list_workspace_users;
 pub mod list_workspaces;
+pub mod report_problem;
+pub mod storage_usage;
+pub mod task_attachments;
+pub mod task_reminders;
+pub mod update_task_status;
 pub mod workspace_invitation;
+
+use diesel::dsl::exists;
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+
+use crate::api_error::ApiError;
+use crate::i18n::keys;
+use crate::schema::workspace_roles as workspace_roles_data;
+use crate::schema::workspace_users as workspace_users_data;
+use crate::schema::workspaces as workspaces_data;
+
+/// Workspace roles managing it next to the owner.
+const MANAGER_ROLES: [&str; 2] = ["CREATOR", "ADMIN"];
+
+/// Owns the workspace or holds one of `MANAGER_ROLES` in it.
+pub fn is_workspace_manager(
+    conn: &mut PgConnection,
+    user_id: i32,
+    workspace_id: i32,
+) -> Result<bool, DieselError> {
+    diesel::select(
+        exists(
+            workspaces_data::table
+                .filter(workspaces_data::id.eq(workspace_id))
+                .filter(workspaces_data::owner_id.eq(user_id)),
+        )
+        .or(exists(
+            workspace_users_data::table
+                .inner_join(workspace_roles_data::table)
+                .filter(workspace_users_data::workspace_id.eq(workspace_id))
+                .filter(workspace_users_data::user_id.eq(user_id))
+                .filter(workspace_roles_data::name.eq_any(MANAGER_ROLES)),
+        )),
+    )
+    .get_result(conn)
+}
+
+/// Fails with not found for a workspace that does not exist and forbidden unless the user
+/// `is_workspace_manager`.
+pub fn require_workspace_manager(
+    conn: &mut PgConnection,
+    user_id: i32,
+    workspace_id: i32,
+) -> Result<(), ApiError> {
+    workspaces_data::table
+        .find(workspace_id)
+        .select(workspaces_data::id)
+        .first::<i32>(conn)
+        .map_err(ApiError::or_not_found(keys::WORKSPACE_NOT_FOUND))?;
+
+    match is_workspace_manager(conn, user_id, workspace_id)? {
+        true => Ok(()),
+        false => Err(ApiError::Forbidden(keys::NOT_WORKSPACE_ADMIN)),
+    }
+}
@@ -0,0 +1,74 @@
+use crate::api_error::ApiError;
+use crate::auth::find_user::{Find, FindData};
+use crate::auth::AuthenticatedUser;
+use crate::buisness_logic::attachments;
+use crate::buisness_logic::notifications::{notify, Notification, NotificationEvent};
+use crate::i18n::{self, keys, Locale};
+use crate::response::Response as Res;
+use crate::schema::problems as problems_data;
+use crate::schema::problems::dsl as problems_table;
+use crate::validation::ValidatedJson;
+use crate::{est_conn, DPool};
+
+use actix_web::{post, HttpResponse};
+use diesel::result::Error as DieselError;
+use diesel::{Connection, ExpressionMethods, RunQueryDsl};
+use serde::Deserialize;
+use validator::Validate;
+
+#[derive(Deserialize, Validate)]
+struct ReportProblemRequest {
+    #[validate(email)]
+    mentor_email: String,
+    #[validate(length(min = 1, max = 2000))]
+    description: String,
+    attachment_id: Option<i32>,
+}
+
+/// A worker asks their mentor for help, the mentor is notified.
+#[post("/problems/report")]
+pub async fn report_problem(
+    user: AuthenticatedUser,
+    req: ValidatedJson<ReportProblemRequest>,
+    pool: DPool,
+    locale: Locale,
+) -> Result<HttpResponse, ApiError> {
+    let worker = <FindData as Find>::find_auth_user_by_email(user.email, pool.clone())
+        .await
+        .map_err(ApiError::or_not_found(keys::USER_NOT_FOUND))?;
+
+    let mentor =
+        <FindData as Find>::find_auth_user_by_email(req.mentor_email.clone(), pool.clone())
+            .await
+            .map_err(ApiError::or_not_found(keys::MENTOR_NOT_FOUND))?;
+
+    let conn = &mut est_conn(pool);
+    if let Some(attachment_id) = req.attachment_id {
+        attachments::attach(conn, attachment_id, worker.id, None)?;
+    }
+
+    conn.transaction::<_, DieselError, _>(|conn| {
+        let problem_id = diesel::insert_into(problems_table::problems)
+            .values((
+                problems_data::worker_id.eq(worker.id),
+                problems_data::mentor_id.eq(mentor.id),
+                problems_data::description.eq(&req.description),
+                problems_data::attachment_id.eq(req.attachment_id),
+            ))
+            .returning(problems_data::id)
+            .get_result::<i32>(conn)?;
+
+        notify(
+            conn,
+            mentor.id,
+            &Notification::new(
+                NotificationEvent::ProblemReported,
+                format!("/problems/{}", problem_id),
+            )
+            .param("user", worker.username.clone())
+            .param("description", req.description.clone()),
+        )
+    })?;
+
+    Ok(HttpResponse::Ok().json(Res::new(i18n::t(locale, keys::PROBLEM_REPORTED))))
+}
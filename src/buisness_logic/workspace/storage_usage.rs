@@ -0,0 +1,32 @@
+use actix_web::web::Path;
+use actix_web::{get, HttpResponse};
+use serde::Serialize;
+
+use crate::api_error::ApiError;
+use crate::auth::AuthenticatedUser;
+use crate::buisness_logic::attachments::{require_workspace_member, workspace_usage};
+use crate::settings;
+use crate::{est_conn, response::Response as Res, DPool};
+
+#[derive(Serialize)]
+struct StorageUsage {
+    used_bytes: i64,
+    quota_bytes: i64,
+}
+
+/// How much of `storage.workspace_quota` the workspace's attachments take.
+#[get("/workspace/{id}/storage")]
+pub async fn get_storage_usage(
+    user: AuthenticatedUser,
+    path: Path<i32>,
+    pool: DPool,
+) -> Result<HttpResponse, ApiError> {
+    let workspace_id = path.into_inner();
+    let conn = &mut est_conn(pool);
+    require_workspace_member(conn, user.id, workspace_id)?;
+
+    Ok(HttpResponse::Ok().json(Res::new(StorageUsage {
+        used_bytes: workspace_usage(conn, workspace_id)?,
+        quota_bytes: settings::get().storage.workspace_quota,
+    })))
+}
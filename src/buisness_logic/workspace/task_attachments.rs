@@ -0,0 +1,379 @@
+//! Files on a task, e.g. a photo of every stage of an installation, in an order the participants
+//! choose and with optional captions. Tasks have no comments yet, attachments on comments come
+//! with them.
+
+use std::collections::HashSet;
+
+use actix_web::web::Path;
+use actix_web::{delete, get, post, put, HttpResponse};
+use diesel::dsl::{exists, max};
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::api_error::ApiError;
+use crate::auth::sessions::ClientInfo;
+use crate::auth::AuthenticatedUser;
+use crate::buisness_logic::attachments::download::AttachmentResponse;
+use crate::buisness_logic::attachments::{self, require_workspace_member};
+use crate::buisness_logic::audit::{self, AuditAction, AuditEvent, AuditTarget};
+use crate::buisness_logic::workspace::is_workspace_manager;
+use crate::i18n::{self, keys, Locale};
+use crate::models::{Attachment, Task, TaskAttachment};
+use crate::models_insertable::NewTaskAttachment;
+use crate::schema::attachments as attachments_data;
+use crate::schema::task_attachments as task_attachments_data;
+use crate::schema::tasks as tasks_data;
+use crate::validation::ValidatedJson;
+use crate::{est_conn, response::Response as Res, DPool};
+
+#[derive(Deserialize)]
+struct TaskPath {
+    workspace_id: i32,
+    task_id: i32,
+}
+
+#[derive(Deserialize)]
+struct TaskAttachmentPath {
+    workspace_id: i32,
+    task_id: i32,
+    attachment_id: i32,
+}
+
+/// An uploaded attachment to put on a task, see `POST /attachments`.
+#[derive(Deserialize, Validate)]
+pub struct TaskAttachmentRequest {
+    attachment_id: i32,
+    #[validate(length(max = 500))]
+    caption: Option<String>,
+}
+
+#[derive(Deserialize, Validate)]
+struct UpdateCaptionRequest {
+    /// `null` removes the caption.
+    #[validate(length(max = 500))]
+    caption: Option<String>,
+}
+
+#[derive(Deserialize, Validate)]
+struct ReorderRequest {
+    /// Every attachment of the task, first to last.
+    attachment_ids: Vec<i32>,
+}
+
+#[derive(Serialize)]
+struct TaskAttachmentResponse {
+    caption: Option<String>,
+    position: i32,
+    #[serde(flatten)]
+    attachment: AttachmentResponse,
+}
+
+/// Checks the attachments can go on a task of `workspace_id`: `owner_id` uploaded them, they fit
+/// into the workspace quota and none of them is on a task yet. See `attachments::attach`.
+pub fn attach_to_task(
+    conn: &mut PgConnection,
+    owner_id: i32,
+    workspace_id: i32,
+    requests: &[TaskAttachmentRequest],
+) -> Result<(), ApiError> {
+    let mut seen = HashSet::new();
+    for request in requests {
+        let on_task = diesel::select(exists(
+            task_attachments_data::table
+                .filter(task_attachments_data::attachment_id.eq(request.attachment_id)),
+        ))
+        .get_result::<bool>(conn)?;
+        if on_task || !seen.insert(request.attachment_id) {
+            return Err(ApiError::Conflict(keys::ATTACHMENT_ALREADY_ON_TASK));
+        }
+
+        attachments::attach(conn, request.attachment_id, owner_id, Some(workspace_id))?;
+    }
+    Ok(())
+}
+
+/// Appends the attachments after the ones the task already has, in the order given.
+pub fn insert_task_attachments(
+    conn: &mut PgConnection,
+    task_id: i32,
+    requests: &[TaskAttachmentRequest],
+) -> Result<Vec<TaskAttachment>, DieselError> {
+    let next_position = task_attachments_data::table
+        .filter(task_attachments_data::task_id.eq(task_id))
+        .select(max(task_attachments_data::position))
+        .first::<Option<i32>>(conn)?
+        .map_or(0, |position| position + 1);
+
+    let rows = requests
+        .iter()
+        .zip(next_position..)
+        .map(|(request, position)| NewTaskAttachment {
+            task_id,
+            attachment_id: request.attachment_id,
+            caption: request.caption.as_deref(),
+            position,
+        })
+        .collect::<Vec<_>>();
+
+    diesel::insert_into(task_attachments_data::table)
+        .values(&rows)
+        .returning(TaskAttachment::as_returning())
+        .get_results(conn)
+}
+
+fn find_task(conn: &mut PgConnection, path: &TaskPath) -> Result<Task, ApiError> {
+    tasks_data::table
+        .filter(tasks_data::id.eq(path.task_id))
+        .filter(tasks_data::workspace_id.eq(path.workspace_id))
+        .select(Task::as_select())
+        .first(conn)
+        .map_err(ApiError::or_not_found(keys::TASK_NOT_FOUND))
+}
+
+fn find_task_attachment(
+    conn: &mut PgConnection,
+    task_id: i32,
+    attachment_id: i32,
+) -> Result<(TaskAttachment, Attachment), ApiError> {
+    task_attachments_data::table
+        .inner_join(attachments_data::table)
+        .filter(task_attachments_data::task_id.eq(task_id))
+        .filter(task_attachments_data::attachment_id.eq(attachment_id))
+        .select((TaskAttachment::as_select(), Attachment::as_select()))
+        .first(conn)
+        .map_err(ApiError::or_not_found(keys::TASK_ATTACHMENT_NOT_FOUND))
+}
+
+/// The uploader and the workspace managers may change or remove an attachment of a task.
+fn require_uploader_or_manager(
+    conn: &mut PgConnection,
+    user_id: i32,
+    workspace_id: i32,
+    attachment: &Attachment,
+) -> Result<(), ApiError> {
+    match attachment.owner_id == user_id || is_workspace_manager(conn, user_id, workspace_id)? {
+        true => Ok(()),
+        false => Err(ApiError::Forbidden(keys::ATTACHMENT_EDIT_FORBIDDEN)),
+    }
+}
+
+fn task_attachment_response(
+    task_attachment: TaskAttachment,
+    attachment: Attachment,
+) -> Result<TaskAttachmentResponse, ApiError> {
+    Ok(TaskAttachmentResponse {
+        caption: task_attachment.caption,
+        position: task_attachment.position,
+        attachment: AttachmentResponse::new(attachment)?,
+    })
+}
+
+fn load_task_attachments(
+    conn: &mut PgConnection,
+    task_id: i32,
+) -> Result<Vec<TaskAttachmentResponse>, ApiError> {
+    task_attachments_data::table
+        .inner_join(attachments_data::table)
+        .filter(task_attachments_data::task_id.eq(task_id))
+        .order(task_attachments_data::position.asc())
+        .select((TaskAttachment::as_select(), Attachment::as_select()))
+        .load::<(TaskAttachment, Attachment)>(conn)?
+        .into_iter()
+        .map(|(task_attachment, attachment)| task_attachment_response(task_attachment, attachment))
+        .collect()
+}
+
+/// The attachments of a task in their order, for the members of its workspace.
+#[get("/workspace/{workspace_id}/tasks/{task_id}/attachments")]
+pub async fn list_task_attachments(
+    user: AuthenticatedUser,
+    path: Path<TaskPath>,
+    pool: DPool,
+) -> Result<HttpResponse, ApiError> {
+    let conn = &mut est_conn(pool);
+    require_workspace_member(conn, user.id, path.workspace_id)?;
+    let task = find_task(conn, &path)?;
+
+    Ok(HttpResponse::Ok().json(Res::new(load_task_attachments(conn, task.id)?)))
+}
+
+/// Puts an attachment the user uploaded at the end of the task. Any member of the workspace can
+/// add files, e.g. the electrician doing the work.
+#[post("/workspace/{workspace_id}/tasks/{task_id}/attachments")]
+pub async fn add_task_attachment(
+    user: AuthenticatedUser,
+    path: Path<TaskPath>,
+    req: ValidatedJson<TaskAttachmentRequest>,
+    client: ClientInfo,
+    pool: DPool,
+) -> Result<HttpResponse, ApiError> {
+    let conn = &mut est_conn(pool);
+    require_workspace_member(conn, user.id, path.workspace_id)?;
+    let task = find_task(conn, &path)?;
+
+    let requests = [req.0];
+    attach_to_task(conn, user.id, task.workspace_id, &requests)?;
+
+    conn.transaction::<_, DieselError, _>(|conn| {
+        insert_task_attachments(conn, task.id, &requests)?;
+
+        audit::record(
+            conn,
+            AuditEvent::new(AuditAction::TaskAttachmentAdded)
+                .actor(user.id)
+                .target(AuditTarget::Task, task.id)
+                .workspace(task.workspace_id)
+                .ip(client.ip.clone())
+                .param("attachment_id", requests[0].attachment_id),
+        )
+    })
+    .map_err(ApiError::or_conflict(keys::ATTACHMENT_ALREADY_ON_TASK))?;
+
+    let (task_attachment, attachment) =
+        find_task_attachment(conn, task.id, requests[0].attachment_id)?;
+    Ok(HttpResponse::Ok().json(Res::new(task_attachment_response(
+        task_attachment,
+        attachment,
+    )?)))
+}
+
+/// Sets or removes the caption, for the uploader and the workspace managers.
+#[put("/workspace/{workspace_id}/tasks/{task_id}/attachments/{attachment_id}")]
+pub async fn update_task_attachment(
+    user: AuthenticatedUser,
+    path: Path<TaskAttachmentPath>,
+    req: ValidatedJson<UpdateCaptionRequest>,
+    client: ClientInfo,
+    pool: DPool,
+) -> Result<HttpResponse, ApiError> {
+    let conn = &mut est_conn(pool);
+    let task = find_task(
+        conn,
+        &TaskPath {
+            workspace_id: path.workspace_id,
+            task_id: path.task_id,
+        },
+    )?;
+    let (task_attachment, attachment) = find_task_attachment(conn, task.id, path.attachment_id)?;
+    require_uploader_or_manager(conn, user.id, task.workspace_id, &attachment)?;
+
+    let task_attachment = conn.transaction::<_, DieselError, _>(|conn| {
+        let task_attachment = diesel::update(task_attachments_data::table.find(task_attachment.id))
+            .set(task_attachments_data::caption.eq(&req.caption))
+            .returning(TaskAttachment::as_returning())
+            .get_result(conn)?;
+
+        audit::record(
+            conn,
+            AuditEvent::new(AuditAction::TaskAttachmentCaptionChanged)
+                .actor(user.id)
+                .target(AuditTarget::Task, task.id)
+                .workspace(task.workspace_id)
+                .ip(client.ip.clone())
+                .param("attachment_id", attachment.id),
+        )?;
+        Ok(task_attachment)
+    })?;
+
+    Ok(HttpResponse::Ok().json(Res::new(task_attachment_response(
+        task_attachment,
+        attachment,
+    )?)))
+}
+
+/// Reorders the attachments, for the task participants and the workspace managers. The list has
+/// to name every attachment of the task exactly once.
+#[put("/workspace/{workspace_id}/tasks/{task_id}/attachments")]
+pub async fn reorder_task_attachments(
+    user: AuthenticatedUser,
+    path: Path<TaskPath>,
+    req: ValidatedJson<ReorderRequest>,
+    client: ClientInfo,
+    pool: DPool,
+) -> Result<HttpResponse, ApiError> {
+    let conn = &mut est_conn(pool);
+    let task = find_task(conn, &path)?;
+    let is_participant = user.id == task.assigner_id || user.id == task.worker_id;
+    if !is_participant && !is_workspace_manager(conn, user.id, task.workspace_id)? {
+        return Err(ApiError::Forbidden(keys::NOT_TASK_PARTICIPANT));
+    }
+
+    conn.transaction::<_, ApiError, _>(|conn| {
+        let current = task_attachments_data::table
+            .filter(task_attachments_data::task_id.eq(task.id))
+            .select(task_attachments_data::attachment_id)
+            .for_update()
+            .load::<i32>(conn)?;
+
+        let requested = req.attachment_ids.iter().collect::<HashSet<_>>();
+        if requested.len() != req.attachment_ids.len()
+            || requested != current.iter().collect::<HashSet<_>>()
+        {
+            return Err(ApiError::BadRequest(keys::TASK_ATTACHMENTS_ORDER_INVALID));
+        }
+
+        for (attachment_id, position) in req.attachment_ids.iter().zip(0..) {
+            diesel::update(
+                task_attachments_data::table
+                    .filter(task_attachments_data::task_id.eq(task.id))
+                    .filter(task_attachments_data::attachment_id.eq(attachment_id)),
+            )
+            .set(task_attachments_data::position.eq(position))
+            .execute(conn)?;
+        }
+
+        audit::record(
+            conn,
+            AuditEvent::new(AuditAction::TaskAttachmentsReordered)
+                .actor(user.id)
+                .target(AuditTarget::Task, task.id)
+                .workspace(task.workspace_id)
+                .ip(client.ip.clone())
+                .param("attachment_ids", req.attachment_ids.clone()),
+        )?;
+        Ok(())
+    })?;
+
+    Ok(HttpResponse::Ok().json(Res::new(load_task_attachments(conn, task.id)?)))
+}
+
+/// Deletes the attachment and its files, for the uploader and the workspace managers.
+#[delete("/workspace/{workspace_id}/tasks/{task_id}/attachments/{attachment_id}")]
+pub async fn delete_task_attachment(
+    user: AuthenticatedUser,
+    path: Path<TaskAttachmentPath>,
+    client: ClientInfo,
+    pool: DPool,
+    locale: Locale,
+) -> Result<HttpResponse, ApiError> {
+    let conn = &mut est_conn(pool);
+    let task = find_task(
+        conn,
+        &TaskPath {
+            workspace_id: path.workspace_id,
+            task_id: path.task_id,
+        },
+    )?;
+    let (_, attachment) = find_task_attachment(conn, task.id, path.attachment_id)?;
+    require_uploader_or_manager(conn, user.id, task.workspace_id, &attachment)?;
+
+    conn.transaction::<_, DieselError, _>(|conn| {
+        diesel::delete(attachments_data::table.find(attachment.id)).execute(conn)?;
+
+        audit::record(
+            conn,
+            AuditEvent::new(AuditAction::TaskAttachmentRemoved)
+                .actor(user.id)
+                .target(AuditTarget::Task, task.id)
+                .workspace(task.workspace_id)
+                .ip(client.ip.clone())
+                .param("attachment_id", attachment.id)
+                .param("file_name", attachment.file_name.clone()),
+        )
+    })?;
+    attachments::delete_stored_files(&attachment).await;
+
+    Ok(HttpResponse::Ok().json(Res::new(i18n::t(locale, keys::TASK_ATTACHMENT_REMOVED))))
+}
@@ -0,0 +1,185 @@
+use crate::buisness_logic::notifications::{notify, Notification, NotificationEvent};
+use crate::buisness_logic::workspace::create_task::Status;
+use crate::models::Task;
+use crate::schema::auth_users as auth_users_data;
+use crate::schema::auth_users::dsl as auth_users_table;
+use crate::schema::task_reminders as task_reminders_data;
+use crate::schema::task_reminders::dsl as task_reminders_table;
+use crate::schema::tasks as tasks_data;
+use crate::schema::tasks::dsl as tasks_table;
+use crate::schema::workspace_roles as workspace_roles_data;
+use crate::schema::workspace_users as workspace_users_data;
+use crate::schema::workspace_users::dsl as workspace_users_table;
+use crate::schema::workspaces as workspaces_data;
+use crate::schema::workspaces::dsl as workspaces_table;
+
+use chrono::{Duration, NaiveDateTime, Utc};
+use diesel::dsl::exists;
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+
+/// Workspace roles told about overdue tasks, next to the owner and the assigner.
+const MANAGER_ROLES: [&str; 2] = ["ADMIN", "MANAGER"];
+
+#[derive(Clone, Copy)]
+enum Reminder {
+    DueIn24Hours,
+    DueIn1Hour,
+    Overdue,
+}
+
+impl Reminder {
+    fn kind(self) -> &'static str {
+        match self {
+            Reminder::DueIn24Hours => "due_24h",
+            Reminder::DueIn1Hour => "due_1h",
+            Reminder::Overdue => "overdue",
+        }
+    }
+
+    /// Due dates the reminder is sent for, relative to now.
+    fn window(self, now: NaiveDateTime) -> (Option<NaiveDateTime>, NaiveDateTime) {
+        match self {
+            Reminder::DueIn24Hours => (Some(now + Duration::hours(1)), now + Duration::hours(24)),
+            Reminder::DueIn1Hour => (Some(now), now + Duration::hours(1)),
+            Reminder::Overdue => (None, now),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct ReminderReport {
+    pub due_soon: usize,
+    pub overdue: usize,
+}
+
+/// Notifies assignees of tasks due within 24h and 1h, and the assigner and workspace
+/// managers of overdue tasks. Sent reminders are recorded so each is sent once.
+pub fn send_task_reminders(conn: &mut PgConnection) -> Result<ReminderReport, DieselError> {
+    let mut report = ReminderReport::default();
+
+    for reminder in [
+        Reminder::DueIn1Hour,
+        Reminder::DueIn24Hours,
+        Reminder::Overdue,
+    ] {
+        for task in due_tasks(conn, reminder)? {
+            let sent = conn.transaction::<_, DieselError, _>(|c| remind(c, &task, reminder));
+            match (sent, reminder) {
+                (Ok(false), _) => {}
+                (Ok(true), Reminder::Overdue) => report.overdue += 1,
+                (Ok(true), _) => report.due_soon += 1,
+                (Err(err), _) => log::error!(
+                    "Error sending {} reminder for task {}: {:?}",
+                    reminder.kind(),
+                    task.id,
+                    err
+                ),
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+/// Open tasks in the reminder window that were not reminded about for their current due date.
+fn due_tasks(conn: &mut PgConnection, reminder: Reminder) -> Result<Vec<Task>, DieselError> {
+    let (from, to) = reminder.window(Utc::now().naive_utc());
+
+    let mut tasks = tasks_table::tasks
+        .filter(tasks_data::due_date.le(to))
+        .filter(tasks_data::status_id.ne_all([Status::Completed.id(), Status::Canceled.id()]))
+        .filter(diesel::dsl::not(exists(
+            task_reminders_table::task_reminders
+                .filter(task_reminders_data::task_id.eq(tasks_data::id))
+                .filter(task_reminders_data::kind.eq(reminder.kind()))
+                .filter(
+                    task_reminders_data::due_date
+                        .nullable()
+                        .eq(tasks_data::due_date),
+                ),
+        )))
+        .select(Task::as_select())
+        .into_boxed();
+
+    if let Some(from) = from {
+        tasks = tasks.filter(tasks_data::due_date.gt(from));
+    }
+
+    tasks.load(conn)
+}
+
+/// Records the reminder and notifies, returns false when another worker already sent it.
+fn remind(conn: &mut PgConnection, task: &Task, reminder: Reminder) -> Result<bool, DieselError> {
+    let Some(due_date) = task.due_date else {
+        return Ok(false);
+    };
+
+    let inserted = diesel::insert_into(task_reminders_table::task_reminders)
+        .values((
+            task_reminders_data::task_id.eq(task.id),
+            task_reminders_data::kind.eq(reminder.kind()),
+            task_reminders_data::due_date.eq(due_date),
+        ))
+        .on_conflict_do_nothing()
+        .execute(conn)?;
+
+    if inserted == 0 {
+        return Ok(false);
+    }
+
+    let link = format!("/workspace/{}/tasks/{}", task.workspace_id, task.id);
+    let due = due_date.format("%Y-%m-%d %H:%M UTC").to_string();
+
+    match reminder {
+        Reminder::DueIn24Hours | Reminder::DueIn1Hour => notify(
+            conn,
+            task.worker_id,
+            &Notification::new(NotificationEvent::TaskDueSoon, link)
+                .param("task", task.title.clone())
+                .param("due_date", due),
+        )?,
+        Reminder::Overdue => {
+            let assignee = auth_users_table::auth_users
+                .find(task.worker_id)
+                .select(auth_users_data::username)
+                .first::<String>(conn)?;
+
+            for user_id in overdue_recipients(conn, task)? {
+                notify(
+                    conn,
+                    user_id,
+                    &Notification::new(NotificationEvent::TaskOverdue, link.clone())
+                        .param("task", task.title.clone())
+                        .param("assignee", assignee.clone())
+                        .param("due_date", due.clone()),
+                )?;
+            }
+        }
+    }
+
+    Ok(true)
+}
+
+/// The assigner, the workspace owner and its admins and managers, without the assignee.
+fn overdue_recipients(conn: &mut PgConnection, task: &Task) -> Result<Vec<i32>, DieselError> {
+    let owner_id = workspaces_table::workspaces
+        .find(task.workspace_id)
+        .select(workspaces_data::owner_id)
+        .first::<i32>(conn)?;
+
+    let managers = workspace_users_table::workspace_users
+        .inner_join(workspace_roles_data::table)
+        .filter(workspace_users_data::workspace_id.eq(task.workspace_id))
+        .filter(workspace_roles_data::name.eq_any(MANAGER_ROLES))
+        .select(workspace_users_data::user_id)
+        .load::<i32>(conn)?;
+
+    let mut recipients = vec![task.assigner_id, owner_id];
+    recipients.extend(managers);
+    recipients.sort_unstable();
+    recipients.dedup();
+    recipients.retain(|user_id| *user_id != task.worker_id);
+
+    Ok(recipients)
+}
@@ -0,0 +1,98 @@
+use crate::api_error::ApiError;
+use crate::auth::find_user::{Find, FindData};
+use crate::auth::sessions::ClientInfo;
+use crate::auth::AuthenticatedUser;
+use crate::buisness_logic::audit::{self, AuditAction, AuditEvent, AuditTarget};
+use crate::buisness_logic::notifications::{notify, Notification, NotificationEvent};
+use crate::buisness_logic::workspace::create_task::Status;
+use crate::i18n::{self, keys, Locale};
+use crate::models::Task;
+use crate::response::Response as Res;
+use crate::schema::tasks as tasks_data;
+use crate::schema::tasks::dsl as tasks_table;
+use crate::validation::ValidatedJson;
+use crate::{est_conn, DPool};
+
+use actix_web::web::Path;
+use actix_web::{put, HttpResponse};
+use diesel::result::Error as DieselError;
+use diesel::{Connection, ExpressionMethods, QueryDsl, RunQueryDsl, SelectableHelper};
+use serde::Deserialize;
+use validator::Validate;
+
+#[derive(Deserialize)]
+struct TaskPath {
+    workspace_id: i32,
+    task_id: i32,
+}
+
+#[derive(Deserialize, Validate)]
+struct UpdateTaskStatusRequest {
+    status: Status,
+}
+
+/// Lets the assigner or the assignee move a task, the other one is notified.
+#[put("/workspace/{workspace_id}/tasks/{task_id}/status")]
+pub async fn update_task_status(
+    user: AuthenticatedUser,
+    path: Path<TaskPath>,
+    req: ValidatedJson<UpdateTaskStatusRequest>,
+    client: ClientInfo,
+    pool: DPool,
+    locale: Locale,
+) -> Result<HttpResponse, ApiError> {
+    let actor = <FindData as Find>::find_auth_user_by_email(user.email, pool.clone())
+        .await
+        .map_err(ApiError::or_not_found(keys::USER_NOT_FOUND))?;
+
+    let conn = &mut est_conn(pool);
+    let task = tasks_table::tasks
+        .filter(tasks_data::id.eq(path.task_id))
+        .filter(tasks_data::workspace_id.eq(path.workspace_id))
+        .select(Task::as_select())
+        .first(conn)
+        .map_err(ApiError::or_not_found(keys::TASK_NOT_FOUND))?;
+
+    let other_party = if actor.id == task.assigner_id {
+        task.worker_id
+    } else if actor.id == task.worker_id {
+        task.assigner_id
+    } else {
+        return Err(ApiError::Forbidden(keys::NOT_TASK_PARTICIPANT));
+    };
+
+    conn.transaction::<_, DieselError, _>(|conn| {
+        diesel::update(tasks_table::tasks.find(task.id))
+            .set(tasks_data::status_id.eq(req.status.id()))
+            .execute(conn)?;
+
+        audit::record(
+            conn,
+            AuditEvent::new(AuditAction::TaskStatusChanged)
+                .actor(actor.id)
+                .target(AuditTarget::Task, task.id)
+                .workspace(task.workspace_id)
+                .ip(client.ip.clone())
+                .param("previous_status_id", task.status_id)
+                .param("status_id", req.status.id()),
+        )?;
+
+        if other_party != actor.id {
+            notify(
+                conn,
+                other_party,
+                &Notification::new(
+                    NotificationEvent::TaskStatusChanged,
+                    format!("/workspace/{}/tasks/{}", task.workspace_id, task.id),
+                )
+                .param("user", actor.username.clone())
+                .param("task", task.title.clone())
+                .param("status", req.status.name()),
+            )?;
+        }
+
+        Ok(())
+    })?;
+
+    Ok(HttpResponse::Ok().json(Res::new(i18n::t(locale, keys::TASK_STATUS_UPDATED))))
+}
@@ -1,69 +1,65 @@
+use crate::api_error::ApiError;
+use crate::auth::sessions::ClientInfo;
+use crate::auth::AuthenticatedUser;
+use crate::buisness_logic::audit::{self, AuditAction, AuditEvent, AuditTarget};
+use crate::buisness_logic::workspace::require_workspace_manager;
+use crate::i18n::{self, keys, Locale};
 use crate::{
     auth::{
         confirmation_token::token::{Cft, ConfirmationToken, TokenEmailType, TokenType},
         find_user::{Find, FindData},
     },
     response::Response as Res,
+    validation::ValidatedJson,
 };
-use actix_web::{post, web::Json, HttpResponse};
+use actix_web::{post, HttpResponse};
 use serde::Deserialize;
+use validator::Validate;
 
-use crate::DPool;
+use crate::{est_conn, DPool};
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Validate)]
 struct InviteToWorkspaceRequest {
     workspace_id: i32,
+    #[validate(email)]
     invited_email: String,
-    inviter_email: String,
 }
 
+/// Invites on behalf of the caller, who has to manage the workspace.
 #[post("/workspace/invitation/create")]
 pub async fn workspace_invitation(
+    user: AuthenticatedUser,
     pool: DPool,
-    req: Json<InviteToWorkspaceRequest>,
-) -> HttpResponse {
-    let invited =
-        <FindData as Find>::exists_by_email(req.invited_email.clone(), pool.clone()).await;
+    req: ValidatedJson<InviteToWorkspaceRequest>,
+    client: ClientInfo,
+    locale: Locale,
+) -> Result<HttpResponse, ApiError> {
+    require_workspace_manager(&mut est_conn(pool.clone()), user.id, req.workspace_id)?;
 
-    match invited {
-        Ok(true) => {}
-        Ok(false) => {
-            return HttpResponse::BadRequest()
-                .json(Res::new("User you are trying to invite doesnt exist"));
-        }
-        Err(e) => {
-            eprintln!("Error while checking if user exists: {:?}", e);
-            return HttpResponse::InternalServerError()
-                .json(Res::new("Server error while checking if user exists"));
-        }
-    }
+    let invited =
+        <FindData as Find>::find_auth_user_by_email(req.invited_email.clone(), pool.clone())
+            .await
+            .map_err(ApiError::or_not_found(keys::INVITEE_NOT_FOUND))?;
 
-    let result = <Cft as ConfirmationToken>::send(
+    <Cft as ConfirmationToken>::send(
         "Worker".to_string(),
         req.invited_email.clone(),
         pool.clone(),
         TokenEmailType::WorkspaceInvitation,
         None,
         false,
-        TokenType::WorkspaceInvitation(req.workspace_id.clone()),
+        TokenType::WorkspaceInvitation(req.workspace_id),
     )
-    .await;
-
-    let workspace =
-        <FindData as Find>::find_workspace_by_owner_email(req.inviter_email.clone(), pool).await;
+    .await?;
 
-    // add errorhndling to find, custom class
-    match workspace {
-        Ok(_) => {}
-        Err(e) => {
-            eprintln!("Erroer while inviting: {:?}", e);
-            return HttpResponse::BadRequest().json(Res::new("Couldnt invite to workspace due to server error. Are you sure that you are owner of workspace?"));
-        }
-    }
+    audit::record_or_log(
+        &mut est_conn(pool),
+        AuditEvent::new(AuditAction::InvitationSent)
+            .actor(user.id)
+            .workspace(req.workspace_id)
+            .ip(client.ip)
+            .target(AuditTarget::User, invited.id),
+    );
 
-    match result {
-        Ok(_) => HttpResponse::Ok().json(Res::new("Invitation created successfully")),
-        Err(_) => HttpResponse::InternalServerError()
-            .json(Res::new("Server error while creating Invitation")),
-    }
+    Ok(HttpResponse::Ok().json(Res::new(i18n::t(locale, keys::INVITATION_CREATED))))
 }
@@ -0,0 +1,201 @@
+use std::any::Any;
+use std::fmt;
+use std::sync::{Mutex, OnceLock};
+
+use lettre::message::{Mailbox, MultiPart, SinglePart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{FileTransport, Message, SmtpTransport, Transport};
+use serde::Serialize;
+
+use super::RenderedEmail;
+use crate::settings::MailSettings;
+
+static MAILER: OnceLock<Box<dyn Mailer>> = OnceLock::new();
+
+#[derive(Debug)]
+pub enum MailerError {
+    Config(String),
+    Address(String),
+    Message(String),
+    Transport(String),
+}
+
+impl fmt::Display for MailerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MailerError::Config(e) => write!(f, "invalid mail configuration: {}", e),
+            MailerError::Address(e) => write!(f, "invalid email address: {}", e),
+            MailerError::Message(e) => write!(f, "could not build email: {}", e),
+            MailerError::Transport(e) => write!(f, "could not deliver email: {}", e),
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct OutgoingEmail {
+    pub to_name: String,
+    pub to_email: String,
+    #[serde(flatten)]
+    pub content: RenderedEmail,
+}
+
+impl OutgoingEmail {
+    fn message(&self, from: &Mailbox) -> Result<Message, MailerError> {
+        let to = format!("{} <{}>", self.to_name, self.to_email)
+            .parse::<Mailbox>()
+            .map_err(|e| MailerError::Address(e.to_string()))?;
+
+        Message::builder()
+            .from(from.clone())
+            .to(to)
+            .subject(self.content.subject.clone())
+            .multipart(
+                MultiPart::alternative()
+                    .singlepart(SinglePart::plain(self.content.text.clone()))
+                    .singlepart(SinglePart::html(self.content.html.clone())),
+            )
+            .map_err(|e| MailerError::Message(e.to_string()))
+    }
+}
+
+/// Delivers rendered emails, the backend is picked by `MAIL_TRANSPORT` at startup.
+pub trait Mailer: Any + Send + Sync {
+    fn send(&self, email: &OutgoingEmail) -> Result<(), MailerError>;
+}
+
+/// Gmail relay, the production transport.
+pub struct SmtpMailer {
+    from: Mailbox,
+    transport: SmtpTransport,
+}
+
+impl Mailer for SmtpMailer {
+    fn send(&self, email: &OutgoingEmail) -> Result<(), MailerError> {
+        self.transport
+            .send(&email.message(&self.from)?)
+            .map(|_| ())
+            .map_err(|e| MailerError::Transport(e.to_string()))
+    }
+}
+
+/// Unauthenticated plain SMTP, e.g. MailHog on `localhost:1025`.
+pub struct LocalSmtpMailer {
+    from: Mailbox,
+    transport: SmtpTransport,
+}
+
+impl Mailer for LocalSmtpMailer {
+    fn send(&self, email: &OutgoingEmail) -> Result<(), MailerError> {
+        self.transport
+            .send(&email.message(&self.from)?)
+            .map(|_| ())
+            .map_err(|e| MailerError::Transport(e.to_string()))
+    }
+}
+
+/// Writes every email as an `.eml` file into a directory.
+pub struct FileMailer {
+    from: Mailbox,
+    transport: FileTransport,
+}
+
+impl Mailer for FileMailer {
+    fn send(&self, email: &OutgoingEmail) -> Result<(), MailerError> {
+        self.transport
+            .send(&email.message(&self.from)?)
+            .map(|_| ())
+            .map_err(|e| MailerError::Transport(e.to_string()))
+    }
+}
+
+/// Keeps the emails in memory so tests can assert on them through `/emails/sent`.
+#[derive(Default)]
+pub struct MemoryMailer {
+    sent: Mutex<Vec<OutgoingEmail>>,
+}
+
+impl MemoryMailer {
+    pub fn sent(&self) -> Vec<OutgoingEmail> {
+        self.sent.lock().unwrap().clone()
+    }
+
+    pub fn clear(&self) {
+        self.sent.lock().unwrap().clear();
+    }
+}
+
+impl Mailer for MemoryMailer {
+    fn send(&self, email: &OutgoingEmail) -> Result<(), MailerError> {
+        self.sent.lock().unwrap().push(email.clone());
+        Ok(())
+    }
+}
+
+fn sender(address: &str) -> Result<Mailbox, MailerError> {
+    format!("Electro-Vision <{}>", address)
+        .parse()
+        .map_err(|e: lettre::address::AddressError| MailerError::Config(e.to_string()))
+}
+
+/// Builds the transport named by `mail.transport`: `smtp`, `local-smtp`, `file` or `memory`.
+pub fn from_settings(settings: &MailSettings) -> Result<Box<dyn Mailer>, MailerError> {
+    match settings.transport.as_str() {
+        "smtp" => {
+            let name = settings.username.clone().ok_or_else(|| {
+                MailerError::Config("mail.username (AUTH_EMAIL_NAME) needs to be set".to_string())
+            })?;
+            let password = settings.password.as_ref().ok_or_else(|| {
+                MailerError::Config(
+                    "mail.password (AUTH_EMAIL_PASSWORD) needs to be set".to_string(),
+                )
+            })?;
+
+            Ok(Box::new(SmtpMailer {
+                from: sender(&name)?,
+                transport: SmtpTransport::relay(&settings.smtp_host)
+                    .map_err(|e| MailerError::Config(e.to_string()))?
+                    .credentials(Credentials::new(name, password.expose().to_string()))
+                    .build(),
+            }))
+        }
+        "local-smtp" => Ok(Box::new(LocalSmtpMailer {
+            from: sender(&settings.from)?,
+            transport: SmtpTransport::builder_dangerous(&settings.local_smtp_host)
+                .port(settings.local_smtp_port)
+                .build(),
+        })),
+        "file" => {
+            let dir = &settings.file_dir;
+            std::fs::create_dir_all(dir)
+                .map_err(|e| MailerError::Config(format!("{}: {}", dir.display(), e)))?;
+
+            Ok(Box::new(FileMailer {
+                from: sender(&settings.from)?,
+                transport: FileTransport::new(dir),
+            }))
+        }
+        "memory" => Ok(Box::new(MemoryMailer::default())),
+        other => Err(MailerError::Config(format!(
+            "unknown mail transport {}, expected smtp, local-smtp, file or memory",
+            other
+        ))),
+    }
+}
+
+pub fn init(mailer: Box<dyn Mailer>) {
+    // a second init keeps the mailer from the first one
+    let _ = MAILER.set(mailer);
+}
+
+pub fn get() -> Result<&'static dyn Mailer, MailerError> {
+    MAILER
+        .get()
+        .map(|mailer| mailer.as_ref())
+        .ok_or_else(|| MailerError::Config("mailer is not initialized".to_string()))
+}
+
+/// The in-memory mailer, when it is the configured transport.
+pub fn memory() -> Option<&'static MemoryMailer> {
+    let mailer: &dyn Any = get().ok()?;
+    mailer.downcast_ref::<MemoryMailer>()
+}
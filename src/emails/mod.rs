@@ -0,0 +1,142 @@
+use std::path::Path;
+use std::sync::OnceLock;
+
+use serde::Serialize;
+use tera::{Context, Tera};
+
+use crate::i18n::{self, keys, Locale};
+
+pub mod mailer;
+pub mod outbox;
+pub mod preview;
+pub mod sent;
+pub mod status;
+
+static TEMPLATES: OnceLock<Tera> = OnceLock::new();
+
+/// Every email has `<locale>/<name>.html` and `<locale>/<name>.txt` templates.
+#[derive(Debug, Clone, Copy)]
+pub enum EmailType {
+    AccountVerification,
+    AccountVerificationResend,
+    ChangePassword,
+    ChangePasswordResend,
+    WorkspaceInvitation,
+    AccountDeletion,
+    Notification,
+}
+
+impl EmailType {
+    pub const ALL: [EmailType; 7] = [
+        EmailType::AccountVerification,
+        EmailType::AccountVerificationResend,
+        EmailType::ChangePassword,
+        EmailType::ChangePasswordResend,
+        EmailType::WorkspaceInvitation,
+        EmailType::AccountDeletion,
+        EmailType::Notification,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            EmailType::AccountVerification => "account_verification",
+            EmailType::AccountVerificationResend => "account_verification_resend",
+            EmailType::ChangePassword => "password_reset",
+            EmailType::ChangePasswordResend => "password_reset_resend",
+            EmailType::WorkspaceInvitation => "workspace_invitation",
+            EmailType::AccountDeletion => "account_deletion",
+            EmailType::Notification => "notification",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<EmailType> {
+        EmailType::ALL
+            .into_iter()
+            .find(|email_type| email_type.name() == name)
+    }
+
+    fn subject_key(self) -> &'static str {
+        match self {
+            EmailType::AccountVerification | EmailType::AccountVerificationResend => {
+                keys::EMAIL_SUBJECT_ACCOUNT_VERIFICATION
+            }
+            EmailType::ChangePassword | EmailType::ChangePasswordResend => {
+                keys::EMAIL_SUBJECT_PASSWORD_RESET
+            }
+            EmailType::WorkspaceInvitation => keys::EMAIL_SUBJECT_WORKSPACE_INVITATION,
+            EmailType::AccountDeletion => keys::EMAIL_SUBJECT_ACCOUNT_DELETION,
+            EmailType::Notification => keys::EMAIL_SUBJECT_NOTIFICATION,
+        }
+    }
+
+    fn template(self, locale: Locale, extension: &str) -> String {
+        format!("{}/{}.{}", locale.code(), self.name(), extension)
+    }
+}
+
+#[derive(Serialize)]
+pub struct EmailContext {
+    pub username: String,
+    pub link: String,
+    /// How long the link stays valid, e.g. "15 minutes".
+    pub expires_in: String,
+}
+
+/// Context of `EmailType::Notification`, which also shows the notification text.
+#[derive(Serialize)]
+pub struct NotificationEmailContext {
+    pub username: String,
+    pub link: String,
+    pub title: String,
+    pub message: String,
+}
+
+#[derive(Clone, Serialize)]
+pub struct RenderedEmail {
+    pub subject: String,
+    pub html: String,
+    pub text: String,
+}
+
+/// Loads the templates from `dir`, fails when any email is missing a locale or a format.
+pub fn init(dir: &Path) -> Result<(), tera::Error> {
+    let tera = Tera::new(&format!("{}/**/*", dir.display()))?;
+
+    for locale in Locale::ALL {
+        for email_type in EmailType::ALL {
+            for extension in ["html", "txt"] {
+                let name = email_type.template(locale, extension);
+                if !tera.get_template_names().any(|template| template == name) {
+                    return Err(tera::Error::msg(format!(
+                        "missing email template {}/{}",
+                        dir.display(),
+                        name
+                    )));
+                }
+            }
+        }
+    }
+
+    // a second init keeps the templates from the first one
+    let _ = TEMPLATES.set(tera);
+    Ok(())
+}
+
+pub fn render(
+    email_type: EmailType,
+    locale: Locale,
+    context: &impl Serialize,
+) -> Result<RenderedEmail, tera::Error> {
+    let tera = TEMPLATES
+        .get()
+        .ok_or_else(|| tera::Error::msg("email templates are not loaded"))?;
+
+    let mut tera_context = Context::from_serialize(context)?;
+    tera_context.insert("lang", locale.code());
+
+    Ok(RenderedEmail {
+        subject: i18n::t(locale, email_type.subject_key()).to_string(),
+        html: tera.render(&email_type.template(locale, "html"), &tera_context)?,
+        text: tera.render(&email_type.template(locale, "txt"), &tera_context)?,
+    })
+}
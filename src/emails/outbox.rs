@@ -0,0 +1,156 @@
+use chrono::{Duration, Utc};
+use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+use diesel::PgConnection;
+
+use super::mailer::{Mailer, OutgoingEmail};
+use super::{EmailType, RenderedEmail};
+use crate::constants::{
+    EMAIL_MAX_ATTEMPTS, EMAIL_OUTBOX_BATCH_SIZE, EMAIL_RETRY_BASE_DELAY, EMAIL_RETRY_MAX_DELAY,
+};
+use crate::models::OutboxEmail;
+use crate::models_insertable::NewOutboxEmail;
+use crate::schema::email_outbox as outbox_data;
+use crate::schema::email_outbox::dsl as outbox_table;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutboxStatus {
+    Pending,
+    Sent,
+    /// Gave up after `EMAIL_MAX_ATTEMPTS`, only an admin retry sends it again.
+    Dead,
+}
+
+impl OutboxStatus {
+    pub fn name(self) -> &'static str {
+        match self {
+            OutboxStatus::Pending => "pending",
+            OutboxStatus::Sent => "sent",
+            OutboxStatus::Dead => "dead",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<OutboxStatus> {
+        [
+            OutboxStatus::Pending,
+            OutboxStatus::Sent,
+            OutboxStatus::Dead,
+        ]
+        .into_iter()
+        .find(|status| status.name() == name)
+    }
+}
+
+#[derive(Default)]
+pub struct DeliveryReport {
+    pub sent: usize,
+    pub retried: usize,
+    pub dead: usize,
+}
+
+/// Queues an email, call it inside the transaction that creates the token the email carries.
+pub fn enqueue(
+    conn: &mut PgConnection,
+    email_type: EmailType,
+    email: &OutgoingEmail,
+) -> Result<i32, DieselError> {
+    diesel::insert_into(outbox_table::email_outbox)
+        .values(NewOutboxEmail {
+            email_type: email_type.name(),
+            to_name: &email.to_name,
+            to_email: &email.to_email,
+            subject: &email.content.subject,
+            html_body: &email.content.html,
+            text_body: &email.content.text,
+        })
+        .returning(outbox_data::id)
+        .get_result(conn)
+}
+
+/// 30s, 1min, 2min, ... capped at `EMAIL_RETRY_MAX_DELAY`.
+fn retry_delay(attempts: i32) -> Duration {
+    let exponent = attempts.saturating_sub(1).clamp(0, 30) as u32;
+    let delay = EMAIL_RETRY_BASE_DELAY.saturating_mul(2i64.saturating_pow(exponent));
+    Duration::seconds(delay.min(EMAIL_RETRY_MAX_DELAY))
+}
+
+/// Sends the pending emails that are due, rows are locked so several workers can share the queue.
+pub fn deliver_due(
+    conn: &mut PgConnection,
+    mailer: &dyn Mailer,
+) -> Result<DeliveryReport, DieselError> {
+    conn.transaction(|conn| {
+        let due = outbox_table::email_outbox
+            .filter(outbox_data::status.eq(OutboxStatus::Pending.name()))
+            .filter(outbox_data::next_attempt_at.le(Utc::now().naive_utc()))
+            .order(outbox_data::next_attempt_at.asc())
+            .limit(EMAIL_OUTBOX_BATCH_SIZE)
+            .for_update()
+            .skip_locked()
+            .select(OutboxEmail::as_select())
+            .load(conn)?;
+
+        let mut report = DeliveryReport::default();
+        for queued in due {
+            let attempts = queued.attempts + 1;
+            let now = Utc::now().naive_utc();
+            let email = OutgoingEmail {
+                to_name: queued.to_name,
+                to_email: queued.to_email,
+                content: RenderedEmail {
+                    subject: queued.subject,
+                    html: queued.html_body,
+                    text: queued.text_body,
+                },
+            };
+
+            let update = diesel::update(outbox_table::email_outbox.find(queued.id));
+            match mailer.send(&email) {
+                Ok(_) => {
+                    update
+                        .set((
+                            outbox_data::status.eq(OutboxStatus::Sent.name()),
+                            outbox_data::attempts.eq(attempts),
+                            outbox_data::sent_at.eq(now),
+                        ))
+                        .execute(conn)?;
+                    report.sent += 1;
+                }
+                Err(e) => {
+                    let status = if attempts >= EMAIL_MAX_ATTEMPTS {
+                        report.dead += 1;
+                        log::error!(
+                            "giving up on {} email {} after {} attempts: {}",
+                            queued.email_type,
+                            queued.id,
+                            attempts,
+                            e
+                        );
+                        OutboxStatus::Dead
+                    } else {
+                        report.retried += 1;
+                        log::warn!(
+                            "{} email {} failed, attempt {}: {}",
+                            queued.email_type,
+                            queued.id,
+                            attempts,
+                            e
+                        );
+                        OutboxStatus::Pending
+                    };
+
+                    update
+                        .set((
+                            outbox_data::status.eq(status.name()),
+                            outbox_data::attempts.eq(attempts),
+                            outbox_data::last_error.eq(e.to_string()),
+                            outbox_data::next_attempt_at.eq(now + retry_delay(attempts)),
+                        ))
+                        .execute(conn)?;
+                }
+            }
+        }
+
+        Ok(report)
+    })
+}
@@ -0,0 +1,92 @@
+use actix_web::web::{Data, Path, Query};
+use actix_web::{get, HttpResponse};
+use serde::Deserialize;
+
+use super::{render, EmailContext, EmailType, NotificationEmailContext};
+use crate::api_error::ApiError;
+use crate::i18n::{self, keys, Locale};
+use crate::settings::Settings;
+
+#[derive(Deserialize)]
+struct PreviewPath {
+    template: String,
+    locale: String,
+}
+
+#[derive(Deserialize, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum PreviewFormat {
+    #[default]
+    Html,
+    Text,
+}
+
+#[derive(Deserialize)]
+struct PreviewQuery {
+    #[serde(default)]
+    format: PreviewFormat,
+}
+
+/// Renders an email template with sample data, e.g. `/emails/preview/password_reset/pl?format=text`.
+#[get("/emails/preview/{template}/{locale}")]
+pub async fn preview_email(
+    path: Path<PreviewPath>,
+    query: Query<PreviewQuery>,
+    settings: Data<Settings>,
+) -> Result<HttpResponse, ApiError> {
+    let email_type =
+        EmailType::from_name(&path.template).ok_or(ApiError::NotFound(keys::TEMPLATE_NOT_FOUND))?;
+    let locale =
+        Locale::from_tag(&path.locale).ok_or(ApiError::NotFound(keys::LOCALE_NOT_FOUND))?;
+
+    let username = "Jan Kowalski".to_string();
+    let link = format!("{}/preview/{}", settings.frontend_url, email_type.name());
+    let email = match email_type {
+        EmailType::Notification => render(
+            email_type,
+            locale,
+            &NotificationEmailContext {
+                username,
+                link,
+                title: "Lorem ipsum".to_string(),
+                message: "Lorem ipsum dolor sit amet, consectetur adipiscing elit.".to_string(),
+            },
+        )
+        .map(|mut email| {
+            email.subject =
+                i18n::interpolate(&email.subject, &[("title", "Lorem ipsum".to_string())]);
+            email
+        }),
+        _ => {
+            let tokens = &settings.tokens;
+            let lifetime = match email_type {
+                EmailType::ChangePassword | EmailType::ChangePasswordResend => {
+                    tokens.password_reset()
+                }
+                EmailType::WorkspaceInvitation => tokens.workspace_invitation(),
+                EmailType::AccountDeletion => tokens.account_deletion(),
+                _ => tokens.account_verification(),
+            };
+
+            render(
+                email_type,
+                locale,
+                &EmailContext {
+                    username,
+                    link,
+                    expires_in: i18n::duration(locale, lifetime),
+                },
+            )
+        }
+    }
+    .map_err(|e| ApiError::Internal(format!("Error rendering email preview: {:?}", e)))?;
+
+    Ok(match query.format {
+        PreviewFormat::Html => HttpResponse::Ok()
+            .content_type("text/html; charset=utf-8")
+            .body(email.html),
+        PreviewFormat::Text => HttpResponse::Ok()
+            .content_type("text/plain; charset=utf-8")
+            .body(format!("Subject: {}\n\n{}", email.subject, email.text)),
+    })
+}
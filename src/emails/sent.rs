@@ -0,0 +1,22 @@
+use actix_web::{delete, get, HttpResponse};
+
+use super::mailer;
+use crate::api_error::ApiError;
+use crate::i18n::keys;
+use crate::response::Response as Res;
+
+/// Emails captured by the `memory` transport, not found with any other transport.
+#[get("/emails/sent")]
+pub async fn sent_emails() -> Result<HttpResponse, ApiError> {
+    let mailer = mailer::memory().ok_or(ApiError::NotFound(keys::NOT_FOUND))?;
+
+    Ok(HttpResponse::Ok().json(Res::new(mailer.sent())))
+}
+
+#[delete("/emails/sent")]
+pub async fn clear_sent_emails() -> Result<HttpResponse, ApiError> {
+    let mailer = mailer::memory().ok_or(ApiError::NotFound(keys::NOT_FOUND))?;
+    mailer.clear();
+
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -0,0 +1,79 @@
+use actix_web::web::{Path, Query};
+use actix_web::{get, post, HttpResponse};
+use chrono::Utc;
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl, SelectableHelper};
+use serde::Deserialize;
+
+use super::outbox::OutboxStatus;
+use crate::api_error::ApiError;
+use crate::auth::AdminUser;
+use crate::i18n::{self, keys, Locale};
+use crate::models::OutboxEmailStatus;
+use crate::schema::email_outbox as outbox_data;
+use crate::schema::email_outbox::dsl as outbox_table;
+use crate::{est_conn, response::Response as Res, DPool};
+
+const STATUS_PAGE_SIZE: i64 = 100;
+
+#[derive(Deserialize)]
+struct StatusQuery {
+    status: Option<String>,
+}
+
+/// Latest queued emails with their delivery state, e.g. `/admin/emails?status=dead`.
+#[get("/admin/emails")]
+pub async fn list_outbox(
+    _admin: AdminUser,
+    query: Query<StatusQuery>,
+    pool: DPool,
+) -> Result<HttpResponse, ApiError> {
+    let mut emails = outbox_table::email_outbox
+        .select(OutboxEmailStatus::as_select())
+        .order(outbox_data::created_at.desc())
+        .limit(STATUS_PAGE_SIZE)
+        .into_boxed();
+
+    if let Some(status) = &query.status {
+        let status = OutboxStatus::from_name(status)
+            .ok_or(ApiError::BadRequest(keys::INVALID_OUTBOX_STATUS))?;
+        emails = emails.filter(outbox_data::status.eq(status.name()));
+    }
+
+    let emails = emails.load(&mut est_conn(pool))?;
+
+    Ok(HttpResponse::Ok().json(Res::new(emails)))
+}
+
+/// Puts a failed email back in the queue with a fresh attempt budget.
+#[post("/admin/emails/{id}/retry")]
+pub async fn retry_email(
+    admin: AdminUser,
+    id: Path<i32>,
+    pool: DPool,
+    locale: Locale,
+) -> Result<HttpResponse, ApiError> {
+    let id = id.into_inner();
+    let conn = &mut est_conn(pool);
+
+    let status = outbox_table::email_outbox
+        .find(id)
+        .select(outbox_data::status)
+        .first::<String>(conn)
+        .map_err(ApiError::or_not_found(keys::OUTBOX_EMAIL_NOT_FOUND))?;
+
+    if status == OutboxStatus::Sent.name() {
+        return Err(ApiError::Conflict(keys::EMAIL_ALREADY_SENT));
+    }
+
+    diesel::update(outbox_table::email_outbox.find(id))
+        .set((
+            outbox_data::status.eq(OutboxStatus::Pending.name()),
+            outbox_data::attempts.eq(0),
+            outbox_data::next_attempt_at.eq(Utc::now().naive_utc()),
+        ))
+        .execute(conn)?;
+
+    log::info!("{} requeued email {}", admin.email, id);
+
+    Ok(HttpResponse::Ok().json(Res::new(i18n::t(locale, keys::EMAIL_REQUEUED))))
+}
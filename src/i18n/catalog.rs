@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::Error as IoError;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use super::keys;
+use super::Locale;
+
+type Catalog = HashMap<String, String>;
+
+static CATALOGS: OnceLock<HashMap<Locale, Catalog>> = OnceLock::new();
+
+#[derive(Debug)]
+pub enum CatalogError {
+    Io(PathBuf, IoError),
+    Json(PathBuf, serde_json::Error),
+    MissingKeys(Locale, Vec<&'static str>),
+}
+
+impl fmt::Display for CatalogError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CatalogError::Io(path, err) => write!(f, "can not read {}: {}", path.display(), err),
+            CatalogError::Json(path, err) => {
+                write!(f, "can not parse {}: {}", path.display(), err)
+            }
+            CatalogError::MissingKeys(locale, missing) => write!(
+                f,
+                "catalog {} is missing keys: {}",
+                locale.code(),
+                missing.join(", ")
+            ),
+        }
+    }
+}
+
+fn load(dir: &Path, locale: Locale) -> Result<Catalog, CatalogError> {
+    let path = dir.join(format!("{}.json", locale.code()));
+    let file = File::open(&path).map_err(|err| CatalogError::Io(path.clone(), err))?;
+    let catalog: Catalog =
+        serde_json::from_reader(file).map_err(|err| CatalogError::Json(path.clone(), err))?;
+
+    let missing: Vec<&'static str> = keys::ALL
+        .iter()
+        .copied()
+        .filter(|key| !catalog.contains_key(*key))
+        .collect();
+    if !missing.is_empty() {
+        return Err(CatalogError::MissingKeys(locale, missing));
+    }
+
+    for key in catalog.keys() {
+        if !keys::ALL.contains(&key.as_str()) {
+            log::warn!("Catalog {} has unused key {}", locale.code(), key);
+        }
+    }
+
+    Ok(catalog)
+}
+
+/// Loads `<dir>/<locale>.json` for every supported locale, fails when a key used in code is missing.
+pub fn init(dir: &Path) -> Result<(), CatalogError> {
+    let catalogs = Locale::ALL
+        .into_iter()
+        .map(|locale| load(dir, locale).map(|catalog| (locale, catalog)))
+        .collect::<Result<HashMap<_, _>, _>>()?;
+
+    // a second init keeps the catalogs from the first one
+    let _ = CATALOGS.set(catalogs);
+    Ok(())
+}
+
+/// Message for `key`, falls back to English and then to the key itself.
+pub fn t(locale: Locale, key: &'static str) -> &'static str {
+    lookup(locale, key).unwrap_or(key)
+}
+
+/// Replaces `{name}` placeholders in `template`.
+pub fn interpolate(template: &str, params: &[(&str, String)]) -> String {
+    params
+        .iter()
+        .fold(template.to_string(), |message, (name, value)| {
+            message.replace(&format!("{{{}}}", name), value)
+        })
+}
+
+/// Same as [`t`] for keys built at runtime, `None` when no catalog knows the key.
+pub fn lookup(locale: Locale, key: &str) -> Option<&'static str> {
+    let catalogs = CATALOGS.get()?;
+    catalogs
+        .get(&locale)
+        .and_then(|catalog| catalog.get(key))
+        .or_else(|| catalogs.get(&Locale::En)?.get(key))
+        .map(String::as_str)
+}
@@ -0,0 +1,66 @@
+use chrono::Duration;
+
+use super::{interpolate, keys, t, Locale};
+
+enum Plural {
+    One,
+    Few,
+    Many,
+}
+
+impl Locale {
+    fn plural(self, count: i64) -> Plural {
+        match self {
+            Locale::En if count == 1 => Plural::One,
+            Locale::En => Plural::Many,
+            Locale::Pl if count == 1 => Plural::One,
+            Locale::Pl
+                if (2..=4).contains(&(count % 10)) && !(12..=14).contains(&(count % 100)) =>
+            {
+                Plural::Few
+            }
+            Locale::Pl => Plural::Many,
+        }
+    }
+}
+
+/// `duration` in its largest whole unit, e.g. "7 days" or "15 minut", partial minutes round up.
+pub fn duration(locale: Locale, duration: Duration) -> String {
+    let seconds = duration.num_seconds().max(0);
+    let (count, [one, few, many]) = if seconds > 0 && seconds % 86400 == 0 {
+        (
+            seconds / 86400,
+            [
+                keys::DURATION_DAYS_ONE,
+                keys::DURATION_DAYS_FEW,
+                keys::DURATION_DAYS_MANY,
+            ],
+        )
+    } else if seconds > 0 && seconds % 3600 == 0 {
+        (
+            seconds / 3600,
+            [
+                keys::DURATION_HOURS_ONE,
+                keys::DURATION_HOURS_FEW,
+                keys::DURATION_HOURS_MANY,
+            ],
+        )
+    } else {
+        (
+            (seconds + 59) / 60,
+            [
+                keys::DURATION_MINUTES_ONE,
+                keys::DURATION_MINUTES_FEW,
+                keys::DURATION_MINUTES_MANY,
+            ],
+        )
+    };
+
+    let key = match locale.plural(count) {
+        Plural::One => one,
+        Plural::Few => few,
+        Plural::Many => many,
+    };
+
+    interpolate(t(locale, key), &[("count", count.to_string())])
+}
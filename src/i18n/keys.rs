@@ -0,0 +1,185 @@
+//! Every message key the code can emit, catalogs are checked against `ALL` at startup.
+
+macro_rules! keys {
+    ($($name:ident = $key:literal,)*) => {
+        // validator codes are turned into keys at runtime, so some constants are never named
+        $(#[allow(dead_code)] pub const $name: &str = $key;)*
+
+        pub const ALL: &[&str] = &[$($key),*];
+    };
+}
+
+keys! {
+    // generic errors
+    VALIDATION_FAILED = "validation_failed",
+    INVALID_PAYLOAD = "invalid_payload",
+    NOT_FOUND = "not_found",
+    ALREADY_EXISTS = "already_exists",
+    INVALID_REFERENCE = "invalid_reference",
+    DATABASE_ERROR = "database_error",
+    INTERNAL_ERROR = "internal_error",
+
+    // tokens and sessions
+    TOKEN_NOT_FOUND = "token_not_found",
+    TOKEN_EXPIRED = "token_expired",
+    TOKEN_ALREADY_EXISTS = "token_already_exists",
+    MISSING_TOKEN = "missing_token",
+    SESSION_INVALID = "session_invalid",
+    SESSION_NOT_FOUND = "session_not_found",
+    OIDC_PROVIDER_NOT_FOUND = "oidc_provider_not_found",
+    OIDC_STATE_INVALID = "oidc_state_invalid",
+    OIDC_LOGIN_FAILED = "oidc_login_failed",
+    OIDC_EMAIL_NOT_VERIFIED = "oidc_email_not_verified",
+
+    // accounts
+    USER_NOT_FOUND = "user_not_found",
+    USERNAME_NOT_FOUND = "username_not_found",
+    EMAIL_NOT_FOUND = "email_not_found",
+    EMAIL_TAKEN = "email_taken",
+    PASSWORD_INCORRECT = "password_incorrect",
+    ACCOUNT_ALREADY_VERIFIED = "account_already_verified",
+    ACCOUNT_DELETION_ALREADY_SCHEDULED = "account_deletion_already_scheduled",
+    NO_PENDING_ACCOUNT_DELETION = "no_pending_account_deletion",
+    ADMIN_REQUIRED = "admin_required",
+    STAFF_REQUIRED = "staff_required",
+    ACCOUNT_DISABLED = "account_disabled",
+    ACCOUNT_INACTIVE = "account_inactive",
+    ROLE_NOT_FOUND = "role_not_found",
+    ROLE_NOT_ASSIGNED = "role_not_assigned",
+    CANNOT_MODIFY_OWN_ACCOUNT = "cannot_modify_own_account",
+
+    // full users
+    FULL_USER_NOT_FOUND = "full_user_not_found",
+    FULL_USER_ALREADY_EXISTS = "full_user_already_exists",
+    NOTHING_TO_UPDATE = "nothing_to_update",
+    MISSING_COUNTRY_OF_ORIGIN = "missing_country_of_origin",
+    MISSING_DIAL_CODE = "missing_dial_code",
+    INVALID_COUNTRY_OF_ORIGIN = "invalid_country_of_origin",
+    INVALID_CITIZENSHIP = "invalid_citizenship",
+    INVALID_DIAL_CODE = "invalid_dial_code",
+
+    // workspaces
+    WORKSPACE_NOT_FOUND = "workspace_not_found",
+    WORKSPACE_NAME_TAKEN = "workspace_name_taken",
+    PLAN_FILE_NAME_TAKEN = "plan_file_name_taken",
+    NOT_WORKSPACE_OWNER = "not_workspace_owner",
+    NOT_WORKSPACE_ADMIN = "not_workspace_admin",
+    INVITEE_NOT_FOUND = "invitee_not_found",
+    USER_ALREADY_IN_WORKSPACE = "user_already_in_workspace",
+    NOT_INVITED = "not_invited",
+    INVITATION_EXPIRED = "invitation_expired",
+    INVITATION_NOT_FOUND = "invitation_not_found",
+    ASSIGNER_NOT_FOUND = "assigner_not_found",
+    ASSIGNEE_NOT_FOUND = "assignee_not_found",
+    ASSIGNEE_NOT_WORKSPACE_MEMBER = "assignee_not_workspace_member",
+    TASK_TITLE_TAKEN = "task_title_taken",
+    TASK_NOT_FOUND = "task_not_found",
+    NOT_TASK_PARTICIPANT = "not_task_participant",
+    MENTOR_NOT_FOUND = "mentor_not_found",
+    NOTIFICATION_NOT_FOUND = "notification_not_found",
+    NOT_WORKSPACE_MEMBER = "not_workspace_member",
+
+    // attachments
+    ATTACHMENT_NOT_FOUND = "attachment_not_found",
+    ATTACHMENT_FILE_MISSING = "attachment_file_missing",
+    ATTACHMENT_TOO_LARGE = "attachment_too_large",
+    ATTACHMENT_UPLOAD_INVALID = "attachment_upload_invalid",
+    ATTACHMENT_URL_INVALID = "attachment_url_invalid",
+    ATTACHMENT_IN_OTHER_WORKSPACE = "attachment_in_other_workspace",
+    ATTACHMENT_ALREADY_ON_TASK = "attachment_already_on_task",
+    ATTACHMENT_EDIT_FORBIDDEN = "attachment_edit_forbidden",
+    TASK_ATTACHMENT_NOT_FOUND = "task_attachment_not_found",
+    TASK_ATTACHMENT_REMOVED = "task_attachment_removed",
+    TASK_ATTACHMENTS_ORDER_INVALID = "task_attachments_order_invalid",
+    WORKSPACE_QUOTA_EXCEEDED = "workspace_quota_exceeded",
+    USER_QUOTA_EXCEEDED = "user_quota_exceeded",
+    ATTACHMENT_IMAGE_INVALID = "attachment_image_invalid",
+    ATTACHMENT_NOT_A_PHOTO = "attachment_not_a_photo",
+
+    // emails
+    TEMPLATE_NOT_FOUND = "template_not_found",
+    LOCALE_NOT_FOUND = "locale_not_found",
+    EMAIL_SUBJECT_ACCOUNT_VERIFICATION = "email_subject_account_verification",
+    EMAIL_SUBJECT_PASSWORD_RESET = "email_subject_password_reset",
+    EMAIL_SUBJECT_WORKSPACE_INVITATION = "email_subject_workspace_invitation",
+    EMAIL_SUBJECT_ACCOUNT_DELETION = "email_subject_account_deletion",
+    EMAIL_SUBJECT_NOTIFICATION = "email_subject_notification",
+    OUTBOX_EMAIL_NOT_FOUND = "outbox_email_not_found",
+    EMAIL_ALREADY_SENT = "email_already_sent",
+    INVALID_OUTBOX_STATUS = "invalid_outbox_status",
+
+    // success messages
+    ACCOUNT_VERIFIED = "account_verified",
+    SESSION_VALID = "session_valid",
+    LOGGED_OUT = "logged_out",
+    SESSION_REVOKED = "session_revoked",
+    OTHER_SESSIONS_REVOKED = "other_sessions_revoked",
+    PASSWORD_CHANGED = "password_changed",
+    PASSWORD_RESET_EMAIL_SENT = "password_reset_email_sent",
+    VERIFICATION_EMAIL_RESENT = "verification_email_resent",
+    INVITATION_ACCEPTED = "invitation_accepted",
+    INVITATION_CREATED = "invitation_created",
+    TASK_CREATED = "task_created",
+    TASK_STATUS_UPDATED = "task_status_updated",
+    PROBLEM_REPORTED = "problem_reported",
+    NOTIFICATIONS_MARKED_READ = "notifications_marked_read",
+    NOTIFICATION_PREFERENCE_UPDATED = "notification_preference_updated",
+    FULL_USER_REGISTERED = "full_user_registered",
+    FULL_USER_UPDATED = "full_user_updated",
+    ACCOUNT_DELETION_EMAIL_SENT = "account_deletion_email_sent",
+    ACCOUNT_DELETION_SCHEDULED = "account_deletion_scheduled",
+    ACCOUNT_DELETION_CANCELLED = "account_deletion_cancelled",
+    LOCALE_UPDATED = "locale_updated",
+    EMAIL_REQUEUED = "email_requeued",
+    USER_DISABLED = "user_disabled",
+    USER_ENABLED = "user_enabled",
+    PASSWORD_RESET_FORCED = "password_reset_forced",
+    ROLE_GRANTED = "role_granted",
+    ROLE_REVOKED = "role_revoked",
+
+    // notifications, `{name}` placeholders are filled from the notification data
+    NOTIFICATION_TASK_ASSIGNED_TITLE = "notification_task_assigned_title",
+    NOTIFICATION_TASK_ASSIGNED_MESSAGE = "notification_task_assigned_message",
+    NOTIFICATION_TASK_STATUS_CHANGED_TITLE = "notification_task_status_changed_title",
+    NOTIFICATION_TASK_STATUS_CHANGED_MESSAGE = "notification_task_status_changed_message",
+    NOTIFICATION_TASK_DUE_SOON_TITLE = "notification_task_due_soon_title",
+    NOTIFICATION_TASK_DUE_SOON_MESSAGE = "notification_task_due_soon_message",
+    NOTIFICATION_TASK_OVERDUE_TITLE = "notification_task_overdue_title",
+    NOTIFICATION_TASK_OVERDUE_MESSAGE = "notification_task_overdue_message",
+    NOTIFICATION_INVITATION_ACCEPTED_TITLE = "notification_invitation_accepted_title",
+    NOTIFICATION_INVITATION_ACCEPTED_MESSAGE = "notification_invitation_accepted_message",
+    NOTIFICATION_PROBLEM_REPORTED_TITLE = "notification_problem_reported_title",
+    NOTIFICATION_PROBLEM_REPORTED_MESSAGE = "notification_problem_reported_message",
+
+    // durations in emails, `{count}` in the accusative, e.g. "valid for 15 minutes"
+    DURATION_MINUTES_ONE = "duration_minutes_one",
+    DURATION_MINUTES_FEW = "duration_minutes_few",
+    DURATION_MINUTES_MANY = "duration_minutes_many",
+    DURATION_HOURS_ONE = "duration_hours_one",
+    DURATION_HOURS_FEW = "duration_hours_few",
+    DURATION_HOURS_MANY = "duration_hours_many",
+    DURATION_DAYS_ONE = "duration_days_one",
+    DURATION_DAYS_FEW = "duration_days_few",
+    DURATION_DAYS_MANY = "duration_days_many",
+
+    // field validation, `validation_<code>` for the validator crate codes
+    VALIDATION_EMAIL = "validation_email",
+    VALIDATION_LENGTH = "validation_length",
+    VALIDATION_LENGTH_BETWEEN = "validation_length_between",
+    VALIDATION_LENGTH_MIN = "validation_length_min",
+    VALIDATION_LENGTH_MAX = "validation_length_max",
+    VALIDATION_RANGE = "validation_range",
+    VALIDATION_INVALID = "validation_invalid",
+    VALIDATION_PASSWORD_TOO_SHORT = "validation_password_too_short",
+    VALIDATION_PASSWORD_TOO_LONG = "validation_password_too_long",
+    VALIDATION_PASSWORD_TOO_WEAK = "validation_password_too_weak",
+    VALIDATION_PHONE_NUMBER_FORMAT = "validation_phone_number_format",
+    VALIDATION_PHONE_NUMBER_LENGTH = "validation_phone_number_length",
+    VALIDATION_PHONE_NUMBER_LENGTH_BETWEEN = "validation_phone_number_length_between",
+    VALIDATION_PHONE_NUMBER_TOO_LONG = "validation_phone_number_too_long",
+    VALIDATION_IBAN = "validation_iban",
+    VALIDATION_LOCALE = "validation_locale",
+    VALIDATION_BIRTH_DATE_IN_FUTURE = "validation_birth_date_in_future",
+    VALIDATION_BIRTH_DATE_TOO_YOUNG = "validation_birth_date_too_young",
+    VALIDATION_BIRTH_DATE_TOO_OLD = "validation_birth_date_too_old",
+}
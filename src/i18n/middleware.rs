@@ -0,0 +1,31 @@
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use actix_web::Error;
+
+use super::Locale;
+use crate::api_error::ApiError;
+
+/// Renders `ApiError` responses again in the language asked for in `Accept-Language`.
+///
+/// `ResponseError::error_response` has no access to the request, so errors are first
+/// rendered with the default locale and replaced here.
+pub async fn localize_errors(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let locale = Locale::from_headers(req.headers());
+    let res = next.call(req).await?;
+
+    let localized = res
+        .response()
+        .error()
+        .and_then(|err| err.as_error::<ApiError>())
+        .filter(|_| locale != Locale::default())
+        .map(|err| err.render(locale));
+
+    Ok(match localized {
+        Some(response) => res.into_response(response).map_into_right_body(),
+        None => res.map_into_left_body(),
+    })
+}
@@ -0,0 +1,10 @@
+mod catalog;
+mod duration;
+pub mod keys;
+mod locale;
+mod middleware;
+
+pub use catalog::{init, interpolate, lookup, t};
+pub use duration::duration;
+pub use locale::Locale;
+pub use middleware::localize_errors;
@@ -0,0 +1,55 @@
+use std::time::Duration;
+
+use actix::{Actor, AsyncContext, Context};
+
+use crate::buisness_logic::attachments;
+use crate::buisness_logic::full_user::delete::delete_due_accounts;
+use crate::constants::{ACCOUNT_DELETION_JOB_INTERVAL, CONNECTION_POOL_ERROR};
+use crate::DBPool;
+
+/// Periodically anonymizes accounts whose deletion grace period has passed.
+pub struct AccountDeletionJob {
+    pool: DBPool,
+}
+
+impl AccountDeletionJob {
+    pub fn new(pool: DBPool) -> Self {
+        Self { pool }
+    }
+
+    fn run(&self) {
+        let mut conn = match self.pool.get() {
+            Ok(conn) => conn,
+            Err(e) => {
+                log::error!("{}: {:?}", CONNECTION_POOL_ERROR, e);
+                return;
+            }
+        };
+
+        match delete_due_accounts(&mut conn) {
+            Ok((0, _)) => {}
+            Ok((deleted, files)) => {
+                log::info!("Deleted {} accounts after grace period", deleted);
+                // the rows are gone, so failures are only logged
+                actix::spawn(async move {
+                    for attachment in &files {
+                        attachments::delete_stored_files(attachment).await;
+                    }
+                });
+            }
+            Err(e) => log::error!("Error deleting due accounts: {:?}", e),
+        }
+    }
+}
+
+impl Actor for AccountDeletionJob {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        self.run();
+        ctx.run_interval(
+            Duration::from_secs(ACCOUNT_DELETION_JOB_INTERVAL),
+            |job, _| job.run(),
+        );
+    }
+}
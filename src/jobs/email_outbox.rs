@@ -0,0 +1,58 @@
+use std::time::Duration;
+
+use actix::{Actor, AsyncContext, Context};
+
+use crate::constants::{CONNECTION_POOL_ERROR, EMAIL_OUTBOX_JOB_INTERVAL};
+use crate::emails::{mailer, outbox};
+use crate::DBPool;
+
+/// Delivers queued emails, failed ones are retried with a growing delay.
+pub struct EmailOutboxJob {
+    pool: DBPool,
+}
+
+impl EmailOutboxJob {
+    pub fn new(pool: DBPool) -> Self {
+        Self { pool }
+    }
+
+    fn run(&self) {
+        let mailer = match mailer::get() {
+            Ok(mailer) => mailer,
+            Err(e) => {
+                log::error!("{}", e);
+                return;
+            }
+        };
+
+        let mut conn = match self.pool.get() {
+            Ok(conn) => conn,
+            Err(e) => {
+                log::error!("{}: {:?}", CONNECTION_POOL_ERROR, e);
+                return;
+            }
+        };
+
+        match outbox::deliver_due(&mut conn, mailer) {
+            Ok(report) if report.sent + report.retried + report.dead > 0 => log::info!(
+                "Email outbox: {} sent, {} to retry, {} dead",
+                report.sent,
+                report.retried,
+                report.dead
+            ),
+            Ok(_) => {}
+            Err(e) => log::error!("Error delivering queued emails: {:?}", e),
+        }
+    }
+}
+
+impl Actor for EmailOutboxJob {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        self.run();
+        ctx.run_interval(Duration::from_secs(EMAIL_OUTBOX_JOB_INTERVAL), |job, _| {
+            job.run()
+        });
+    }
+}
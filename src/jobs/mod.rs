@@ -0,0 +1,8 @@
+mod account_deletion;
+mod email_outbox;
+mod task_reminders;
+mod token_cleanup;
+pub use account_deletion::AccountDeletionJob;
+pub use email_outbox::EmailOutboxJob;
+pub use task_reminders::TaskReminderJob;
+pub use token_cleanup::TokenCleanupJob;
@@ -0,0 +1,49 @@
+use std::time::Duration;
+
+use actix::{Actor, AsyncContext, Context};
+
+use crate::buisness_logic::workspace::task_reminders;
+use crate::constants::{CONNECTION_POOL_ERROR, TASK_REMINDER_JOB_INTERVAL};
+use crate::DBPool;
+
+/// Reminds assignees of upcoming due dates and escalates overdue tasks.
+pub struct TaskReminderJob {
+    pool: DBPool,
+}
+
+impl TaskReminderJob {
+    pub fn new(pool: DBPool) -> Self {
+        Self { pool }
+    }
+
+    fn run(&self) {
+        let mut conn = match self.pool.get() {
+            Ok(conn) => conn,
+            Err(e) => {
+                log::error!("{}: {:?}", CONNECTION_POOL_ERROR, e);
+                return;
+            }
+        };
+
+        match task_reminders::send_task_reminders(&mut conn) {
+            Ok(report) if report.due_soon + report.overdue > 0 => log::info!(
+                "Task reminders: {} due soon, {} overdue",
+                report.due_soon,
+                report.overdue
+            ),
+            Ok(_) => {}
+            Err(e) => log::error!("Error sending task reminders: {:?}", e),
+        }
+    }
+}
+
+impl Actor for TaskReminderJob {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        self.run();
+        ctx.run_interval(Duration::from_secs(TASK_REMINDER_JOB_INTERVAL), |job, _| {
+            job.run()
+        });
+    }
+}
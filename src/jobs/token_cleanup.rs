@@ -0,0 +1,51 @@
+use std::time::Duration;
+
+use actix::{Actor, AsyncContext, Context};
+
+use crate::auth::confirmation_token::cleanup::purge_expired_tokens;
+use crate::constants::{CONNECTION_POOL_ERROR, TOKEN_CLEANUP_JOB_INTERVAL};
+use crate::DBPool;
+
+/// Expires stale workspace invitations and purges old tokens.
+pub struct TokenCleanupJob {
+    pool: DBPool,
+}
+
+impl TokenCleanupJob {
+    pub fn new(pool: DBPool) -> Self {
+        Self { pool }
+    }
+
+    fn run(&self) {
+        let mut conn = match self.pool.get() {
+            Ok(conn) => conn,
+            Err(e) => {
+                log::error!("{}: {:?}", CONNECTION_POOL_ERROR, e);
+                return;
+            }
+        };
+
+        match purge_expired_tokens(&mut conn) {
+            Ok(run) if run.total() > 0 => log::info!(
+                "Purged {} confirmation, {} reset and {} invitation tokens, expired {} invitations",
+                run.confirmation_tokens,
+                run.password_reset_tokens,
+                run.workspace_invitations,
+                run.expired_invitations
+            ),
+            Ok(_) => {}
+            Err(e) => log::error!("Error purging expired tokens: {:?}", e),
+        }
+    }
+}
+
+impl Actor for TokenCleanupJob {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        self.run();
+        ctx.run_interval(Duration::from_secs(TOKEN_CLEANUP_JOB_INTERVAL), |job, _| {
+            job.run()
+        });
+    }
+}
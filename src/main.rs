@@ -1,27 +1,32 @@
+mod api_error;
 mod auth;
 mod buisness_logic;
 mod constants;
 mod emails;
+mod i18n;
+mod jobs;
 mod models;
 mod models_insertable;
 mod response;
-mod response_handler;
 mod schema;
+mod settings;
+mod storage;
 mod user;
+mod validation;
 
-use crate::constants::CONNECTION_POOL_ERROR;
+use crate::constants::{CONNECTION_POOL_ERROR, EMAIL_TEMPLATES_DIR, LOCALES_DIR};
+use actix::Actor;
 use actix_web::web::Data;
 use actix_web::{middleware, App, HttpServer};
 use chrono::Utc;
-use constants::DOMAIN;
+use clap::Parser;
 use diesel::{
     r2d2::{self, ConnectionManager, Pool, PooledConnection},
     PgConnection,
 };
 use dotenv::dotenv;
-use std::env;
-use std::fs::File;
-use std::io::Read;
+use std::io::{Error as IoError, ErrorKind};
+use std::path::Path;
 
 type DBPool = Pool<ConnectionManager<PgConnection>>;
 pub type DBPConn = PooledConnection<ConnectionManager<PgConnection>>;
@@ -30,56 +35,121 @@ pub fn est_conn(pool: Data<DBPool>) -> PooledConnection<ConnectionManager<PgConn
     pool.get().expect(CONNECTION_POOL_ERROR)
 }
 
-pub type ResponseKeys = Data<serde_json::Value>;
-
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
+    let cli = settings::Cli::parse();
     dotenv().ok();
     env_logger::init();
 
-    println!("{}", Utc::now().naive_utc());
+    let settings = settings::Settings::load(&cli)
+        .map_err(|e| IoError::new(ErrorKind::InvalidInput, e.to_string()))?;
+    if cli.print_config {
+        println!("{}", settings.redacted());
+        return Ok(());
+    }
+    log::info!("Effective configuration:\n{}", settings.redacted());
+    settings::init(settings.clone());
 
-    let mut file = File::open("api-response.json")?;
-    let mut contents = String::new();
-    file.read_to_string(&mut contents)?;
+    println!("{}", Utc::now().naive_utc());
 
-    let response_keys = response::JsonResponse::read(&contents);
+    i18n::init(Path::new(LOCALES_DIR))
+        .map_err(|e| IoError::new(ErrorKind::InvalidData, e.to_string()))?;
+    emails::init(Path::new(EMAIL_TEMPLATES_DIR))
+        .map_err(|e| IoError::new(ErrorKind::InvalidData, format!("{:?}", e)))?;
+    let jwt_keys = auth::jwt::keys::JwtKeys::load(&settings.jwt)
+        .map_err(|e| IoError::new(ErrorKind::InvalidInput, e.to_string()))?;
+    log::info!(
+        "JWT keys: {}, signing with {}",
+        jwt_keys.kids().collect::<Vec<_>>().join(", "),
+        jwt_keys.signing().kid
+    );
+    auth::jwt::keys::init(jwt_keys);
+    emails::mailer::init(
+        emails::mailer::from_settings(&settings.mail)
+            .map_err(|e| IoError::new(ErrorKind::InvalidInput, e.to_string()))?,
+    );
+    storage::init(
+        storage::from_settings(&settings.storage)
+            .map_err(|e| IoError::new(ErrorKind::InvalidInput, e.to_string()))?,
+    );
 
-    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-    let manager = ConnectionManager::<PgConnection>::new(database_url);
+    let manager = ConnectionManager::<PgConnection>::new(settings.database_url());
     let pool = r2d2::Pool::builder()
         .build(manager)
         .expect("Failed to create pool");
 
     println!("now: {}", Utc::now().naive_utc());
 
+    match auth::confirmation_token::hash::hash_plain_tokens(
+        &mut pool.get().expect("Failed to get connection"),
+    ) {
+        Ok(0) => {}
+        Ok(hashed) => log::info!("Hashed {} tokens stored in plain text", hashed),
+        Err(e) => log::error!("Error hashing stored tokens: {:?}", e),
+    }
+
+    match buisness_logic::attachments::legacy::move_legacy_files(
+        &mut pool.get().expect("Failed to get connection"),
+    )
+    .await
+    {
+        Ok(0) => {}
+        Ok(moved) => log::info!("Moved {} files from bytea columns into storage", moved),
+        Err(e) => log::error!(
+            "Error moving files from bytea columns into storage: {:?}",
+            e
+        ),
+    }
+
+    jobs::AccountDeletionJob::new(pool.clone()).start();
+    jobs::EmailOutboxJob::new(pool.clone()).start();
+    jobs::TaskReminderJob::new(pool.clone()).start();
+    jobs::TokenCleanupJob::new(pool.clone()).start();
+
+    let bind_address = settings.bind_address();
+    let settings = Data::new(settings);
+
     HttpServer::new(move || {
-        let cors = actix_cors::Cors::default()
-            .allowed_origin("http://localhost:3000")
-            .allowed_origin("http://localhost:3001")
+        let cors = settings
+            .server
+            .cors_origins
+            .iter()
+            .fold(actix_cors::Cors::default(), |cors, origin| {
+                cors.allowed_origin(origin)
+            })
             .allowed_methods(vec!["GET", "POST", "PUT", "DELETE", "OPTIONS"])
             .allowed_headers(vec![
                 actix_web::http::header::AUTHORIZATION,
                 actix_web::http::header::ACCEPT,
                 actix_web::http::header::CONTENT_TYPE,
+                actix_web::http::header::HeaderName::from_static(constants::DEVICE_NAME_HEADER),
             ])
             .supports_credentials()
             .max_age(3600);
 
         App::new()
             .app_data(actix_web::web::Data::new(pool.clone()))
-            .app_data(actix_web::web::Data::new(response_keys.clone()))
+            .app_data(settings.clone())
+            .wrap(middleware::from_fn(i18n::localize_errors))
             .wrap(middleware::Logger::default())
             .wrap(cors)
             .service(user::list)
             .service(auth::register::register)
             .service(auth::login::login_email)
             .service(auth::login::login_username)
+            .service(auth::logout::logout)
+            .service(auth::sessions::manage::list_sessions)
+            .service(auth::sessions::manage::revoke_session)
+            .service(auth::sessions::manage::revoke_other_sessions)
+            .service(auth::oidc::list_providers)
+            .service(auth::oidc::authorize)
+            .service(auth::oidc::callback)
             .service(auth::validate_account::validate_account)
             .service(auth::resend_verification_email::resend_verification_email)
             .service(auth::reset_password::reset_password)
             .service(auth::reset_password::email_reset_password)
             .service(auth::verify_session::verify_session)
+            .service(auth::jwt::jwks::jwks)
             .service(buisness_logic::workspace::add_user_to_workspace::add_user_to_workspace)
             .service(buisness_logic::workspace::workspace_invitation::workspace_invitation)
             .service(buisness_logic::workspace::list_workspace_users::list_workspace_users)
@@ -87,10 +157,51 @@ async fn main() -> std::io::Result<()> {
             .service(buisness_logic::workspace::create_task::create_task)
             .service(buisness_logic::workspace::list_tasks::list_tasks)
             .service(buisness_logic::workspace::list_workspaces::list_workspaces)
+            .service(buisness_logic::workspace::update_task_status::update_task_status)
+            .service(buisness_logic::workspace::report_problem::report_problem)
+            .service(buisness_logic::workspace::audit::list_workspace_audit_events)
+            .service(buisness_logic::workspace::storage_usage::get_storage_usage)
+            .service(buisness_logic::workspace::task_attachments::list_task_attachments)
+            .service(buisness_logic::workspace::task_attachments::add_task_attachment)
+            .service(buisness_logic::workspace::task_attachments::update_task_attachment)
+            .service(buisness_logic::workspace::task_attachments::reorder_task_attachments)
+            .service(buisness_logic::workspace::task_attachments::delete_task_attachment)
             .service(buisness_logic::full_user::read::get_full_user)
             .service(buisness_logic::full_user::register::register_full_user)
+            .service(buisness_logic::full_user::update::update_full_user)
+            .service(buisness_logic::full_user::delete::request_account_deletion)
+            .service(buisness_logic::full_user::delete::confirm_account_deletion)
+            .service(buisness_logic::full_user::delete::cancel_account_deletion)
+            .service(buisness_logic::full_user::export::export_user_data)
+            .service(buisness_logic::full_user::locale::update_locale)
+            .service(buisness_logic::attachments::upload::upload_attachment)
+            .service(buisness_logic::attachments::download::get_attachment)
+            .service(buisness_logic::attachments::download::get_attachment_content)
+            .service(buisness_logic::notifications::read::list_notifications)
+            .service(buisness_logic::notifications::read::unread_count)
+            .service(buisness_logic::notifications::read::mark_all_read)
+            .service(buisness_logic::notifications::read::mark_read)
+            .service(buisness_logic::notifications::preferences::get_preferences)
+            .service(buisness_logic::notifications::preferences::update_preference)
+            .service(emails::preview::preview_email)
+            .service(emails::sent::sent_emails)
+            .service(emails::sent::clear_sent_emails)
+            .service(emails::status::list_outbox)
+            .service(emails::status::retry_email)
+            .service(auth::confirmation_token::cleanup::list_cleanup_runs)
+            .service(auth::confirmation_token::cleanup::run_cleanup)
+            .service(buisness_logic::admin::users::search_users)
+            .service(buisness_logic::admin::users::get_user)
+            .service(buisness_logic::admin::users::disable_user)
+            .service(buisness_logic::admin::users::enable_user)
+            .service(buisness_logic::admin::users::verify_user)
+            .service(buisness_logic::admin::users::force_password_reset)
+            .service(buisness_logic::admin::users::grant_role)
+            .service(buisness_logic::admin::users::revoke_role)
+            .service(buisness_logic::admin::workspaces::get_workspace)
+            .service(buisness_logic::audit::read::list_audit_events)
     })
-    .bind(DOMAIN)?
+    .bind(bind_address)?
     .run()
     .await
 }
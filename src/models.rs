@@ -2,7 +2,8 @@ use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
 use diesel::prelude::*;
 use serde::{Deserialize, Serialize};
 
-#[derive(Queryable, Debug, Serialize, Deserialize, Selectable, Insertable, Clone)]
+// no Serialize, it holds the password hash, responses use `auth::PublicUser`
+#[derive(Queryable, Debug, Selectable, Insertable, Clone)]
 #[diesel(table_name = crate::schema::auth_users)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 pub struct AuthUser {
@@ -12,6 +13,8 @@ pub struct AuthUser {
     pub password: String,
     pub created_at: NaiveDateTime,
     pub account_valid: bool,
+    pub locale: String,
+    pub disabled_at: Option<NaiveDateTime>,
 }
 
 #[derive(Queryable, Debug, Serialize, Deserialize, Selectable, Insertable)]
@@ -53,6 +56,7 @@ pub struct WorkspaceInvitation {
     pub expires_at: NaiveDateTime,
     pub confirmed_at: Option<NaiveDateTime>,
     pub workspace_id: i32,
+    pub expired_at: Option<NaiveDateTime>,
 }
 
 #[derive(Queryable, Debug, Serialize, Deserialize, Selectable, Insertable)]
@@ -90,7 +94,6 @@ pub struct Task {
     pub assigner_id: i32,
     pub worker_id: i32, // asignee
     pub description: Option<String>,
-    pub description_multimedia: Option<Vec<u8>>,
     pub assignment_date: NaiveDateTime,
     pub due_date: Option<NaiveDateTime>,
     pub status_id: i32,
@@ -144,7 +147,7 @@ pub struct FullUser {
     pub education: Option<String>,
     pub birth_date: NaiveDate,
     pub account_bank_number: Option<String>,
-    pub photo: Option<Vec<u8>>,
+    pub photo_attachment_id: Option<i32>,
 }
 
 #[derive(Queryable, Debug, Serialize, Deserialize, Selectable, Insertable)]
@@ -174,7 +177,7 @@ pub struct Problem {
     pub worker_id: i32,
     pub description: Option<String>,
     pub mentor_id: i32,
-    pub problem_multimedia: Option<Vec<u8>>,
+    pub attachment_id: Option<i32>,
 }
 
 #[derive(Queryable, Debug, Serialize, Deserialize, Selectable, Insertable)]
@@ -262,8 +265,154 @@ pub struct Attendance {
     pub user_id: i32,
     pub date: NaiveDate,
     pub checkin: NaiveTime,
-    pub checkin_photo: Option<Vec<u8>>,
+    pub checkin_photo_attachment_id: Option<i32>,
     pub checkout: Option<NaiveTime>,
-    pub checkout_photo: Option<Vec<u8>>,
+    pub checkout_photo_attachment_id: Option<i32>,
     pub workspace_id: i32,
 }
+
+#[derive(Queryable, Debug, Serialize, Deserialize, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::account_deletion_requests)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct AccountDeletionRequest {
+    pub id: i32,
+    pub user_email: String,
+    pub token: String,
+    pub created_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+    pub confirmed_at: Option<NaiveDateTime>,
+    pub scheduled_for: Option<NaiveDateTime>,
+    pub cancelled_at: Option<NaiveDateTime>,
+    pub completed_at: Option<NaiveDateTime>,
+}
+
+/// What the outbox worker needs to deliver a queued email.
+#[derive(Queryable, Debug, Selectable)]
+#[diesel(table_name = crate::schema::email_outbox)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct OutboxEmail {
+    pub id: i32,
+    pub email_type: String,
+    pub to_name: String,
+    pub to_email: String,
+    pub subject: String,
+    pub html_body: String,
+    pub text_body: String,
+    pub attempts: i32,
+}
+
+/// Delivery state of a queued email, without the bodies.
+#[derive(Queryable, Debug, Serialize, Selectable)]
+#[diesel(table_name = crate::schema::email_outbox)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct OutboxEmailStatus {
+    pub id: i32,
+    pub email_type: String,
+    pub to_email: String,
+    pub subject: String,
+    pub status: String,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub next_attempt_at: NaiveDateTime,
+    pub created_at: NaiveDateTime,
+    pub sent_at: Option<NaiveDateTime>,
+}
+
+#[derive(Queryable, Debug, Selectable)]
+#[diesel(table_name = crate::schema::notifications)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Notification {
+    pub id: i32,
+    pub event_type: String,
+    pub data: serde_json::Value,
+    pub read_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Queryable, Debug, Serialize, Selectable)]
+#[diesel(table_name = crate::schema::token_cleanup_runs)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct TokenCleanupRun {
+    pub id: i32,
+    pub ran_at: NaiveDateTime,
+    pub confirmation_tokens: i32,
+    pub password_reset_tokens: i32,
+    pub workspace_invitations: i32,
+    pub expired_invitations: i32,
+    pub revoked_tokens: i32,
+    pub sessions: i32,
+    pub oidc_login_states: i32,
+}
+
+impl TokenCleanupRun {
+    pub fn total(&self) -> i32 {
+        self.confirmation_tokens
+            + self.password_reset_tokens
+            + self.workspace_invitations
+            + self.expired_invitations
+            + self.revoked_tokens
+            + self.sessions
+            + self.oidc_login_states
+    }
+}
+
+#[derive(Queryable, Debug, Serialize, Selectable)]
+#[diesel(table_name = crate::schema::sessions)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Session {
+    pub id: i32,
+    pub user_id: i32,
+    pub device: Option<String>,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub last_seen_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+    pub revoked_at: Option<NaiveDateTime>,
+}
+
+#[derive(Queryable, Debug, Serialize, Selectable)]
+#[diesel(table_name = crate::schema::audit_events)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct AuditEvent {
+    pub id: i64,
+    pub actor_id: Option<i32>,
+    pub action: String,
+    pub target_type: Option<String>,
+    pub target_id: Option<i32>,
+    pub workspace_id: Option<i32>,
+    pub ip: Option<String>,
+    pub data: serde_json::Value,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Queryable, Debug, Serialize, Selectable, Clone)]
+#[diesel(table_name = crate::schema::attachments)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Attachment {
+    pub id: i32,
+    pub owner_id: i32,
+    pub workspace_id: Option<i32>,
+    // where the content is stored is nobody's business, downloads go through signed URLs
+    #[serde(skip)]
+    pub storage_key: String,
+    #[serde(skip)]
+    pub thumbnail_key: Option<String>,
+    pub file_name: String,
+    pub mime_type: String,
+    pub size_bytes: i64,
+    pub checksum: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Queryable, Debug, Serialize, Selectable, Clone)]
+#[diesel(table_name = crate::schema::task_attachments)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct TaskAttachment {
+    pub id: i32,
+    pub task_id: i32,
+    pub attachment_id: i32,
+    pub caption: Option<String>,
+    pub position: i32,
+    pub created_at: NaiveDateTime,
+}
@@ -1,5 +1,5 @@
 use chrono::{NaiveDateTime, NaiveTime};
-use diesel::prelude::Insertable;
+use diesel::prelude::{AsChangeset, Insertable};
 
 #[derive(Insertable)]
 #[diesel(table_name = crate::schema::workspaces)]
@@ -49,7 +49,6 @@ pub struct Task {
     pub assigner_id: i32,
     pub worker_id: i32, // asignee
     pub description: Option<String>,
-    pub description_multimedia: Option<Vec<u8>>,
     pub assignment_date: NaiveDateTime,
     pub due_date: Option<NaiveDateTime>,
     pub status_id: i32,
@@ -57,3 +56,90 @@ pub struct Task {
     pub importance_id: i32,
     pub category_id: i32,
 }
+
+#[derive(AsChangeset)]
+#[diesel(table_name = crate::schema::full_users)]
+pub struct FullUserChangeset {
+    pub phone: Option<String>,
+    pub phonde_dial_code_id: Option<i32>,
+    pub country_of_origin_id: Option<i32>,
+    pub title: Option<Option<String>>,
+    pub education: Option<Option<String>>,
+    pub account_bank_number: Option<Option<String>>,
+    pub photo_attachment_id: Option<Option<i32>>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::email_outbox)]
+pub struct NewOutboxEmail<'a> {
+    pub email_type: &'a str,
+    pub to_name: &'a str,
+    pub to_email: &'a str,
+    pub subject: &'a str,
+    pub html_body: &'a str,
+    pub text_body: &'a str,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::notifications)]
+pub struct NewNotification<'a> {
+    pub user_id: i32,
+    pub event_type: &'a str,
+    pub data: serde_json::Value,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::token_cleanup_runs)]
+pub struct NewTokenCleanupRun {
+    pub confirmation_tokens: i32,
+    pub password_reset_tokens: i32,
+    pub workspace_invitations: i32,
+    pub expired_invitations: i32,
+    pub revoked_tokens: i32,
+    pub sessions: i32,
+    pub oidc_login_states: i32,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::sessions)]
+pub struct NewSession {
+    pub user_id: i32,
+    pub device: Option<String>,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+    pub expires_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::audit_events)]
+pub struct NewAuditEvent<'a> {
+    pub actor_id: Option<i32>,
+    pub action: &'a str,
+    pub target_type: Option<&'a str>,
+    pub target_id: Option<i32>,
+    pub workspace_id: Option<i32>,
+    pub ip: Option<&'a str>,
+    pub data: serde_json::Value,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::attachments)]
+pub struct NewAttachment<'a> {
+    pub owner_id: i32,
+    pub workspace_id: Option<i32>,
+    pub storage_key: &'a str,
+    pub thumbnail_key: Option<&'a str>,
+    pub file_name: &'a str,
+    pub mime_type: &'a str,
+    pub size_bytes: i64,
+    pub checksum: &'a str,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = crate::schema::task_attachments)]
+pub struct NewTaskAttachment<'a> {
+    pub task_id: i32,
+    pub attachment_id: i32,
+    pub caption: Option<&'a str>,
+    pub position: i32,
+}
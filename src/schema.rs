@@ -1,16 +1,67 @@
+diesel::table! {
+    account_deletion_requests (id) {
+        id -> Int4,
+        user_email -> Varchar,
+        token -> Varchar,
+        created_at -> Timestamp,
+        expires_at -> Timestamp,
+        confirmed_at -> Nullable<Timestamp>,
+        scheduled_for -> Nullable<Timestamp>,
+        cancelled_at -> Nullable<Timestamp>,
+        completed_at -> Nullable<Timestamp>,
+    }
+}
+
 diesel::table! {
     attendance (id) {
         id -> Int4,
         user_id -> Int4,
         date -> Date,
         checkin -> Time,
-        checkin_photo -> Nullable<Bytea>,
+        checkin_photo_attachment_id -> Nullable<Int4>,
         checkout -> Nullable<Time>,
-        checkout_photo -> Nullable<Bytea>,
+        checkout_photo_attachment_id -> Nullable<Int4>,
         workspace_id -> Int4,
     }
 }
 
+diesel::table! {
+    attachments (id) {
+        id -> Int4,
+        owner_id -> Int4,
+        workspace_id -> Nullable<Int4>,
+        #[max_length = 100]
+        storage_key -> Varchar,
+        #[max_length = 100]
+        thumbnail_key -> Nullable<Varchar>,
+        #[max_length = 255]
+        file_name -> Varchar,
+        #[max_length = 100]
+        mime_type -> Varchar,
+        size_bytes -> Int8,
+        #[max_length = 64]
+        checksum -> Varchar,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    audit_events (id) {
+        id -> Int8,
+        actor_id -> Nullable<Int4>,
+        #[max_length = 50]
+        action -> Varchar,
+        #[max_length = 30]
+        target_type -> Nullable<Varchar>,
+        target_id -> Nullable<Int4>,
+        workspace_id -> Nullable<Int4>,
+        #[max_length = 45]
+        ip -> Nullable<Varchar>,
+        data -> Jsonb,
+        created_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     auth_users (id) {
         id -> Int4,
@@ -19,6 +70,8 @@ diesel::table! {
         password -> Varchar,
         created_at -> Timestamp,
         account_valid -> Bool,
+        locale -> Varchar,
+        disabled_at -> Nullable<Timestamp>,
     }
 }
 
@@ -59,6 +112,26 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    email_outbox (id) {
+        id -> Int4,
+        #[max_length = 50]
+        email_type -> Varchar,
+        to_name -> Varchar,
+        to_email -> Varchar,
+        subject -> Varchar,
+        html_body -> Text,
+        text_body -> Text,
+        #[max_length = 10]
+        status -> Varchar,
+        attempts -> Int4,
+        last_error -> Nullable<Text>,
+        next_attempt_at -> Timestamp,
+        created_at -> Timestamp,
+        sent_at -> Nullable<Timestamp>,
+    }
+}
+
 diesel::table! {
     ev_subscriptions (id) {
         id -> Int4,
@@ -81,7 +154,7 @@ diesel::table! {
         birth_date -> Date,
         #[max_length = 70]
         account_bank_number -> Nullable<Varchar>,
-        photo -> Nullable<Bytea>,
+        photo_attachment_id -> Nullable<Int4>,
     }
 }
 
@@ -93,6 +166,21 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    linked_identities (id) {
+        id -> Int4,
+        user_id -> Int4,
+        #[max_length = 50]
+        provider -> Varchar,
+        #[max_length = 255]
+        subject -> Varchar,
+        #[max_length = 255]
+        email -> Nullable<Varchar>,
+        created_at -> Timestamp,
+        last_login_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     messages (id) {
         id -> Int4,
@@ -104,6 +192,43 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    notification_preferences (user_id, event_type) {
+        user_id -> Int4,
+        #[max_length = 50]
+        event_type -> Varchar,
+        #[max_length = 10]
+        channel -> Varchar,
+    }
+}
+
+diesel::table! {
+    notifications (id) {
+        id -> Int4,
+        user_id -> Int4,
+        #[max_length = 50]
+        event_type -> Varchar,
+        data -> Jsonb,
+        read_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    oidc_login_states (state) {
+        #[max_length = 64]
+        state -> Varchar,
+        #[max_length = 50]
+        provider -> Varchar,
+        #[max_length = 128]
+        code_verifier -> Varchar,
+        #[max_length = 64]
+        nonce -> Varchar,
+        created_at -> Timestamp,
+        expires_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     password_reset_tokens (id) {
         id -> Int4,
@@ -140,7 +265,17 @@ diesel::table! {
         worker_id -> Int4,
         description -> Nullable<Text>,
         mentor_id -> Int4,
-        problem_multimedia -> Nullable<Bytea>,
+        attachment_id -> Nullable<Int4>,
+    }
+}
+
+diesel::table! {
+    revoked_tokens (jti) {
+        #[max_length = 36]
+        jti -> Varchar,
+        user_id -> Nullable<Int4>,
+        expires_at -> Timestamp,
+        revoked_at -> Timestamp,
     }
 }
 
@@ -151,6 +286,22 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    sessions (id) {
+        id -> Int4,
+        user_id -> Int4,
+        #[max_length = 100]
+        device -> Nullable<Varchar>,
+        user_agent -> Nullable<Text>,
+        #[max_length = 45]
+        ip -> Nullable<Varchar>,
+        created_at -> Timestamp,
+        last_seen_at -> Timestamp,
+        expires_at -> Timestamp,
+        revoked_at -> Nullable<Timestamp>,
+    }
+}
+
 diesel::table! {
     status (id) {
         id -> Int4,
@@ -159,6 +310,28 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    task_attachments (id) {
+        id -> Int4,
+        task_id -> Int4,
+        attachment_id -> Int4,
+        #[max_length = 500]
+        caption -> Nullable<Varchar>,
+        position -> Int4,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    task_reminders (task_id, kind, due_date) {
+        task_id -> Int4,
+        #[max_length = 10]
+        kind -> Varchar,
+        due_date -> Timestamp,
+        sent_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     tasks (id) {
         id -> Int4,
@@ -166,7 +339,6 @@ diesel::table! {
         assigner_id -> Int4,
         worker_id -> Int4,
         description -> Nullable<Text>,
-        description_multimedia -> Nullable<Bytea>,
         assignment_date -> Timestamp,
         due_date -> Nullable<Timestamp>,
         status_id -> Int4,
@@ -185,6 +357,20 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    token_cleanup_runs (id) {
+        id -> Int4,
+        ran_at -> Timestamp,
+        confirmation_tokens -> Int4,
+        password_reset_tokens -> Int4,
+        workspace_invitations -> Int4,
+        expired_invitations -> Int4,
+        revoked_tokens -> Int4,
+        sessions -> Int4,
+        oidc_login_states -> Int4,
+    }
+}
+
 diesel::table! {
     user_roles (user_id, role_id) {
         user_id -> Int4,
@@ -216,6 +402,7 @@ diesel::table! {
         expires_at -> Timestamp,
         confirmed_at -> Nullable<Timestamp>,
         workspace_id -> Int4,
+        expired_at -> Nullable<Timestamp>,
     }
 }
 
@@ -257,13 +444,25 @@ diesel::table! {
     }
 }
 
+diesel::joinable!(attachments -> auth_users (owner_id));
+diesel::joinable!(attachments -> workspaces (workspace_id));
 diesel::joinable!(attendance -> auth_users (user_id));
 diesel::joinable!(attendance -> workspaces (workspace_id));
+diesel::joinable!(full_users -> attachments (photo_attachment_id));
 diesel::joinable!(full_users -> countries (country_of_origin_id));
 diesel::joinable!(full_users -> phone_dial_codes (phonde_dial_code_id));
+diesel::joinable!(linked_identities -> auth_users (user_id));
 diesel::joinable!(messages -> auth_users (sender_id));
 diesel::joinable!(messages -> conversations (conversation_id));
+diesel::joinable!(notification_preferences -> auth_users (user_id));
+diesel::joinable!(notifications -> auth_users (user_id));
 diesel::joinable!(positions -> auth_users (workspace_id));
+diesel::joinable!(problems -> attachments (attachment_id));
+diesel::joinable!(revoked_tokens -> auth_users (user_id));
+diesel::joinable!(sessions -> auth_users (user_id));
+diesel::joinable!(task_attachments -> attachments (attachment_id));
+diesel::joinable!(task_attachments -> tasks (task_id));
+diesel::joinable!(task_reminders -> tasks (task_id));
 diesel::joinable!(tasks -> status (status_id));
 diesel::joinable!(tasks -> workspaces (workspace_id));
 diesel::joinable!(tasks_category -> workspaces (workspace_id));
@@ -282,24 +481,37 @@ diesel::joinable!(workspaces -> auth_users (owner_id));
 diesel::joinable!(workspaces -> ev_subscriptions (ev_subscription_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    account_deletion_requests,
+    attachments,
     attendance,
+    audit_events,
     auth_users,
     confirmation_tokens,
     conversation_participants,
     conversations,
     countries,
+    email_outbox,
     ev_subscriptions,
     full_users,
     importance,
+    linked_identities,
     messages,
+    notification_preferences,
+    notifications,
+    oidc_login_states,
     password_reset_tokens,
     phone_dial_codes,
     positions,
     problems,
+    revoked_tokens,
     roles,
+    sessions,
     status,
+    task_attachments,
+    task_reminders,
     tasks,
     tasks_category,
+    token_cleanup_runs,
     user_roles,
     users_citizenships,
     worker_workspace_data,
@@ -0,0 +1,570 @@
+//! Runtime configuration, layered from lowest to highest priority: built-in defaults,
+//! a TOML file (`config.toml` or `--config`), environment variables and CLI flags.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use chrono::Duration;
+use clap::Parser;
+use figment::providers::{Env, Format, Serialized, Toml};
+use figment::Figment;
+use serde::{Deserialize, Serialize, Serializer};
+
+const DEFAULT_CONFIG_FILE: &str = "config.toml";
+
+/// Environment variables and the setting each one overrides.
+const ENV_KEYS: [(&str, &str); 42] = [
+    ("SERVER_HOST", "server.host"),
+    ("SERVER_PORT", "server.port"),
+    ("CORS_ORIGINS", "server.cors_origins"),
+    ("FRONTEND_URL", "frontend_url"),
+    ("DATABASE_URL", "database_url"),
+    ("JWT_KEYS_DIR", "jwt.keys_dir"),
+    ("JWT_SIGNING_KEY", "jwt.signing_key"),
+    ("JWT_LIFETIME", "jwt.lifetime"),
+    ("JWT_ISSUER", "jwt.issuer"),
+    ("JWT_AUDIENCE", "jwt.audience"),
+    ("PASSWORD_MEMORY_COST", "password.memory_cost"),
+    ("PASSWORD_TIME_COST", "password.time_cost"),
+    ("PASSWORD_PARALLELISM", "password.parallelism"),
+    ("MAIL_TRANSPORT", "mail.transport"),
+    ("MAIL_SMTP_HOST", "mail.smtp_host"),
+    ("AUTH_EMAIL_NAME", "mail.username"),
+    ("AUTH_EMAIL_PASSWORD", "mail.password"),
+    ("MAIL_LOCAL_SMTP_HOST", "mail.local_smtp_host"),
+    ("MAIL_LOCAL_SMTP_PORT", "mail.local_smtp_port"),
+    ("MAIL_FILE_DIR", "mail.file_dir"),
+    ("MAIL_FROM", "mail.from"),
+    (
+        "ACCOUNT_VERIFICATION_TOKEN_LIFETIME",
+        "tokens.account_verification",
+    ),
+    ("PASSWORD_RESET_TOKEN_LIFETIME", "tokens.password_reset"),
+    (
+        "WORKSPACE_INVITATION_LIFETIME",
+        "tokens.workspace_invitation",
+    ),
+    ("ACCOUNT_DELETION_TOKEN_LIFETIME", "tokens.account_deletion"),
+    (
+        "ACCOUNT_DELETION_GRACE_PERIOD",
+        "tokens.account_deletion_grace_period",
+    ),
+    ("TOKEN_RETENTION_PERIOD", "tokens.retention"),
+    ("OIDC_REDIRECT_URL", "oidc.redirect_url"),
+    ("OIDC_GOOGLE_CLIENT_ID", "oidc.providers.google.client_id"),
+    (
+        "OIDC_GOOGLE_CLIENT_SECRET",
+        "oidc.providers.google.client_secret",
+    ),
+    ("OIDC_MICROSOFT_ISSUER", "oidc.providers.microsoft.issuer"),
+    (
+        "OIDC_MICROSOFT_CLIENT_ID",
+        "oidc.providers.microsoft.client_id",
+    ),
+    (
+        "OIDC_MICROSOFT_CLIENT_SECRET",
+        "oidc.providers.microsoft.client_secret",
+    ),
+    ("STORAGE_BACKEND", "storage.backend"),
+    ("STORAGE_LOCAL_DIR", "storage.local_dir"),
+    ("STORAGE_URL_SECRET", "storage.url_secret"),
+    ("S3_ENDPOINT", "storage.s3.endpoint"),
+    ("S3_REGION", "storage.s3.region"),
+    ("S3_BUCKET", "storage.s3.bucket"),
+    ("S3_ACCESS_KEY_ID", "storage.s3.access_key_id"),
+    ("S3_SECRET_ACCESS_KEY", "storage.s3.secret_access_key"),
+    ("APP_CONFIG", "config"),
+];
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+#[derive(Parser, Debug, Default)]
+#[command(about = "Electro-Vision API server")]
+pub struct Cli {
+    /// TOML configuration file, `config.toml` is used when it exists
+    #[arg(long, short)]
+    pub config: Option<PathBuf>,
+    /// Address to bind, e.g. `0.0.0.0`
+    #[arg(long)]
+    pub host: Option<String>,
+    #[arg(long)]
+    pub port: Option<u16>,
+    /// Base URL of the frontend, used in email links
+    #[arg(long)]
+    pub frontend_url: Option<String>,
+    /// `smtp`, `local-smtp`, `file` or `memory`
+    #[arg(long)]
+    pub mail_transport: Option<String>,
+    /// Print the effective configuration and exit
+    #[arg(long)]
+    pub print_config: bool,
+}
+
+/// A value that must not end up in logs, it serializes as `***`.
+#[derive(Clone, Default, Deserialize)]
+#[serde(transparent)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("***")
+    }
+}
+
+impl Serialize for Secret {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str("***")
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Settings {
+    pub server: ServerSettings,
+    /// Base URL of the frontend, links in emails point there.
+    pub frontend_url: String,
+    pub database_url: Option<Secret>,
+    pub jwt: JwtSettings,
+    pub password: PasswordSettings,
+    pub mail: MailSettings,
+    pub tokens: TokenSettings,
+    pub oidc: OidcSettings,
+    pub storage: StorageSettings,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerSettings {
+    pub host: String,
+    pub port: u16,
+    /// Origins allowed by CORS, `CORS_ORIGINS=[http://a,http://b]` in the environment.
+    pub cors_origins: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JwtSettings {
+    /// Directory of `<kid>.pem` private keys, see `auth::jwt::keys`.
+    pub keys_dir: PathBuf,
+    /// kid of the key signing new tokens, the others only verify.
+    pub signing_key: Option<String>,
+    /// Seconds.
+    pub lifetime: i64,
+    /// `iss` of issued tokens, others are rejected.
+    pub issuer: String,
+    /// `aud` of issued tokens, others are rejected.
+    pub audience: String,
+}
+
+/// Argon2id parameters of new password hashes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PasswordSettings {
+    /// KiB.
+    pub memory_cost: u32,
+    /// Iterations.
+    pub time_cost: u32,
+    /// Lanes.
+    pub parallelism: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MailSettings {
+    /// `smtp`, `local-smtp`, `file` or `memory`.
+    pub transport: String,
+    pub smtp_host: String,
+    pub username: Option<String>,
+    pub password: Option<Secret>,
+    pub local_smtp_host: String,
+    pub local_smtp_port: u16,
+    pub file_dir: PathBuf,
+    /// Sender of the non-SMTP transports, SMTP sends as `username`.
+    pub from: String,
+}
+
+/// Lifetimes of emailed tokens and related periods, all in seconds.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenSettings {
+    pub account_verification: i64,
+    pub password_reset: i64,
+    pub workspace_invitation: i64,
+    pub account_deletion: i64,
+    pub account_deletion_grace_period: i64,
+    /// How long expired or used tokens are kept before the cleanup job deletes them.
+    pub retention: i64,
+}
+
+/// External login providers, see `auth::oidc`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OidcSettings {
+    /// Where providers send the browser back to, `/{provider}` is appended. The page there
+    /// posts the code and state to `/auth/oidc/{provider}/callback`.
+    pub redirect_url: String,
+    /// By name, a provider is offered once it has a `client_id`.
+    pub providers: BTreeMap<String, OidcProvider>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OidcProvider {
+    /// `/.well-known/openid-configuration` is read from here.
+    pub issuer: String,
+    #[serde(default)]
+    pub client_id: Option<String>,
+    #[serde(default)]
+    pub client_secret: Option<Secret>,
+    /// Accept the `email` claim without `email_verified`, only for providers that verify every
+    /// address they issue, e.g. a single-tenant Microsoft Entra directory.
+    #[serde(default)]
+    pub trust_email: bool,
+}
+
+/// Where uploaded files are kept, see `storage`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageSettings {
+    /// `local` or `s3`.
+    pub backend: String,
+    pub local_dir: PathBuf,
+    /// Bytes, larger uploads are rejected.
+    pub max_upload_size: usize,
+    /// Bytes all attachments of one workspace may take together.
+    pub workspace_quota: i64,
+    /// Bytes the attachments a user keeps outside of workspaces may take together.
+    pub user_quota: i64,
+    /// Seconds a download URL stays valid.
+    pub url_lifetime: i64,
+    /// Signs the download URLs of the local backend. Without it a random key is used and the
+    /// URLs stop working when the server restarts.
+    #[serde(default)]
+    pub url_secret: Option<Secret>,
+    pub s3: S3Settings,
+}
+
+/// An S3-compatible bucket, AWS or e.g. MinIO.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct S3Settings {
+    /// e.g. `https://s3.eu-central-1.amazonaws.com` or `http://localhost:9000`.
+    pub endpoint: String,
+    pub region: String,
+    pub bucket: String,
+    #[serde(default)]
+    pub access_key_id: Option<String>,
+    #[serde(default)]
+    pub secret_access_key: Option<Secret>,
+    /// `endpoint/bucket/key` instead of `bucket.endpoint/key`, MinIO needs it.
+    pub path_style: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            server: ServerSettings {
+                host: "127.0.0.1".to_string(),
+                port: 3501,
+                cors_origins: vec![
+                    "http://localhost:3000".to_string(),
+                    "http://localhost:3001".to_string(),
+                ],
+            },
+            frontend_url: "http://localhost:3001".to_string(),
+            database_url: None,
+            jwt: JwtSettings {
+                keys_dir: PathBuf::from("keys"),
+                signing_key: None,
+                lifetime: 900,
+                issuer: "morning-compass-api".to_string(),
+                audience: "morning-compass".to_string(),
+            },
+            // OWASP's recommended minimum for Argon2id
+            password: PasswordSettings {
+                memory_cost: 19456,
+                time_cost: 2,
+                parallelism: 1,
+            },
+            mail: MailSettings {
+                transport: "smtp".to_string(),
+                smtp_host: "smtp.gmail.com".to_string(),
+                username: None,
+                password: None,
+                local_smtp_host: "localhost".to_string(),
+                local_smtp_port: 1025,
+                file_dir: PathBuf::from("mail"),
+                from: "no-reply@localhost".to_string(),
+            },
+            tokens: TokenSettings {
+                account_verification: 900,
+                password_reset: 900,
+                workspace_invitation: 604800, // 7 days
+                account_deletion: 86400,
+                account_deletion_grace_period: 2592000, // 30 days
+                retention: 604800,
+            },
+            oidc: OidcSettings {
+                redirect_url: "http://localhost:3001/auth/oidc".to_string(),
+                providers: BTreeMap::from([
+                    (
+                        "google".to_string(),
+                        OidcProvider::new("https://accounts.google.com"),
+                    ),
+                    (
+                        "microsoft".to_string(),
+                        // work and school accounts of any tenant
+                        OidcProvider::new("https://login.microsoftonline.com/organizations/v2.0"),
+                    ),
+                ]),
+            },
+            storage: StorageSettings {
+                backend: "local".to_string(),
+                local_dir: PathBuf::from("uploads"),
+                max_upload_size: 10 * 1024 * 1024,
+                workspace_quota: 1024 * 1024 * 1024,
+                user_quota: 100 * 1024 * 1024,
+                url_lifetime: 300,
+                url_secret: None,
+                s3: S3Settings {
+                    endpoint: "https://s3.amazonaws.com".to_string(),
+                    region: "us-east-1".to_string(),
+                    bucket: "morning-compass".to_string(),
+                    access_key_id: None,
+                    secret_access_key: None,
+                    path_style: false,
+                },
+            },
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SettingsError {
+    Load(Box<figment::Error>),
+    Invalid(Vec<String>),
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SettingsError::Load(e) => write!(f, "invalid configuration: {}", e),
+            SettingsError::Invalid(problems) => {
+                write!(f, "invalid configuration: {}", problems.join("; "))
+            }
+        }
+    }
+}
+
+impl Settings {
+    /// Merges the layers for `cli`, `.env` has to be loaded before.
+    pub fn load(cli: &Cli) -> Result<Settings, SettingsError> {
+        let env = Env::raw()
+            .only(&ENV_KEYS.map(|(name, _)| name))
+            .map(|name| {
+                ENV_KEYS
+                    .iter()
+                    .find(|(env_name, _)| name == *env_name)
+                    .map(|(_, key)| (*key).into())
+                    .unwrap_or_else(|| name.into())
+            });
+
+        let config_file = cli.config.clone().or_else(|| {
+            Figment::from(env.clone())
+                .extract_inner::<PathBuf>("config")
+                .ok()
+        });
+
+        let mut figment = Figment::from(Serialized::defaults(Settings::default()));
+        figment = match config_file {
+            Some(path) => figment.merge(Toml::file_exact(path)),
+            None => figment.merge(Toml::file(DEFAULT_CONFIG_FILE)),
+        };
+        figment = figment.merge(env);
+
+        if let Some(host) = &cli.host {
+            figment = figment.merge(("server.host", host));
+        }
+        if let Some(port) = cli.port {
+            figment = figment.merge(("server.port", port));
+        }
+        if let Some(frontend_url) = &cli.frontend_url {
+            figment = figment.merge(("frontend_url", frontend_url));
+        }
+        if let Some(transport) = &cli.mail_transport {
+            figment = figment.merge(("mail.transport", transport));
+        }
+
+        let settings = figment
+            .extract::<Settings>()
+            .map_err(|e| SettingsError::Load(Box::new(e)))?;
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    fn validate(&self) -> Result<(), SettingsError> {
+        let mut problems = Vec::new();
+
+        if self.server.port == 0 {
+            problems.push("server.port must not be 0".to_string());
+        }
+        let issuers = self
+            .oidc
+            .providers
+            .values()
+            .filter(|provider| provider.client_id.is_some())
+            .map(|provider| &provider.issuer);
+        for url in self
+            .server
+            .cors_origins
+            .iter()
+            .chain([&self.frontend_url, &self.oidc.redirect_url])
+            .chain(issuers)
+            .chain((self.storage.backend == "s3").then_some(&self.storage.s3.endpoint))
+        {
+            if !(url.starts_with("http://") || url.starts_with("https://")) || url.ends_with('/') {
+                problems.push(format!(
+                    "{} must be an http(s) URL without a trailing /",
+                    url
+                ));
+            }
+        }
+        if self
+            .database_url
+            .as_ref()
+            .is_none_or(|url| url.0.is_empty())
+        {
+            problems.push("database_url (DATABASE_URL) must be set".to_string());
+        }
+        if self.jwt.signing_key.as_ref().is_none_or(String::is_empty) {
+            problems.push("jwt.signing_key (JWT_SIGNING_KEY) must be set".to_string());
+        }
+        if self.jwt.issuer.is_empty() || self.jwt.audience.is_empty() {
+            problems.push("jwt.issuer and jwt.audience must not be empty".to_string());
+        }
+        if let Err(e) = self.password.params() {
+            problems.push(format!("password: {}", e));
+        }
+        if self.storage.max_upload_size == 0 {
+            problems.push("storage.max_upload_size must not be 0".to_string());
+        }
+        if self.storage.workspace_quota <= 0 {
+            problems.push("storage.workspace_quota must be a positive number of bytes".to_string());
+        }
+        if self.storage.user_quota <= 0 {
+            problems.push("storage.user_quota must be a positive number of bytes".to_string());
+        }
+
+        let lifetimes = [
+            ("jwt.lifetime", self.jwt.lifetime),
+            (
+                "tokens.account_verification",
+                self.tokens.account_verification,
+            ),
+            ("tokens.password_reset", self.tokens.password_reset),
+            (
+                "tokens.workspace_invitation",
+                self.tokens.workspace_invitation,
+            ),
+            ("tokens.account_deletion", self.tokens.account_deletion),
+            (
+                "tokens.account_deletion_grace_period",
+                self.tokens.account_deletion_grace_period,
+            ),
+            ("tokens.retention", self.tokens.retention),
+            ("storage.url_lifetime", self.storage.url_lifetime),
+        ];
+        for (name, seconds) in lifetimes {
+            if seconds <= 0 {
+                problems.push(format!("{} must be a positive number of seconds", name));
+            }
+        }
+
+        match problems.is_empty() {
+            true => Ok(()),
+            false => Err(SettingsError::Invalid(problems)),
+        }
+    }
+
+    pub fn bind_address(&self) -> (String, u16) {
+        (self.server.host.clone(), self.server.port)
+    }
+
+    pub fn database_url(&self) -> &str {
+        self.database_url.as_ref().map_or("", Secret::expose)
+    }
+
+    /// The effective configuration with secrets replaced by `***`.
+    pub fn redacted(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_else(|e| e.to_string())
+    }
+}
+
+impl PasswordSettings {
+    pub fn params(&self) -> Result<argon2::Params, argon2::Error> {
+        argon2::Params::new(self.memory_cost, self.time_cost, self.parallelism, None)
+    }
+}
+
+impl OidcProvider {
+    fn new(issuer: &str) -> Self {
+        Self {
+            issuer: issuer.to_string(),
+            client_id: None,
+            client_secret: None,
+            trust_email: false,
+        }
+    }
+}
+
+impl OidcSettings {
+    /// Providers with a `client_id`.
+    pub fn enabled(&self) -> impl Iterator<Item = (&String, &OidcProvider)> {
+        self.providers
+            .iter()
+            .filter(|(_, provider)| provider.client_id.is_some())
+    }
+}
+
+impl TokenSettings {
+    pub fn account_verification(&self) -> Duration {
+        Duration::seconds(self.account_verification)
+    }
+
+    pub fn password_reset(&self) -> Duration {
+        Duration::seconds(self.password_reset)
+    }
+
+    pub fn workspace_invitation(&self) -> Duration {
+        Duration::seconds(self.workspace_invitation)
+    }
+
+    pub fn account_deletion(&self) -> Duration {
+        Duration::seconds(self.account_deletion)
+    }
+
+    pub fn account_deletion_grace_period(&self) -> Duration {
+        Duration::seconds(self.account_deletion_grace_period)
+    }
+
+    pub fn retention(&self) -> Duration {
+        Duration::seconds(self.retention)
+    }
+}
+
+impl StorageSettings {
+    pub fn url_lifetime(&self) -> Duration {
+        Duration::seconds(self.url_lifetime)
+    }
+}
+
+pub fn init(settings: Settings) {
+    // a second init keeps the settings from the first one
+    let _ = SETTINGS.set(settings);
+}
+
+/// The settings the server started with. `main` calls `init` before anything reads them, so
+/// reaching this earlier is a bug and panics rather than running on the defaults.
+pub fn get() -> &'static Settings {
+    SETTINGS
+        .get()
+        .expect("settings are read before settings::init")
+}
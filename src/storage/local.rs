@@ -0,0 +1,89 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use actix_web::web;
+use chrono::Duration;
+use futures_util::future::BoxFuture;
+
+use super::{check_key, Storage, StorageError};
+
+/// Files in `storage.local_dir`, named by their key. The API serves them through signed URLs.
+pub struct LocalStorage {
+    dir: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new(dir: &Path) -> Result<Self, StorageError> {
+        fs::create_dir_all(dir)
+            .map_err(|e| StorageError::Config(format!("{}: {}", dir.display(), e)))?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+        })
+    }
+
+    fn path(&self, key: &str) -> Result<PathBuf, StorageError> {
+        check_key(key)?;
+        Ok(self.dir.join(key))
+    }
+}
+
+async fn blocking<T: Send + 'static>(
+    f: impl FnOnce() -> Result<T, StorageError> + Send + 'static,
+) -> Result<T, StorageError> {
+    web::block(f)
+        .await
+        .map_err(|e| StorageError::Io(e.to_string()))?
+}
+
+impl Storage for LocalStorage {
+    fn put<'a>(
+        &'a self,
+        key: &'a str,
+        content: Vec<u8>,
+        _mime_type: &'a str,
+    ) -> BoxFuture<'a, Result<(), StorageError>> {
+        Box::pin(async move {
+            let path = self.path(key)?;
+            blocking(move || {
+                // a half written file never shows up under the key
+                let partial = path.with_extension("part");
+                fs::write(&partial, content)
+                    .and_then(|_| fs::rename(&partial, &path))
+                    .map_err(|e| StorageError::Io(format!("{}: {}", path.display(), e)))
+            })
+            .await
+        })
+    }
+
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<Vec<u8>, StorageError>> {
+        Box::pin(async move {
+            let path = self.path(key)?;
+            let key = key.to_string();
+            blocking(move || {
+                fs::read(&path).map_err(|e| match e.kind() {
+                    ErrorKind::NotFound => StorageError::NotFound(key),
+                    _ => StorageError::Io(format!("{}: {}", path.display(), e)),
+                })
+            })
+            .await
+        })
+    }
+
+    fn delete<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<(), StorageError>> {
+        Box::pin(async move {
+            let path = self.path(key)?;
+            blocking(move || match fs::remove_file(&path) {
+                Err(e) if e.kind() != ErrorKind::NotFound => {
+                    Err(StorageError::Io(format!("{}: {}", path.display(), e)))
+                }
+                _ => Ok(()),
+            })
+            .await
+        })
+    }
+
+    fn presigned_url(&self, _: &str, _: &str, _: Duration) -> Option<String> {
+        None
+    }
+}
@@ -0,0 +1,97 @@
+//! Where attachment contents live. The backend is picked by `storage.backend` at startup, the
+//! `attachments` table keeps the metadata and the key every file is stored under.
+
+mod local;
+mod s3;
+
+use std::fmt;
+use std::sync::OnceLock;
+
+use chrono::Duration;
+use futures_util::future::BoxFuture;
+use uuid::Uuid;
+
+use crate::settings::StorageSettings;
+pub use local::LocalStorage;
+pub use s3::S3Storage;
+
+static STORAGE: OnceLock<Box<dyn Storage>> = OnceLock::new();
+
+#[derive(Debug)]
+pub enum StorageError {
+    Config(String),
+    NotFound(String),
+    Io(String),
+    Request(String),
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StorageError::Config(e) => write!(f, "invalid storage configuration: {}", e),
+            StorageError::NotFound(key) => write!(f, "no stored file {}", key),
+            StorageError::Io(e) => write!(f, "could not access stored file: {}", e),
+            StorageError::Request(e) => write!(f, "storage request failed: {}", e),
+        }
+    }
+}
+
+/// Keeps file contents by key, the keys come from `new_key`.
+pub trait Storage: Send + Sync {
+    fn put<'a>(
+        &'a self,
+        key: &'a str,
+        content: Vec<u8>,
+        mime_type: &'a str,
+    ) -> BoxFuture<'a, Result<(), StorageError>>;
+
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<Vec<u8>, StorageError>>;
+
+    /// Deleting a missing file is not an error.
+    fn delete<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<(), StorageError>>;
+
+    /// A URL downloading the file straight from the backend until `expires_in` passes, `None`
+    /// when the API has to serve it.
+    fn presigned_url(
+        &self,
+        key: &str,
+        content_disposition: &str,
+        expires_in: Duration,
+    ) -> Option<String>;
+}
+
+pub fn new_key() -> String {
+    Uuid::new_v4().to_string()
+}
+
+/// Keys only ever come from `new_key`, anything else is refused before it reaches a path or URL.
+fn check_key(key: &str) -> Result<(), StorageError> {
+    match !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        true => Ok(()),
+        false => Err(StorageError::NotFound(key.to_string())),
+    }
+}
+
+/// Builds the backend named by `storage.backend`: `local` or `s3`.
+pub fn from_settings(settings: &StorageSettings) -> Result<Box<dyn Storage>, StorageError> {
+    match settings.backend.as_str() {
+        "local" => Ok(Box::new(LocalStorage::new(&settings.local_dir)?)),
+        "s3" => Ok(Box::new(S3Storage::new(&settings.s3)?)),
+        other => Err(StorageError::Config(format!(
+            "unknown storage backend {}, expected local or s3",
+            other
+        ))),
+    }
+}
+
+pub fn init(storage: Box<dyn Storage>) {
+    // a second init keeps the storage from the first one
+    let _ = STORAGE.set(storage);
+}
+
+pub fn get() -> Result<&'static dyn Storage, StorageError> {
+    STORAGE
+        .get()
+        .map(|storage| storage.as_ref())
+        .ok_or_else(|| StorageError::Config("storage is not initialized".to_string()))
+}
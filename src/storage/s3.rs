@@ -0,0 +1,258 @@
+//! S3 REST calls signed with AWS Signature Version 4, which AWS and MinIO both accept.
+
+use std::time::Duration as StdDuration;
+
+use chrono::{DateTime, Duration, Utc};
+use futures_util::future::BoxFuture;
+use reqwest::{Client, Method, Response, StatusCode, Url};
+use ring::hmac;
+use sha2::{Digest, Sha256};
+
+use super::{check_key, Storage, StorageError};
+use crate::constants::STORAGE_HTTP_TIMEOUT;
+use crate::settings::{S3Settings, Secret};
+
+const ALGORITHM: &str = "AWS4-HMAC-SHA256";
+const SIGNED_HEADERS: &str = "host;x-amz-content-sha256;x-amz-date";
+
+/// Objects of one bucket, named by their key.
+pub struct S3Storage {
+    client: Client,
+    /// Scheme and host requests go to, the bucket is in the host unless `path_style` is set.
+    base: String,
+    host: String,
+    /// `/bucket` with path-style addressing, empty otherwise.
+    prefix: String,
+    region: String,
+    access_key_id: String,
+    secret_access_key: Secret,
+}
+
+impl S3Storage {
+    pub fn new(settings: &S3Settings) -> Result<Self, StorageError> {
+        let endpoint = Url::parse(&settings.endpoint)
+            .map_err(|e| StorageError::Config(format!("storage.s3.endpoint: {}", e)))?;
+        let host = match (endpoint.host_str(), endpoint.port()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (Some(host), None) => host.to_string(),
+            (None, _) => {
+                return Err(StorageError::Config(
+                    "storage.s3.endpoint has no host".to_string(),
+                ))
+            }
+        };
+        let (host, prefix) = match settings.path_style {
+            true => (host, format!("/{}", uri_encode(&settings.bucket))),
+            false => (format!("{}.{}", settings.bucket, host), String::new()),
+        };
+
+        let access_key_id = settings.access_key_id.clone().ok_or_else(|| {
+            StorageError::Config(
+                "storage.s3.access_key_id (S3_ACCESS_KEY_ID) needs to be set".to_string(),
+            )
+        })?;
+        let secret_access_key = settings.secret_access_key.clone().ok_or_else(|| {
+            StorageError::Config(
+                "storage.s3.secret_access_key (S3_SECRET_ACCESS_KEY) needs to be set".to_string(),
+            )
+        })?;
+
+        Ok(Self {
+            client: Client::builder()
+                .timeout(StdDuration::from_secs(STORAGE_HTTP_TIMEOUT))
+                .build()
+                .map_err(|e| StorageError::Config(e.to_string()))?,
+            base: format!("{}://{}", endpoint.scheme(), host),
+            host,
+            prefix,
+            region: settings.region.clone(),
+            access_key_id,
+            secret_access_key,
+        })
+    }
+
+    fn path(&self, key: &str) -> Result<String, StorageError> {
+        check_key(key)?;
+        Ok(format!("{}/{}", self.prefix, key))
+    }
+
+    fn scope(&self, now: &DateTime<Utc>) -> String {
+        format!("{}/{}/s3/aws4_request", now.format("%Y%m%d"), self.region)
+    }
+
+    fn signature(&self, now: &DateTime<Utc>, canonical_request: &str) -> String {
+        let string_to_sign = format!(
+            "{}\n{}\n{}\n{}",
+            ALGORITHM,
+            amz_date(now),
+            self.scope(now),
+            hex::encode(Sha256::digest(canonical_request))
+        );
+
+        let date = now.format("%Y%m%d").to_string();
+        let mut key = format!("AWS4{}", self.secret_access_key.expose()).into_bytes();
+        for part in [date.as_str(), &self.region, "s3", "aws4_request"] {
+            key = hmac_sha256(&key, part.as_bytes());
+        }
+        hex::encode(hmac_sha256(&key, string_to_sign.as_bytes()))
+    }
+
+    async fn send(
+        &self,
+        method: Method,
+        key: &str,
+        content: Option<(Vec<u8>, &str)>,
+    ) -> Result<Response, StorageError> {
+        let path = self.path(key)?;
+        let now = Utc::now();
+        let payload_hash = hex::encode(Sha256::digest(
+            content.as_ref().map_or(&[][..], |(content, _)| content),
+        ));
+
+        let canonical_request = format!(
+            "{}\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+            method,
+            path,
+            self.host,
+            payload_hash,
+            amz_date(&now),
+            SIGNED_HEADERS,
+            payload_hash
+        );
+        let authorization = format!(
+            "{} Credential={}/{}, SignedHeaders={}, Signature={}",
+            ALGORITHM,
+            self.access_key_id,
+            self.scope(&now),
+            SIGNED_HEADERS,
+            self.signature(&now, &canonical_request)
+        );
+
+        let mut request = self
+            .client
+            .request(method, format!("{}{}", self.base, path))
+            .header("x-amz-content-sha256", payload_hash)
+            .header("x-amz-date", amz_date(&now))
+            .header("authorization", authorization);
+        if let Some((content, mime_type)) = content {
+            request = request.header("content-type", mime_type).body(content);
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| StorageError::Request(e.to_string()))?;
+        match response.status() {
+            status if status.is_success() => Ok(response),
+            StatusCode::NOT_FOUND => Err(StorageError::NotFound(key.to_string())),
+            status => {
+                let body = response.text().await.unwrap_or_default();
+                Err(StorageError::Request(format!(
+                    "{} {}: {}",
+                    status, key, body
+                )))
+            }
+        }
+    }
+}
+
+impl Storage for S3Storage {
+    fn put<'a>(
+        &'a self,
+        key: &'a str,
+        content: Vec<u8>,
+        mime_type: &'a str,
+    ) -> BoxFuture<'a, Result<(), StorageError>> {
+        Box::pin(async move {
+            self.send(Method::PUT, key, Some((content, mime_type)))
+                .await
+                .map(|_| ())
+        })
+    }
+
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<Vec<u8>, StorageError>> {
+        Box::pin(async move {
+            let response = self.send(Method::GET, key, None).await?;
+            response
+                .bytes()
+                .await
+                .map(|bytes| bytes.to_vec())
+                .map_err(|e| StorageError::Request(e.to_string()))
+        })
+    }
+
+    fn delete<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<(), StorageError>> {
+        Box::pin(async move {
+            match self.send(Method::DELETE, key, None).await {
+                Err(StorageError::NotFound(_)) => Ok(()),
+                result => result.map(|_| ()),
+            }
+        })
+    }
+
+    fn presigned_url(
+        &self,
+        key: &str,
+        content_disposition: &str,
+        expires_in: Duration,
+    ) -> Option<String> {
+        let path = self.path(key).ok()?;
+        let now = Utc::now();
+
+        let mut query = [
+            ("X-Amz-Algorithm", ALGORITHM.to_string()),
+            (
+                "X-Amz-Credential",
+                format!("{}/{}", self.access_key_id, self.scope(&now)),
+            ),
+            ("X-Amz-Date", amz_date(&now)),
+            ("X-Amz-Expires", expires_in.num_seconds().to_string()),
+            ("X-Amz-SignedHeaders", "host".to_string()),
+            (
+                "response-content-disposition",
+                content_disposition.to_string(),
+            ),
+        ];
+        query.sort();
+        let query = query
+            .iter()
+            .map(|(name, value)| format!("{}={}", uri_encode(name), uri_encode(value)))
+            .collect::<Vec<_>>()
+            .join("&");
+
+        let canonical_request = format!(
+            "GET\n{}\n{}\nhost:{}\n\nhost\nUNSIGNED-PAYLOAD",
+            path, query, self.host
+        );
+        Some(format!(
+            "{}{}?{}&X-Amz-Signature={}",
+            self.base,
+            path,
+            query,
+            self.signature(&now, &canonical_request)
+        ))
+    }
+}
+
+fn amz_date(now: &DateTime<Utc>) -> String {
+    now.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    hmac::sign(&hmac::Key::new(hmac::HMAC_SHA256, key), data)
+        .as_ref()
+        .to_vec()
+}
+
+/// Percent-encodes everything but the unreserved characters, as SigV4 wants it.
+fn uri_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
@@ -1,17 +1,20 @@
+use crate::api_error::ApiError;
+use crate::auth::{PublicUser, StaffUser, Viewer};
+use crate::constants::APPLICATION_JSON;
+use crate::i18n::Locale;
 use crate::models::AuthUser as User;
 use crate::response::Response;
-use crate::constants::APPLICATION_JSON;
 use actix_web::{
     get,
     web::{self},
     HttpResponse,
 };
 use chrono::{NaiveDateTime, Utc};
-use diesel::{prelude::*, result::Error};
+use diesel::prelude::*;
 
 use crate::{est_conn, DPool};
 
-pub type Users = Response<Vec<User>>;
+pub type Users = Response<Vec<PublicUser>>;
 
 pub struct NoIdUser {
     pub username: String,
@@ -19,6 +22,7 @@ pub struct NoIdUser {
     pub password: String,
     pub created_at: NaiveDateTime,
     pub account_valid: bool,
+    pub locale: String,
 }
 
 impl User {
@@ -29,11 +33,12 @@ impl User {
             password,
             created_at: Utc::now().naive_utc(),
             account_valid: false,
+            locale: Locale::default().code().to_string(),
         }
     }
 }
 
-pub async fn list_users(amount: i64, pool: DPool) -> Result<Users, Error> {
+pub async fn list_users(amount: i64, pool: DPool) -> Result<Users, ApiError> {
     use crate::schema::auth_users::dsl::*;
 
     let users_query = auth_users
@@ -41,35 +46,29 @@ pub async fn list_users(amount: i64, pool: DPool) -> Result<Users, Error> {
         .order(created_at.desc())
         .limit(amount)
         .load::<User>(&mut est_conn(pool))
-        .unwrap_or_else(|e| {
-            eprintln!("Error querying users {:?}", e);
-            vec![]
-        });
+        .map_err(|e| {
+            log::error!("Error querying users {:?}", e);
+            ApiError::Database(e)
+        })?;
 
     Ok(Users {
-        response: users_query.into_iter().collect(),
+        response: users_query
+            .into_iter()
+            .map(|user| PublicUser::new(user, Viewer::Staff))
+            .collect(),
     })
 }
 
 #[get("/users")]
-pub async fn list(pool: DPool) -> HttpResponse {
-    let users_listed = web::block(move || list_users(50, pool))
+pub async fn list(_staff: StaffUser, pool: DPool) -> Result<HttpResponse, ApiError> {
+    let users = web::block(move || list_users(50, pool))
         .await
-        .map_err(|e| {
-            eprintln!("Failed to list users: {:?}", e);
-            HttpResponse::InternalServerError().finish()
-        })
-        .unwrap();
+        .map_err(|e| ApiError::Internal(e.to_string()))?
+        .await?;
 
-    match users_listed.await {
-        Ok(users) => HttpResponse::Ok()
-            .content_type(APPLICATION_JSON)
-            .json(users),
-        Err(e) => {
-            eprintln!("Failed to serialize users {:?}", e);
-            HttpResponse::InternalServerError().finish()
-        }
-    }
+    Ok(HttpResponse::Ok()
+        .content_type(APPLICATION_JSON)
+        .json(users))
 }
 
 // #[put("/change-password")]
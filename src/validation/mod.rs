@@ -0,0 +1,7 @@
+mod rules;
+mod validated_json;
+pub use rules::{
+    validate_birth_date, validate_iban, validate_locale, validate_password,
+    validate_phone_number_for_dial_code,
+};
+pub use validated_json::{field_errors, on_field, FieldError, ValidatedJson};
@@ -0,0 +1,227 @@
+use std::borrow::Cow;
+
+use chrono::{Datelike, NaiveDate, Utc};
+use validator::ValidationError;
+
+use crate::constants::{MAX_USER_AGE, MIN_PASSWORD_LENGTH, MIN_USER_AGE};
+use crate::i18n::{keys, Locale};
+
+// Argon2id takes any length, the cap keeps hashing a request cheap
+const MAX_PASSWORD_BYTES: usize = 128;
+// E.164 caps the full number, dial code included, at 15 digits
+const MAX_E164_DIGITS: usize = 15;
+// full_users.phone is varchar(10)
+const MAX_PHONE_DIGITS: usize = 10;
+const MIN_PHONE_DIGITS: usize = 4;
+// national number lengths for the dial codes our customers use the most
+const PHONE_NUMBER_LENGTHS: [(&str, usize, usize); 10] = [
+    ("1", 10, 10),
+    ("33", 9, 9),
+    ("44", 10, 10),
+    ("48", 9, 9),
+    ("49", 6, 10),
+    ("370", 8, 8),
+    ("371", 8, 8),
+    ("380", 9, 9),
+    ("420", 9, 9),
+    ("421", 9, 9),
+];
+
+/// The message holds the catalog key, limits are passed as params for its placeholders.
+fn error(
+    code: &'static str,
+    key: &'static str,
+    params: &[(&'static str, usize)],
+) -> ValidationError {
+    let mut error = ValidationError::new(code).with_message(Cow::from(key));
+    for (name, value) in params {
+        error.add_param(Cow::from(*name), value);
+    }
+    error
+}
+
+pub fn validate_password(password: &str) -> Result<(), ValidationError> {
+    if password.chars().count() < MIN_PASSWORD_LENGTH {
+        return Err(error(
+            "password_too_short",
+            keys::VALIDATION_PASSWORD_TOO_SHORT,
+            &[("min", MIN_PASSWORD_LENGTH)],
+        ));
+    }
+
+    if password.len() > MAX_PASSWORD_BYTES {
+        return Err(error(
+            "password_too_long",
+            keys::VALIDATION_PASSWORD_TOO_LONG,
+            &[("max", MAX_PASSWORD_BYTES)],
+        ));
+    }
+
+    let character_classes = [
+        password.chars().any(|c| c.is_lowercase()),
+        password.chars().any(|c| c.is_uppercase()),
+        password.chars().any(|c| c.is_ascii_digit()),
+        password.chars().any(|c| !c.is_alphanumeric()),
+    ]
+    .iter()
+    .filter(|present| **present)
+    .count();
+
+    if character_classes < 3 {
+        return Err(error(
+            "password_too_weak",
+            keys::VALIDATION_PASSWORD_TOO_WEAK,
+            &[],
+        ));
+    }
+
+    Ok(())
+}
+
+pub fn validate_phone_number_for_dial_code(
+    phone: &str,
+    dial_code: Option<&str>,
+) -> Result<(), ValidationError> {
+    if phone.is_empty() || !phone.chars().all(|c| c.is_ascii_digit()) {
+        return Err(error(
+            "phone_number_format",
+            keys::VALIDATION_PHONE_NUMBER_FORMAT,
+            &[],
+        ));
+    }
+
+    let digits = phone.len();
+    let (min, max) = dial_code
+        .and_then(|code| {
+            PHONE_NUMBER_LENGTHS
+                .iter()
+                .find(|(known_code, _, _)| *known_code == code)
+        })
+        .map(|(_, min, max)| (*min, *max))
+        .unwrap_or((MIN_PHONE_DIGITS, MAX_PHONE_DIGITS));
+
+    if digits < min || digits > max {
+        return Err(error(
+            "phone_number_length",
+            if min == max {
+                keys::VALIDATION_PHONE_NUMBER_LENGTH
+            } else {
+                keys::VALIDATION_PHONE_NUMBER_LENGTH_BETWEEN
+            },
+            &[("min", min), ("max", max)],
+        ));
+    }
+
+    if let Some(code) = dial_code {
+        if code.len() + digits > MAX_E164_DIGITS {
+            return Err(error(
+                "phone_number_length",
+                keys::VALIDATION_PHONE_NUMBER_TOO_LONG,
+                &[("max", MAX_E164_DIGITS)],
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+pub fn validate_iban(iban: &str) -> Result<(), ValidationError> {
+    let normalized: String = iban
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+
+    let invalid = || error("iban", keys::VALIDATION_IBAN, &[]);
+
+    // the checks below slice by byte
+    if !normalized.is_ascii() || normalized.len() < 15 || normalized.len() > 34 {
+        return Err(invalid());
+    }
+
+    let (country, rest) = normalized.split_at(2);
+    if !country.chars().all(|c| c.is_ascii_uppercase())
+        || !rest[..2].chars().all(|c| c.is_ascii_digit())
+        || !rest.chars().all(|c| c.is_ascii_alphanumeric())
+    {
+        return Err(invalid());
+    }
+
+    // ISO 13616: move the first four characters to the end, letters become 10..=35, mod 97 == 1
+    let rearranged = normalized[4..].chars().chain(normalized[..4].chars());
+    let mut remainder: u32 = 0;
+    for c in rearranged {
+        let value = c.to_digit(36).ok_or_else(invalid)?;
+        remainder = if value < 10 {
+            (remainder * 10 + value) % 97
+        } else {
+            (remainder * 100 + value) % 97
+        };
+    }
+
+    if remainder != 1 {
+        return Err(invalid());
+    }
+
+    Ok(())
+}
+
+pub fn validate_locale(locale: &str) -> Result<(), ValidationError> {
+    match Locale::from_tag(locale) {
+        Some(_) => Ok(()),
+        None => Err(error("locale", keys::VALIDATION_LOCALE, &[])),
+    }
+}
+
+pub fn validate_birth_date(birth_date: &NaiveDate) -> Result<(), ValidationError> {
+    let today = Utc::now().date_naive();
+
+    if *birth_date > today {
+        return Err(error(
+            "birth_date_in_future",
+            keys::VALIDATION_BIRTH_DATE_IN_FUTURE,
+            &[],
+        ));
+    }
+
+    let mut age = today.year() - birth_date.year();
+    if (today.month(), today.day()) < (birth_date.month(), birth_date.day()) {
+        age -= 1;
+    }
+
+    if age < MIN_USER_AGE {
+        return Err(error(
+            "birth_date_too_young",
+            keys::VALIDATION_BIRTH_DATE_TOO_YOUNG,
+            &[("min", MIN_USER_AGE as usize)],
+        ));
+    }
+
+    if age > MAX_USER_AGE {
+        return Err(error(
+            "birth_date_too_old",
+            keys::VALIDATION_BIRTH_DATE_TOO_OLD,
+            &[("max", MAX_USER_AGE as usize)],
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::validate_iban;
+
+    #[test]
+    fn iban_checksum() {
+        assert!(validate_iban("PL61 1090 1014 0000 0712 1981 2874").is_ok());
+        assert!(validate_iban("PL61 1090 1014 0000 0712 1981 2875").is_err());
+    }
+
+    #[test]
+    fn iban_with_multibyte_characters_is_invalid() {
+        assert!(validate_iban("€AAAAAAAAAAAAAAAA").is_err());
+        assert!(validate_iban("PL€1234567890123456").is_err());
+        assert!(validate_iban("PL6€1090101400000712198128").is_err());
+    }
+}
@@ -0,0 +1,150 @@
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::ops::Deref;
+use std::pin::Pin;
+
+use actix_web::dev::Payload;
+use actix_web::web::Json;
+use actix_web::{FromRequest, HttpRequest};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use validator::{Validate, ValidationError, ValidationErrors, ValidationErrorsKind};
+
+use crate::api_error::ApiError;
+use crate::i18n::{self, keys, Locale};
+
+/// Struct level errors name the field they belong to with this param.
+pub const FIELD_PARAM: &str = "field";
+
+/// Attaches a struct level error to one of the request fields.
+pub fn on_field(mut error: ValidationError, field: &'static str) -> ValidationError {
+    error.add_param(FIELD_PARAM.into(), &field);
+    error
+}
+
+/// `Json<T>` that also runs `T::validate` before the handler is called.
+pub struct ValidatedJson<T>(pub T);
+
+impl<T> Deref for ValidatedJson<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+#[derive(Serialize)]
+pub struct FieldError {
+    pub code: String,
+    pub message: String,
+}
+
+/// Rules from `rules.rs` put the catalog key in the message, derived ones use `validation_<code>`.
+fn describe(error: &ValidationError, locale: Locale) -> String {
+    let params: Vec<(&str, String)> = error
+        .params
+        .iter()
+        .map(|(name, value)| {
+            let value = value
+                .as_str()
+                .map(str::to_string)
+                .unwrap_or_else(|| value.to_string());
+            (name.as_ref(), value)
+        })
+        .collect();
+    let has = |name: &str| error.params.contains_key(name);
+
+    let key = match (error.message.as_deref(), error.code.as_ref()) {
+        (Some(key), _) => key.to_string(),
+        (None, "length") => match (has("min"), has("max")) {
+            (true, true) => keys::VALIDATION_LENGTH_BETWEEN,
+            (true, false) => keys::VALIDATION_LENGTH_MIN,
+            (false, true) => keys::VALIDATION_LENGTH_MAX,
+            (false, false) => keys::VALIDATION_LENGTH,
+        }
+        .to_string(),
+        (None, code) => format!("validation_{}", code),
+    };
+
+    let template =
+        i18n::lookup(locale, &key).unwrap_or_else(|| i18n::t(locale, keys::VALIDATION_INVALID));
+    i18n::interpolate(template, &params)
+}
+
+fn collect(
+    prefix: Option<&str>,
+    errors: &ValidationErrors,
+    locale: Locale,
+    fields: &mut BTreeMap<String, Vec<FieldError>>,
+) {
+    for (name, kind) in errors.errors() {
+        let path = match prefix {
+            Some(prefix) => format!("{}.{}", prefix, name),
+            None => name.to_string(),
+        };
+
+        match kind {
+            ValidationErrorsKind::Field(field_errors) => {
+                for error in field_errors {
+                    let field = error
+                        .params
+                        .get(FIELD_PARAM)
+                        .and_then(|value| value.as_str())
+                        .map(str::to_string)
+                        .unwrap_or_else(|| path.clone());
+
+                    fields.entry(field).or_default().push(FieldError {
+                        code: error.code.to_string(),
+                        message: describe(error, locale),
+                    });
+                }
+            }
+            ValidationErrorsKind::Struct(nested) => collect(Some(&path), nested, locale, fields),
+            ValidationErrorsKind::List(items) => {
+                for (index, nested) in items {
+                    collect(
+                        Some(&format!("{}[{}]", path, index)),
+                        nested,
+                        locale,
+                        fields,
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Flattens nested validation errors into a map keyed by field path.
+pub fn field_errors(
+    errors: &ValidationErrors,
+    locale: Locale,
+) -> BTreeMap<String, Vec<FieldError>> {
+    let mut fields = BTreeMap::new();
+    collect(None, errors, locale, &mut fields);
+    fields
+}
+
+impl<T> FromRequest for ValidatedJson<T>
+where
+    T: DeserializeOwned + Validate + 'static,
+{
+    type Error = actix_web::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let json = Json::<T>::from_request(req, payload);
+
+        Box::pin(async move {
+            let value = json
+                .await
+                .map_err(|err| {
+                    log::debug!("Invalid json payload: {}", err);
+                    ApiError::BadRequest(keys::INVALID_PAYLOAD)
+                })?
+                .into_inner();
+
+            value.validate().map_err(ApiError::Validation)?;
+            Ok(ValidatedJson(value))
+        })
+    }
+}